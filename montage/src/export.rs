@@ -0,0 +1,373 @@
+use super::graphql_client::GraphQLClientOptions;
+use chrono::{DateTime, Local, NaiveDate};
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use cynic::QueryBuilder;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+/// Write out the sessions between two dates (inclusive), for spreadsheets or backups.
+#[derive(Debug, clap::Parser)]
+pub struct Export {
+    /// The starting date. If omitted, uses today's date. Assumed to be in the local time zone.
+    from: Option<NaiveDate>,
+
+    /// The ending date. If omitted, you'll just get the starting date. Assumed to be in the local
+    /// time zone.
+    to: Option<NaiveDate>,
+
+    /// How to write the sessions
+    #[arg(long, value_enum)]
+    format: Format,
+
+    #[command(flatten)]
+    client: GraphQLClientOptions,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// One row per session. Pauses, notes, and interruptions are left out.
+    Csv,
+
+    /// A JSON array of sessions. `montage import --format json` reads it back in.
+    Json,
+
+    /// One JSON session per line. `montage import --format jsonl` reads it back in.
+    Jsonl,
+}
+
+/// A session as we export it. This is the documented export format, so add fields instead of
+/// changing the ones that are here.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExportedSession {
+    pub id: i32,
+    pub kind: String,
+    pub description: String,
+    pub start_time: DateTime<Local>,
+
+    /// Empty if the session is still going
+    pub end_time: Option<DateTime<Local>>,
+
+    pub projected_end_time: DateTime<Local>,
+
+    /// Durations are ISO 8601, like `PT25M`
+    pub duration: String,
+    pub actual_duration: String,
+    pub paused_duration: String,
+
+    /// An IANA time zone name, like `America/Chicago`
+    pub time_zone: String,
+
+    /// `completed`, `partial`, `abandoned`, or `unknown`
+    pub outcome: String,
+
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    #[serde(default)]
+    pub pauses: Vec<ExportedPause>,
+
+    #[serde(default)]
+    pub notes: Vec<ExportedNote>,
+
+    #[serde(default)]
+    pub interruptions: Vec<ExportedInterruption>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExportedPause {
+    pub start_time: DateTime<Local>,
+    pub end_time: Option<DateTime<Local>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExportedNote {
+    pub created_at: DateTime<Local>,
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExportedInterruption {
+    pub created_at: DateTime<Local>,
+    pub source: String,
+    pub note: Option<String>,
+}
+
+impl From<montage_client::export::Session> for ExportedSession {
+    fn from(session: montage_client::export::Session) -> Self {
+        Self {
+            id: session.id,
+            kind: session.kind,
+            description: session.description,
+            start_time: session.start_time,
+            end_time: session.end_time,
+            projected_end_time: session.projected_end_time,
+            duration: format_duration(session.duration),
+            actual_duration: format_duration(session.actual_duration),
+            paused_duration: format_duration(session.paused_duration),
+            time_zone: session.time_zone.0,
+            outcome: format_outcome(session.outcome).to_string(),
+            tags: session.tags,
+            pauses: session
+                .pauses
+                .into_iter()
+                .map(|pause| ExportedPause {
+                    start_time: pause.start_time,
+                    end_time: pause.end_time,
+                })
+                .collect(),
+            notes: session
+                .notes
+                .into_iter()
+                .map(|note| ExportedNote {
+                    created_at: note.created_at,
+                    text: note.text,
+                })
+                .collect(),
+            interruptions: session
+                .interruptions
+                .into_iter()
+                .map(|interruption| ExportedInterruption {
+                    created_at: interruption.created_at,
+                    source: interruption.source,
+                    note: interruption.note,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The name of an outcome, as `montage import` reads it.
+fn format_outcome(outcome: montage_client::export::Outcome) -> &'static str {
+    use montage_client::export::Outcome;
+
+    match outcome {
+        Outcome::Completed => "completed",
+        Outcome::Partial => "partial",
+        Outcome::Abandoned => "abandoned",
+        Outcome::Unknown => "unknown",
+    }
+}
+
+/// Write a duration as ISO 8601 seconds, like `PT90S` or `PT1.054S`. (`iso8601::Duration`'s own
+/// `Display` drops leading zeros from the milliseconds.)
+fn format_duration(duration: iso8601::Duration) -> String {
+    let duration = std::time::Duration::from(duration);
+
+    match duration.subsec_millis() {
+        0 => format!("PT{}S", duration.as_secs()),
+        millis => format!("PT{}.{:03}S", duration.as_secs(), millis),
+    }
+}
+
+/// The columns of a CSV export, in order. These are a superset of what `montage import --format
+/// csv` reads, so exports can be imported again.
+#[derive(Serialize, Debug)]
+struct CsvSession<'a> {
+    id: i32,
+    kind: &'a str,
+    description: &'a str,
+    start_time: String,
+    end_time: Option<String>,
+    projected_end_time: String,
+    duration: &'a str,
+    actual_duration: &'a str,
+    paused_duration: &'a str,
+    time_zone: &'a str,
+    outcome: &'a str,
+    tags: String,
+}
+
+impl<'a> From<&'a ExportedSession> for CsvSession<'a> {
+    fn from(session: &'a ExportedSession) -> Self {
+        Self {
+            id: session.id,
+            kind: &session.kind,
+            description: &session.description,
+            start_time: session.start_time.to_rfc3339(),
+            end_time: session.end_time.map(|time| time.to_rfc3339()),
+            projected_end_time: session.projected_end_time.to_rfc3339(),
+            duration: &session.duration,
+            actual_duration: &session.actual_duration,
+            paused_duration: &session.paused_duration,
+            time_zone: &session.time_zone,
+            outcome: &session.outcome,
+            tags: session.tags.join(";"),
+        }
+    }
+}
+
+impl Export {
+    pub async fn run(&self) -> Result<()> {
+        let from =
+            super::Opts::on_local_date(self.from.unwrap_or_else(|| Local::now().date_naive()))?;
+
+        let to = match self.to {
+            Some(date) => super::Opts::on_local_date(date)?,
+            None => from,
+        };
+
+        let query = montage_client::export::ExportQuery::build(
+            montage_client::export::ExportQueryVariables {
+                start: from,
+                end: to,
+                time_zone: super::Opts::local_time_zone().map(montage_client::export::TimeZone),
+            },
+        );
+
+        let sessions: Vec<ExportedSession> = self
+            .client
+            .make_graphql_request(query)
+            .await?
+            .data
+            .ok_or(eyre!("data was null"))?
+            .report
+            .sessions
+            .into_iter()
+            .map(ExportedSession::from)
+            .collect();
+
+        write(self.format, &sessions, std::io::stdout().lock())
+    }
+}
+
+fn write(format: Format, sessions: &[ExportedSession], mut out: impl Write) -> Result<()> {
+    match format {
+        Format::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+
+            for session in sessions {
+                writer
+                    .serialize(CsvSession::from(session))
+                    .wrap_err("could not write CSV")?;
+            }
+
+            writer.flush().wrap_err("could not write CSV")?;
+        }
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut out, sessions).wrap_err("could not write JSON")?;
+            writeln!(out)?;
+        }
+        Format::Jsonl => {
+            for session in sessions {
+                serde_json::to_writer(&mut out, session).wrap_err("could not write JSON")?;
+                writeln!(out)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn session() -> ExportedSession {
+        let start_time = Local.with_ymd_and_hms(2023, 10, 18, 9, 0, 0).unwrap();
+
+        ExportedSession {
+            id: 1,
+            kind: String::from("task"),
+            description: String::from("Write docs, finally"),
+            start_time,
+            end_time: Some(start_time + chrono::Duration::minutes(30)),
+            projected_end_time: start_time + chrono::Duration::minutes(30),
+            duration: String::from("PT25M"),
+            actual_duration: String::from("PT25M"),
+            paused_duration: String::from("PT5M"),
+            time_zone: String::from("America/Chicago"),
+            outcome: String::from("completed"),
+            tags: vec![String::from("docs"), String::from("writing")],
+            pauses: vec![ExportedPause {
+                start_time: start_time + chrono::Duration::minutes(10),
+                end_time: Some(start_time + chrono::Duration::minutes(15)),
+            }],
+            notes: vec![ExportedNote {
+                created_at: start_time + chrono::Duration::minutes(29),
+                text: String::from("done"),
+            }],
+            interruptions: Vec::new(),
+        }
+    }
+
+    fn written(format: Format, sessions: &[ExportedSession]) -> String {
+        let mut out = Vec::new();
+        write(format, sessions, &mut out).unwrap();
+
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn csv_has_a_stable_header() {
+        let csv = written(Format::Csv, &[session()]);
+
+        assert_eq!(
+            csv.lines().next().unwrap(),
+            "id,kind,description,start_time,end_time,projected_end_time,duration,actual_duration,paused_duration,time_zone,outcome,tags"
+        );
+        assert!(csv
+            .lines()
+            .nth(1)
+            .unwrap()
+            .ends_with(",PT25M,PT25M,PT5M,America/Chicago,completed,docs;writing"));
+    }
+
+    #[test]
+    fn durations_keep_their_milliseconds() {
+        assert_eq!(
+            format_duration(iso8601::duration("PT1.054S").unwrap()),
+            "PT1.054S"
+        );
+        assert_eq!(
+            format_duration(iso8601::duration("PT25M").unwrap()),
+            "PT1500S"
+        );
+        assert_eq!(
+            std::time::Duration::from(
+                iso8601::duration(&format_duration(iso8601::duration("PT1.054S").unwrap()))
+                    .unwrap()
+            ),
+            std::time::Duration::from_millis(1054)
+        );
+    }
+
+    #[test]
+    fn outcomes_import_again() {
+        use montage_client::export::Outcome as Exported;
+        use montage_client::import_sessions::Outcome as Imported;
+
+        for (exported, imported) in [
+            (Exported::Completed, Imported::Completed),
+            (Exported::Partial, Imported::Partial),
+            (Exported::Abandoned, Imported::Abandoned),
+            (Exported::Unknown, Imported::Unknown),
+        ] {
+            assert_eq!(
+                crate::import::parse_outcome(format_outcome(exported)).unwrap(),
+                imported
+            );
+        }
+    }
+
+    #[test]
+    fn jsonl_writes_a_session_per_line() {
+        let jsonl = written(Format::Jsonl, &[session(), session()]);
+
+        assert_eq!(jsonl.lines().count(), 2);
+        assert_eq!(
+            serde_json::from_str::<ExportedSession>(jsonl.lines().next().unwrap()).unwrap(),
+            session()
+        );
+    }
+
+    #[test]
+    fn json_reads_back_in() {
+        let json = written(Format::Json, &[session()]);
+
+        assert_eq!(
+            serde_json::from_str::<Vec<ExportedSession>>(&json).unwrap(),
+            vec![session()]
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use super::graphql_client::GraphQLClientOptions;
+use super::KindOptions;
+use chrono::Duration;
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use cynic::{MutationBuilder, QueryBuilder};
+use montage_client::goals::{GoalBound, GoalPeriod, GoalProgress};
+
+#[derive(Debug, clap::Parser)]
+pub struct Goals {
+    #[command(subcommand)]
+    command: GoalsCommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum GoalsCommand {
+    /// Set a goal for time spent on a kind of session (tasks unless otherwise specified) or on a
+    /// tag
+    Add {
+        /// How often the goal starts over
+        #[arg(long, value_enum, default_value = "day")]
+        per: Period,
+
+        /// Spend at least this many minutes
+        #[arg(long, conflicts_with = "at_most", required_unless_present = "at_most")]
+        at_least: Option<usize>,
+
+        /// Spend no more than this many minutes
+        #[arg(
+            long,
+            conflicts_with = "at_least",
+            required_unless_present = "at_least"
+        )]
+        at_most: Option<usize>,
+
+        /// Which kind of session to count
+        #[command(flatten)]
+        kind: KindOptions,
+
+        /// Count time spent in sessions with this tag instead of a kind of session
+        #[arg(long, conflicts_with_all = ["kind", "is_task", "is_meeting", "is_break", "is_offline"])]
+        tag: Option<String>,
+
+        #[command(flatten)]
+        client: GraphQLClientOptions,
+    },
+
+    /// Show the goals and how they're going today and this week
+    List(GraphQLClientOptions),
+
+    /// Stop tracking a goal
+    Remove {
+        /// The ID of the goal (shown in `montage goals list`)
+        id: i32,
+
+        #[command(flatten)]
+        client: GraphQLClientOptions,
+    },
+}
+
+/// How often a goal starts over
+#[derive(clap::ValueEnum, Debug, Clone, Copy)]
+enum Period {
+    /// Every day
+    Day,
+
+    /// Every week, starting on Monday
+    Week,
+}
+
+impl Goals {
+    pub async fn run(&self) -> Result<()> {
+        match &self.command {
+            GoalsCommand::Add {
+                per,
+                at_least,
+                at_most,
+                kind,
+                tag,
+                client,
+            } => {
+                use montage_client::create_goal;
+
+                let kind = if tag.is_some() {
+                    None
+                } else {
+                    Some(kind.name().unwrap_or("task"))
+                };
+
+                let (minutes, bound) = match (at_least, at_most) {
+                    (Some(minutes), _) => (minutes, create_goal::GoalBound::Min),
+                    (None, Some(minutes)) => (minutes, create_goal::GoalBound::Max),
+                    (None, None) => {
+                        return Err(eyre!(
+                            "got neither --at-least nor --at-most. Configuration error in montage!"
+                        ))
+                    }
+                };
+
+                let query = create_goal::CreateGoalMutation::build(
+                    create_goal::CreateGoalMutationVariables {
+                        kind,
+                        tag: tag.as_deref(),
+                        period: match per {
+                            Period::Day => create_goal::GoalPeriod::Day,
+                            Period::Week => create_goal::GoalPeriod::Week,
+                        },
+                        target: iso8601::duration(&format!("PT{}M", minutes))
+                            .map_err(|err| eyre!("could not parse duration: {err}"))?,
+                        bound,
+                    },
+                );
+
+                let goal = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .ok_or(eyre!("data was null"))?
+                    .create_goal;
+
+                println!("Added goal #{}", goal.id);
+            }
+            GoalsCommand::List(client) => {
+                let query = montage_client::goals::GoalsQuery::build(
+                    montage_client::goals::GoalsQueryVariables {
+                        time_zone: super::Opts::local_time_zone()
+                            .map(montage_client::goals::TimeZone),
+                    },
+                );
+
+                let goals = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .ok_or(eyre!("data was null"))?
+                    .goals;
+
+                if goals.is_empty() {
+                    println!("No goals set");
+                }
+
+                for progress in goals {
+                    println!("{}", Self::format(&progress)?);
+                }
+            }
+            GoalsCommand::Remove { id, client } => {
+                let query = montage_client::delete_goal::DeleteGoalMutation::build(
+                    montage_client::delete_goal::DeleteGoalMutationVariables { id: *id },
+                );
+
+                let removed = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .ok_or(eyre!("data was null"))?
+                    .delete_goal;
+
+                println!("Removed goal #{}", removed.id);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn format(progress: &GoalProgress) -> Result<String> {
+        let minutes = |duration: &iso8601::Duration| -> Result<i64> {
+            Ok(Duration::from_std(std::time::Duration::from(*duration))
+                .wrap_err("could not parse duration")?
+                .num_minutes())
+        };
+
+        let goal = &progress.goal;
+
+        let counted = match (&goal.kind, &goal.tag) {
+            (_, Some(tag)) => format!("tagged {tag}"),
+            (Some(kind), None) => format!("in {kind} sessions"),
+            (None, None) => String::from("on anything"),
+        };
+
+        Ok(format!(
+            "#{}: {} {} minutes {} per {} ({} so far, {})",
+            goal.id,
+            match goal.bound {
+                GoalBound::Min => "at least",
+                GoalBound::Max => "at most",
+            },
+            minutes(&goal.target)?,
+            counted,
+            match goal.period {
+                GoalPeriod::Day => "day",
+                GoalPeriod::Week => "week",
+            },
+            minutes(&progress.actual)?,
+            if progress.met { "met" } else { "not met" },
+        ))
+    }
+}
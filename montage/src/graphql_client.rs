@@ -1,11 +1,19 @@
+use async_tungstenite::tokio::{client_async_tls, ClientStream};
 use async_tungstenite::tungstenite::{
     client::IntoClientRequest, handshake::client::Request, http::HeaderValue,
 };
+use async_tungstenite::WebSocketStream;
 use clap::Parser;
-use color_eyre::eyre::{Result, WrapErr};
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
 use cynic::http::ReqwestExt;
 use cynic::{GraphQlResponse, Operation};
 use serde::{de::DeserializeOwned, Serialize};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::str::FromStr;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpStream, UnixStream};
 
 pub static DEFAULT_ADDR: &str = "127.0.0.1";
 
@@ -23,11 +31,36 @@ pub struct GraphQLClientOptions {
     /// The port to bind to
     #[arg(long, default_value = DEFAULT_PORT, env = "MONTAGE_PORT")]
     server_port: u16,
+
+    /// Where the server is, if it isn't plain HTTP on the address and port above. Takes
+    /// `https://host:port` for servers using TLS or `unix:///path/to/socket` for servers
+    /// listening on a Unix socket.
+    #[arg(long, env = "MONTAGE_URL")]
+    server_url: Option<ServerUrl>,
+
+    /// A token to authenticate with, if the server requires one (see `montage token`)
+    #[arg(long, env = "MONTAGE_TOKEN", hide_env_values = true)]
+    token: Option<String>,
 }
 
 impl GraphQLClientOptions {
     pub fn endpoint(&self) -> String {
-        format!("http://{}:{}/graphql", self.server_addr, self.server_port)
+        match &self.server_url {
+            None => format!("http://{}:{}/graphql", self.server_addr, self.server_port),
+            Some(ServerUrl::Http(url)) => format!("{}/graphql", url.as_str().trim_end_matches('/')),
+            // the host doesn't matter over a socket, but HTTP needs one anyway
+            Some(ServerUrl::Unix(_)) => "http://localhost/graphql".to_string(),
+        }
+    }
+
+    /// Start a request to the GraphQL endpoint, with our token if we have one.
+    pub fn post(&self, client: &reqwest::Client) -> reqwest::RequestBuilder {
+        let request = client.post(self.endpoint());
+
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
     }
 
     pub async fn make_graphql_request<ResponseData, Vars>(
@@ -38,17 +71,94 @@ impl GraphQLClientOptions {
         Vars: Serialize,
         ResponseData: DeserializeOwned + 'static,
     {
-        let client = reqwest::Client::new();
+        let resp = match &self.server_url {
+            Some(ServerUrl::Unix(path)) => self
+                .post_unix(path, query)
+                .await
+                .wrap_err("GraphQL request failed")?,
+            _ => self
+                .post(&reqwest::Client::new())
+                .run_graphql(query)
+                .await
+                .wrap_err("GraphQL request failed")?,
+        };
 
-        client
-            .post(self.endpoint())
-            .run_graphql(query)
+        // if the server couldn't give us anything, its errors are the most useful thing we can
+        // show (for example "there is no current session".)
+        if let (None, Some(errors)) = (&resp.data, &resp.errors) {
+            let messages: Vec<&str> = errors.iter().map(|error| error.message.as_str()).collect();
+            bail!("{}", messages.join(", "));
+        }
+
+        Ok(resp)
+    }
+
+    /// Make a GraphQL request over a Unix socket. reqwest can't do this, so we speak HTTP over
+    /// the socket ourselves.
+    async fn post_unix<ResponseData, Vars>(
+        &self,
+        path: &Path,
+        query: Operation<ResponseData, Vars>,
+    ) -> Result<GraphQlResponse<ResponseData>>
+    where
+        Vars: Serialize,
+        ResponseData: DeserializeOwned + 'static,
+    {
+        let stream = UnixStream::connect(path)
             .await
-            .wrap_err("GraphQL request failed")
+            .wrap_err_with(|| format!("could not connect to {}", path.display()))?;
+
+        let (mut sender, connection) = hyper::client::conn::handshake(stream)
+            .await
+            .wrap_err("could not start HTTP connection")?;
+
+        tokio::spawn(async move {
+            if let Err(err) = connection.await {
+                tracing::warn!(err = ?err, "HTTP connection failed");
+            }
+        });
+
+        let mut request = hyper::Request::post("/graphql")
+            .header(hyper::header::HOST, "localhost")
+            .header(hyper::header::CONTENT_TYPE, "application/json");
+
+        if let Some(token) = &self.token {
+            request = request.header(hyper::header::AUTHORIZATION, format!("Bearer {token}"));
+        }
+
+        let response = sender
+            .send_request(request.body(hyper::Body::from(serde_json::to_vec(&query)?))?)
+            .await
+            .wrap_err("could not send request")?;
+
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .wrap_err("could not read response")?;
+
+        // like cynic does for reqwest, errors are fine as long as they come as GraphQL errors
+        serde_json::from_slice(&body).map_err(|_| {
+            eyre!(
+                "Server returned {}: {}",
+                status,
+                String::from_utf8_lossy(&body)
+            )
+        })
     }
 
     pub fn ws_endpoint(&self) -> String {
-        format!("ws://{}:{}", self.server_addr, self.server_port)
+        match &self.server_url {
+            None => format!("ws://{}:{}", self.server_addr, self.server_port),
+            Some(ServerUrl::Http(url)) => {
+                let base = url.as_str().trim_end_matches('/');
+
+                match url.scheme() {
+                    "https" => base.replacen("https", "wss", 1),
+                    _ => base.replacen("http", "ws", 1),
+                }
+            }
+            Some(ServerUrl::Unix(_)) => "ws://localhost".to_string(),
+        }
     }
 
     pub fn request(&self) -> Result<Request> {
@@ -64,4 +174,177 @@ impl GraphQLClientOptions {
 
         Ok(request)
     }
+
+    /// Open a websocket to the server for subscriptions, over TLS or a Unix socket if that's
+    /// what we've been told to use.
+    pub async fn connect_websocket(&self) -> Result<WebSocketStream<ClientStream<Connection>>> {
+        let request = self.request()?;
+
+        let connection = match &self.server_url {
+            Some(ServerUrl::Unix(path)) => Connection::Unix(
+                UnixStream::connect(path)
+                    .await
+                    .wrap_err_with(|| format!("could not connect to {}", path.display()))?,
+            ),
+            _ => {
+                let uri = request.uri();
+                let host = uri
+                    .host()
+                    .ok_or_else(|| eyre!("{} has no host", uri))?
+                    .trim_start_matches('[')
+                    .trim_end_matches(']')
+                    .to_string();
+                let port = uri.port_u16().unwrap_or(match uri.scheme_str() {
+                    Some("wss") => 443,
+                    _ => 80,
+                });
+
+                Connection::Tcp(
+                    TcpStream::connect((host.as_str(), port))
+                        .await
+                        .wrap_err_with(|| format!("could not connect to {}:{}", host, port))?,
+                )
+            }
+        };
+
+        let (stream, _) = client_async_tls(request, connection)
+            .await
+            .wrap_err("could not start websocket")?;
+
+        Ok(stream)
+    }
+
+    /// What to send when starting a websocket connection. The server looks for our token here,
+    /// since subscriptions don't get the headers that regular requests do.
+    pub fn connection_init_payload(&self) -> serde_json::Value {
+        match &self.token {
+            Some(token) => serde_json::json!({ "authorization": format!("Bearer {token}") }),
+            None => serde_json::json!({}),
+        }
+    }
+}
+
+/// Where to find the server, when it's not plain HTTP over TCP.
+#[derive(Debug, Clone)]
+pub enum ServerUrl {
+    Http(reqwest::Url),
+    Unix(PathBuf),
+}
+
+impl FromStr for ServerUrl {
+    type Err = color_eyre::eyre::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some(path) = s.strip_prefix("unix://") {
+            return Ok(ServerUrl::Unix(PathBuf::from(path)));
+        }
+
+        let mut url = reqwest::Url::parse(s).wrap_err("could not parse server URL")?;
+
+        // we figure out the websocket URL from the HTTP one, but accept either
+        let scheme = match url.scheme() {
+            "http" | "ws" => "http",
+            "https" | "wss" => "https",
+            other => bail!("can't connect to a server over {}", other),
+        };
+        url.set_scheme(scheme)
+            .map_err(|_| eyre!("could not use {} for {}", scheme, s))?;
+
+        Ok(ServerUrl::Http(url))
+    }
+}
+
+/// A connection to the server, over whichever kind of socket it's listening on.
+pub enum Connection {
+    Tcp(TcpStream),
+    Unix(UnixStream),
+}
+
+impl AsyncRead for Connection {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            Connection::Tcp(stream) => Pin::new(stream).poll_read(cx, buf),
+            Connection::Unix(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for Connection {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        match self.get_mut() {
+            Connection::Tcp(stream) => Pin::new(stream).poll_write(cx, buf),
+            Connection::Unix(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            Connection::Tcp(stream) => Pin::new(stream).poll_flush(cx),
+            Connection::Unix(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            Connection::Tcp(stream) => Pin::new(stream).poll_shutdown(cx),
+            Connection::Unix(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(args: &[&str]) -> GraphQLClientOptions {
+        GraphQLClientOptions::parse_from(std::iter::once("montage").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn endpoints_default_to_address_and_port() {
+        let options = options(&["--server-addr", "10.0.0.2", "--server-port", "1234"]);
+
+        assert_eq!(options.endpoint(), "http://10.0.0.2:1234/graphql");
+        assert_eq!(options.ws_endpoint(), "ws://10.0.0.2:1234");
+    }
+
+    #[test]
+    fn endpoints_follow_https_urls() {
+        let options = options(&["--server-url", "https://montage.example.com:4774/"]);
+
+        assert_eq!(
+            options.endpoint(),
+            "https://montage.example.com:4774/graphql"
+        );
+        assert_eq!(options.ws_endpoint(), "wss://montage.example.com:4774");
+    }
+
+    #[test]
+    fn endpoints_accept_websocket_urls() {
+        let options = options(&["--server-url", "wss://montage.example.com"]);
+
+        assert_eq!(options.endpoint(), "https://montage.example.com/graphql");
+        assert_eq!(options.ws_endpoint(), "wss://montage.example.com");
+    }
+
+    #[test]
+    fn unix_urls_are_paths() {
+        match "unix:///tmp/montage.sock".parse::<ServerUrl>().unwrap() {
+            ServerUrl::Unix(path) => assert_eq!(path, PathBuf::from("/tmp/montage.sock")),
+            other => panic!("expected a Unix socket, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn other_schemes_are_rejected() {
+        assert!("ftp://montage.example.com".parse::<ServerUrl>().is_err());
+    }
 }
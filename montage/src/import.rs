@@ -0,0 +1,840 @@
+use super::export::ExportedSession;
+use super::graphql_client::GraphQLClientOptions;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+use cynic::MutationBuilder;
+use montage_client::import_sessions::{
+    ImportedInterruption, ImportedNote, ImportedPause, ImportedSession, OnOverlap, Outcome,
+};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::PathBuf;
+
+/// Bring in sessions tracked somewhere else. Nothing is imported unless every session can be.
+#[derive(Debug, clap::Parser)]
+pub struct Import {
+    /// The file to import
+    file: PathBuf,
+
+    /// What wrote the file
+    #[arg(long, value_enum)]
+    format: Format,
+
+    /// Use a montage kind for sessions with this kind (in CSV and JSON), project (in Toggl), or tag (in
+    /// Timewarrior), like `--kind-map Meetings=meeting`. Can be given more than once.
+    #[arg(long("kind-map"), value_parser = parse_kind_mapping)]
+    kind_map: Vec<(String, String)>,
+
+    /// The kind to use for sessions that don't match anything in `--kind-map`
+    #[arg(long, default_value = "task")]
+    default_kind: String,
+
+    /// What to do with sessions that overlap ones you already have
+    #[arg(long, value_enum, default_value = "reject")]
+    on_overlap: Overlap,
+
+    /// Show what would be imported without saving anything
+    #[arg(long)]
+    dry_run: bool,
+
+    #[command(flatten)]
+    client: GraphQLClientOptions,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Montage's own CSV, with `description`, `start_time`, and `end_time` columns (plus optional
+    /// `kind`, `duration`, `time_zone`, `outcome`, and `tags` separated by semicolons.) Times are
+    /// RFC 3339.
+    Csv,
+
+    /// A Toggl Track detailed report, exported as CSV
+    Toggl,
+
+    /// The output of `timew export`
+    Timewarrior,
+
+    /// The output of `montage export --format json`
+    Json,
+
+    /// The output of `montage export --format jsonl`
+    Jsonl,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy)]
+enum Overlap {
+    /// Leave out overlapping sessions and import the rest
+    Skip,
+
+    /// Don't import anything if any sessions overlap
+    Reject,
+}
+
+impl From<Overlap> for OnOverlap {
+    fn from(overlap: Overlap) -> Self {
+        match overlap {
+            Overlap::Skip => OnOverlap::Skip,
+            Overlap::Reject => OnOverlap::Reject,
+        }
+    }
+}
+
+fn parse_kind_mapping(mapping: &str) -> std::result::Result<(String, String), String> {
+    match mapping.split_once('=') {
+        Some((from, kind)) if !from.trim().is_empty() && !kind.trim().is_empty() => {
+            Ok((from.trim().to_string(), kind.trim().to_string()))
+        }
+        _ => Err(format!("expected FROM=KIND, but got \"{mapping}\"")),
+    }
+}
+
+impl Import {
+    pub async fn run(&self) -> Result<()> {
+        let file = std::fs::File::open(&self.file)
+            .wrap_err_with(|| format!("could not open {}", self.file.display()))?;
+
+        let kinds = KindMap {
+            mappings: self.kind_map.iter().cloned().collect(),
+            default: self.default_kind.clone(),
+        };
+        let time_zone =
+            super::Opts::local_time_zone().map(montage_client::import_sessions::TimeZone);
+
+        let Parsed { mut sessions, open } = parse(self.format, file, &kinds)?;
+        for session in &mut sessions {
+            if session.time_zone.is_none() {
+                session.time_zone = time_zone.clone();
+            }
+        }
+
+        let query = montage_client::import_sessions::ImportSessionsMutation::build(
+            montage_client::import_sessions::ImportSessionsMutationVariables {
+                sessions,
+                on_overlap: self.on_overlap.into(),
+                dry_run: self.dry_run,
+            },
+        );
+
+        let result = self
+            .client
+            .make_graphql_request(query)
+            .await?
+            .data
+            .ok_or(eyre!("data was null"))?
+            .import_sessions;
+
+        println!(
+            "{} {} session{}",
+            if self.dry_run {
+                "Would import"
+            } else {
+                "Imported"
+            },
+            result.created.len(),
+            if result.created.len() == 1 { "" } else { "s" },
+        );
+
+        for session in &result.created {
+            println!(
+                "  {} \"{}\" ({} to {}){}",
+                session.kind,
+                session.description,
+                super::Opts::humanize_time_12hr(session.start_time),
+                session
+                    .end_time
+                    .map(super::Opts::humanize_time_12hr)
+                    .unwrap_or_default(),
+                if session.tags.is_empty() {
+                    String::new()
+                } else {
+                    format!(" tagged {}", session.tags.join(", "))
+                },
+            );
+        }
+
+        if !result.skipped.is_empty() {
+            println!(
+                "{} {} overlapping session{}",
+                if self.dry_run {
+                    "Would skip"
+                } else {
+                    "Skipped"
+                },
+                result.skipped.len(),
+                if result.skipped.len() == 1 { "" } else { "s" },
+            );
+        }
+
+        for skipped in &result.skipped {
+            println!(
+                "  row {}: \"{}\" (started at {}) overlaps #{}",
+                skipped.row,
+                skipped.description,
+                super::Opts::humanize_time_12hr(skipped.start_time),
+                skipped.overlaps,
+            );
+        }
+
+        if !open.is_empty() {
+            println!(
+                "{} {} session{} that hadn't ended",
+                if self.dry_run {
+                    "Would leave out"
+                } else {
+                    "Left out"
+                },
+                open.len(),
+                if open.len() == 1 { "" } else { "s" },
+            );
+        }
+
+        for row in &open {
+            println!("  row {}: \"{}\"", row.row, row.description);
+        }
+
+        Ok(())
+    }
+}
+
+/// How to turn the categories other tools use into montage kinds
+struct KindMap {
+    mappings: HashMap<String, String>,
+    default: String,
+}
+
+impl KindMap {
+    /// The kind to use for something explicitly mapped, if it is
+    fn mapped(&self, from: &str) -> Option<String> {
+        self.mappings.get(from.trim()).cloned()
+    }
+
+    /// The kind to use for a category: its mapping if there is one, or the default.
+    fn kind_for(&self, from: &str) -> String {
+        self.mapped(from).unwrap_or_else(|| self.default.clone())
+    }
+}
+
+/// What we got out of a file: the sessions to import, and the rows we left out because their
+/// sessions hadn't ended (only finished sessions can be imported.)
+#[derive(Debug, Default)]
+struct Parsed {
+    sessions: Vec<ImportedSession>,
+    open: Vec<OpenRow>,
+}
+
+/// A row left out of an import because its session hadn't ended
+#[derive(Debug, PartialEq, Eq)]
+struct OpenRow {
+    /// Where it was in the file, starting at 1
+    row: usize,
+
+    description: String,
+}
+
+fn parse(format: Format, reader: impl Read, kinds: &KindMap) -> Result<Parsed> {
+    match format {
+        Format::Csv => parse_csv(reader, kinds),
+        Format::Toggl => Ok(Parsed {
+            sessions: parse_toggl(reader, kinds)?,
+            open: Vec::new(),
+        }),
+        Format::Timewarrior => parse_timewarrior(reader, kinds),
+        Format::Json => parse_json(reader, kinds),
+        Format::Jsonl => parse_jsonl(reader, kinds),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct CsvRow {
+    description: String,
+    start_time: String,
+    end_time: Option<String>,
+    kind: Option<String>,
+    duration: Option<String>,
+    time_zone: Option<String>,
+    outcome: Option<String>,
+    tags: Option<String>,
+}
+
+impl CsvRow {
+    /// Kinds are taken as they are unless they're mapped to something else, and rows without one
+    /// get the default kind.
+    fn into_session(self, end_time: &str, kinds: &KindMap) -> Result<ImportedSession> {
+        let kind = match non_empty(self.kind) {
+            Some(kind) => kinds.mapped(&kind).unwrap_or(kind),
+            None => kinds.default.clone(),
+        };
+
+        Ok(ImportedSession {
+            kind,
+            description: self.description,
+            start_time: parse_rfc3339(&self.start_time)?,
+            end_time: parse_rfc3339(end_time)?,
+            duration: non_empty(self.duration)
+                .map(|duration| iso8601::duration(&duration))
+                .transpose()
+                .map_err(|err| eyre!("could not parse duration: {err}"))?,
+            time_zone: non_empty(self.time_zone).map(montage_client::import_sessions::TimeZone),
+            outcome: non_empty(self.outcome)
+                .map(|outcome| parse_outcome(&outcome))
+                .transpose()?,
+            tags: split_tags(self.tags.as_deref().unwrap_or_default(), ';'),
+            pauses: Vec::new(),
+            notes: Vec::new(),
+            interruptions: Vec::new(),
+        })
+    }
+}
+
+/// Montage's own CSV format. Rows without an end time (like sessions that were still going when
+/// they were exported) are left out, since only finished sessions can be imported.
+fn parse_csv(reader: impl Read, kinds: &KindMap) -> Result<Parsed> {
+    let mut parsed = Parsed::default();
+
+    for (index, row) in csv::Reader::from_reader(reader).deserialize().enumerate() {
+        let row: CsvRow = row.wrap_err_with(|| format!("could not read row {}", index + 1))?;
+
+        let end_time = match non_empty(row.end_time.clone()) {
+            Some(end_time) => end_time,
+            None => {
+                parsed.open.push(OpenRow {
+                    row: index + 1,
+                    description: row.description,
+                });
+                continue;
+            }
+        };
+
+        parsed.sessions.push(
+            row.into_session(&end_time, kinds)
+                .wrap_err_with(|| format!("could not import row {}", index + 1))?,
+        );
+    }
+
+    Ok(parsed)
+}
+
+#[derive(serde::Deserialize)]
+struct TogglRow {
+    #[serde(rename = "Description")]
+    description: String,
+
+    #[serde(rename = "Project", default)]
+    project: String,
+
+    #[serde(rename = "Start date")]
+    start_date: String,
+
+    #[serde(rename = "Start time")]
+    start_time: String,
+
+    #[serde(rename = "End date")]
+    end_date: String,
+
+    #[serde(rename = "End time")]
+    end_time: String,
+
+    #[serde(rename = "Tags", default)]
+    tags: String,
+}
+
+impl TogglRow {
+    /// Toggl writes times in the exporter's local time zone, so we read them in ours. Projects
+    /// become kinds through the kind map.
+    fn into_session(self, kinds: &KindMap) -> Result<ImportedSession> {
+        Ok(ImportedSession {
+            kind: kinds.kind_for(&self.project),
+            start_time: parse_local(&self.start_date, &self.start_time)?,
+            end_time: parse_local(&self.end_date, &self.end_time)?,
+            description: if self.description.trim().is_empty() {
+                self.project
+            } else {
+                self.description
+            },
+            duration: None,
+            time_zone: None,
+            outcome: None,
+            tags: split_tags(&self.tags, ','),
+            pauses: Vec::new(),
+            notes: Vec::new(),
+            interruptions: Vec::new(),
+        })
+    }
+}
+
+/// A Toggl Track detailed report
+fn parse_toggl(reader: impl Read, kinds: &KindMap) -> Result<Vec<ImportedSession>> {
+    csv::Reader::from_reader(reader)
+        .deserialize()
+        .enumerate()
+        .map(|(index, row)| {
+            row.map_err(|err| eyre!(err))
+                .and_then(|row: TogglRow| row.into_session(kinds))
+                .wrap_err_with(|| format!("could not import row {}", index + 1))
+        })
+        .collect()
+}
+
+#[derive(serde::Deserialize)]
+struct TimewarriorInterval {
+    start: String,
+    end: Option<String>,
+
+    #[serde(default)]
+    tags: Vec<String>,
+
+    annotation: Option<String>,
+}
+
+impl TimewarriorInterval {
+    fn description(&self) -> Option<String> {
+        match non_empty(self.annotation.clone()) {
+            Some(annotation) => Some(annotation),
+            None if !self.tags.is_empty() => Some(self.tags.join(", ")),
+            None => None,
+        }
+    }
+
+    /// The first tag with a kind mapping picks the kind (and the rest stay tags.) Without an
+    /// annotation, the tags describe the session.
+    fn into_session(self, end: &str, kinds: &KindMap) -> Result<ImportedSession> {
+        let description = match self.description() {
+            Some(description) => description,
+            None => bail!("interval has neither an annotation nor tags to describe it"),
+        };
+
+        let mut tags = self.tags;
+        let kind = match tags.iter().position(|tag| kinds.mapped(tag).is_some()) {
+            Some(position) => kinds.kind_for(&tags.remove(position)),
+            None => kinds.default.clone(),
+        };
+
+        Ok(ImportedSession {
+            kind,
+            description,
+            start_time: parse_timewarrior_time(&self.start)?,
+            end_time: parse_timewarrior_time(end)?,
+            duration: None,
+            time_zone: None,
+            outcome: None,
+            tags,
+            pauses: Vec::new(),
+            notes: Vec::new(),
+            interruptions: Vec::new(),
+        })
+    }
+}
+
+/// The JSON from `timew export`. Intervals that are still open are left out, since only finished
+/// sessions can be imported.
+fn parse_timewarrior(reader: impl Read, kinds: &KindMap) -> Result<Parsed> {
+    let intervals: Vec<TimewarriorInterval> =
+        serde_json::from_reader(reader).wrap_err("could not read Timewarrior export")?;
+
+    let mut parsed = Parsed::default();
+
+    for (index, mut interval) in intervals.into_iter().enumerate() {
+        let end = match interval.end.take() {
+            Some(end) => end,
+            None => {
+                parsed.open.push(OpenRow {
+                    row: index + 1,
+                    description: interval.description().unwrap_or_default(),
+                });
+                continue;
+            }
+        };
+
+        parsed.sessions.push(
+            interval
+                .into_session(&end, kinds)
+                .wrap_err_with(|| format!("could not import interval {}", index + 1))?,
+        );
+    }
+
+    Ok(parsed)
+}
+
+impl ExportedSession {
+    /// Everything except the fields the server works out for itself, like `actual_duration`.
+    /// Kinds are taken as they are unless they're mapped to something else.
+    fn into_session(self, end_time: DateTime<Local>, kinds: &KindMap) -> Result<ImportedSession> {
+        Ok(ImportedSession {
+            kind: kinds.mapped(&self.kind).unwrap_or(self.kind),
+            description: self.description,
+            start_time: self.start_time,
+            end_time,
+            duration: Some(
+                iso8601::duration(&self.duration)
+                    .map_err(|err| eyre!("could not parse duration: {err}"))?,
+            ),
+            time_zone: non_empty(Some(self.time_zone))
+                .map(montage_client::import_sessions::TimeZone),
+            outcome: Some(parse_outcome(&self.outcome)?),
+            tags: self.tags,
+            pauses: self
+                .pauses
+                .into_iter()
+                .filter_map(|pause| {
+                    pause.end_time.map(|end_time| ImportedPause {
+                        start_time: pause.start_time,
+                        end_time,
+                    })
+                })
+                .collect(),
+            notes: self
+                .notes
+                .into_iter()
+                .map(|note| ImportedNote {
+                    created_at: note.created_at,
+                    text: note.text,
+                })
+                .collect(),
+            interruptions: self
+                .interruptions
+                .into_iter()
+                .map(|interruption| ImportedInterruption {
+                    created_at: interruption.created_at,
+                    source: interruption.source,
+                    note: interruption.note,
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Turn exported sessions back into sessions to import. Sessions that were still going when they
+/// were exported are left out, since only finished sessions can be imported.
+fn import_exported(exported: Vec<ExportedSession>, kinds: &KindMap) -> Result<Parsed> {
+    let mut parsed = Parsed::default();
+
+    for (index, session) in exported.into_iter().enumerate() {
+        let end_time = match session.end_time {
+            Some(end_time) => end_time,
+            None => {
+                parsed.open.push(OpenRow {
+                    row: index + 1,
+                    description: session.description,
+                });
+                continue;
+            }
+        };
+
+        parsed.sessions.push(
+            session
+                .into_session(end_time, kinds)
+                .wrap_err_with(|| format!("could not import session {}", index + 1))?,
+        );
+    }
+
+    Ok(parsed)
+}
+
+/// The output of `montage export --format json`
+fn parse_json(reader: impl Read, kinds: &KindMap) -> Result<Parsed> {
+    let exported: Vec<ExportedSession> =
+        serde_json::from_reader(reader).wrap_err("could not read montage export")?;
+
+    import_exported(exported, kinds)
+}
+
+/// The output of `montage export --format jsonl`
+fn parse_jsonl(reader: impl Read, kinds: &KindMap) -> Result<Parsed> {
+    let exported = serde_json::Deserializer::from_reader(reader)
+        .into_iter::<ExportedSession>()
+        .enumerate()
+        .map(|(index, session)| {
+            session.wrap_err_with(|| format!("could not read session {}", index + 1))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    import_exported(exported, kinds)
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|value| !value.trim().is_empty())
+}
+
+fn split_tags(tags: &str, separator: char) -> Vec<String> {
+    tags.split(separator)
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn parse_rfc3339(time: &str) -> Result<DateTime<Local>> {
+    DateTime::parse_from_rfc3339(time.trim())
+        .map(|time| time.with_timezone(&Local))
+        .wrap_err_with(|| format!("could not parse \"{time}\" as an RFC 3339 time"))
+}
+
+fn parse_local(date: &str, time: &str) -> Result<DateTime<Local>> {
+    let naive = NaiveDateTime::parse_from_str(&format!("{date} {time}"), "%Y-%m-%d %H:%M:%S")
+        .wrap_err_with(|| format!("could not parse \"{date} {time}\" as a date and time"))?;
+
+    naive
+        .and_local_timezone(Local)
+        .earliest()
+        .ok_or_else(|| eyre!("{naive} does not exist in the local time zone"))
+}
+
+fn parse_timewarrior_time(time: &str) -> Result<DateTime<Local>> {
+    let naive = NaiveDateTime::parse_from_str(time, "%Y%m%dT%H%M%SZ")
+        .wrap_err_with(|| format!("could not parse \"{time}\" as a Timewarrior time"))?;
+
+    Ok(Utc.from_utc_datetime(&naive).with_timezone(&Local))
+}
+
+pub(crate) fn parse_outcome(outcome: &str) -> Result<Outcome> {
+    match outcome.trim().to_lowercase().as_str() {
+        "completed" => Ok(Outcome::Completed),
+        "partial" => Ok(Outcome::Partial),
+        "abandoned" => Ok(Outcome::Abandoned),
+        "unknown" => Ok(Outcome::Unknown),
+        _ => bail!("\"{outcome}\" is not an outcome"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(mappings: &[(&str, &str)]) -> KindMap {
+        KindMap {
+            mappings: mappings
+                .iter()
+                .map(|(from, kind)| (from.to_string(), kind.to_string()))
+                .collect(),
+            default: String::from("task"),
+        }
+    }
+
+    #[test]
+    fn csv_reads_montage_columns() {
+        let csv = concat!(
+            "description,kind,start_time,end_time,duration,time_zone,outcome,tags\n",
+            "Write docs,task,2023-10-18T09:00:00Z,2023-10-18T09:25:00Z,PT25M,America/Chicago,completed,docs;writing\n",
+            "Standup,Meetings,2023-10-18T10:00:00Z,2023-10-18T10:15:00Z,,,,\n",
+        );
+
+        let sessions = parse(
+            Format::Csv,
+            csv.as_bytes(),
+            &kinds(&[("Meetings", "meeting")]),
+        )
+        .unwrap()
+        .sessions;
+
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].kind, "task");
+        assert_eq!(sessions[0].description, "Write docs");
+        assert_eq!(
+            sessions[0].start_time,
+            Utc.with_ymd_and_hms(2023, 10, 18, 9, 0, 0).unwrap()
+        );
+        assert_eq!(
+            sessions[0].duration,
+            Some(iso8601::duration("PT25M").unwrap())
+        );
+        assert_eq!(
+            sessions[0].time_zone,
+            Some(montage_client::import_sessions::TimeZone(String::from(
+                "America/Chicago"
+            )))
+        );
+        assert_eq!(sessions[0].outcome, Some(Outcome::Completed));
+        assert_eq!(sessions[0].tags, vec!["docs", "writing"]);
+
+        assert_eq!(sessions[1].kind, "meeting");
+        assert_eq!(sessions[1].duration, None);
+        assert_eq!(sessions[1].outcome, None);
+        assert_eq!(sessions[1].tags, Vec::<String>::new());
+    }
+
+    #[test]
+    fn csv_errors_mention_the_row() {
+        let csv = concat!(
+            "description,start_time,end_time\n",
+            "Fine,2023-10-18T09:00:00Z,2023-10-18T09:25:00Z\n",
+            "Broken,yesterday,2023-10-18T09:25:00Z\n",
+        );
+
+        let err = parse(Format::Csv, csv.as_bytes(), &kinds(&[])).unwrap_err();
+
+        assert_eq!(err.to_string(), "could not import row 2");
+    }
+
+    #[test]
+    fn toggl_maps_projects_to_kinds() {
+        let csv = concat!(
+            "User,Email,Client,Project,Task,Description,Billable,Start date,Start time,End date,End time,Duration,Tags,Amount ()\n",
+            "Sam,sam@example.com,,Meetings,,Planning,No,2023-10-18,09:00:00,2023-10-18,09:30:00,00:30:00,\"work, planning\",\n",
+            "Sam,sam@example.com,,Side project,,Hacking,No,2023-10-18,10:00:00,2023-10-18,11:00:00,01:00:00,,\n",
+        );
+
+        let sessions = parse(
+            Format::Toggl,
+            csv.as_bytes(),
+            &kinds(&[("Meetings", "meeting")]),
+        )
+        .unwrap()
+        .sessions;
+
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].kind, "meeting");
+        assert_eq!(sessions[0].description, "Planning");
+        assert_eq!(sessions[0].tags, vec!["work", "planning"]);
+        assert_eq!(
+            sessions[0].end_time - sessions[0].start_time,
+            chrono::Duration::minutes(30)
+        );
+
+        assert_eq!(sessions[1].kind, "task");
+    }
+
+    #[test]
+    fn timewarrior_uses_mapped_tags_for_kinds_and_skips_open_intervals() {
+        let json = r#"[
+            {"id":3,"start":"20231018T090000Z","end":"20231018T092500Z","tags":["focus","montage"],"annotation":"Write docs"},
+            {"id":2,"start":"20231018T100000Z","end":"20231018T101500Z","tags":["standup"]},
+            {"id":1,"start":"20231018T110000Z","tags":["focus"]}
+        ]"#;
+
+        let Parsed { sessions, open } = parse(
+            Format::Timewarrior,
+            json.as_bytes(),
+            &kinds(&[("standup", "meeting")]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            open,
+            vec![OpenRow {
+                row: 3,
+                description: String::from("focus")
+            }]
+        );
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].kind, "task");
+        assert_eq!(sessions[0].description, "Write docs");
+        assert_eq!(sessions[0].tags, vec!["focus", "montage"]);
+        assert_eq!(
+            sessions[0].end_time,
+            Utc.with_ymd_and_hms(2023, 10, 18, 9, 25, 0).unwrap()
+        );
+
+        assert_eq!(sessions[1].kind, "meeting");
+        assert_eq!(sessions[1].description, "standup");
+        assert_eq!(sessions[1].tags, Vec::<String>::new());
+    }
+
+    #[test]
+    fn kind_mappings_need_both_sides() {
+        assert_eq!(
+            parse_kind_mapping("Meetings=meeting"),
+            Ok((String::from("Meetings"), String::from("meeting")))
+        );
+        assert!(parse_kind_mapping("meeting").is_err());
+        assert!(parse_kind_mapping("=meeting").is_err());
+    }
+
+    #[test]
+    fn json_exports_import_again() {
+        let start_time = Local.with_ymd_and_hms(2023, 10, 18, 9, 0, 0).unwrap();
+        let finished = ExportedSession {
+            id: 1,
+            kind: String::from("pairing"),
+            description: String::from("Write docs"),
+            start_time,
+            end_time: Some(start_time + chrono::Duration::minutes(30)),
+            projected_end_time: start_time + chrono::Duration::minutes(30),
+            duration: String::from("PT25M"),
+            actual_duration: String::from("PT25M"),
+            paused_duration: String::from("PT5M"),
+            time_zone: String::from("America/Chicago"),
+            outcome: String::from("partial"),
+            tags: vec![String::from("docs")],
+            pauses: vec![crate::export::ExportedPause {
+                start_time: start_time + chrono::Duration::minutes(10),
+                end_time: Some(start_time + chrono::Duration::minutes(15)),
+            }],
+            notes: vec![crate::export::ExportedNote {
+                created_at: start_time + chrono::Duration::minutes(29),
+                text: String::from("done"),
+            }],
+            interruptions: Vec::new(),
+        };
+        let still_going = ExportedSession {
+            id: 2,
+            end_time: None,
+            ..finished.clone()
+        };
+
+        let json = serde_json::to_string(&[&finished, &still_going]).unwrap();
+        let jsonl = format!(
+            "{}\n{}\n",
+            serde_json::to_string(&finished).unwrap(),
+            serde_json::to_string(&still_going).unwrap()
+        );
+
+        for (format, input) in [(Format::Json, json), (Format::Jsonl, jsonl)] {
+            let Parsed { sessions, open } = parse(format, input.as_bytes(), &kinds(&[])).unwrap();
+
+            assert_eq!(
+                open,
+                vec![OpenRow {
+                    row: 2,
+                    description: String::from("Write docs")
+                }]
+            );
+
+            assert_eq!(
+                sessions,
+                vec![ImportedSession {
+                    kind: String::from("pairing"),
+                    description: String::from("Write docs"),
+                    start_time,
+                    end_time: start_time + chrono::Duration::minutes(30),
+                    duration: Some(iso8601::duration("PT25M").unwrap()),
+                    time_zone: Some(montage_client::import_sessions::TimeZone(String::from(
+                        "America/Chicago"
+                    ))),
+                    outcome: Some(Outcome::Partial),
+                    tags: vec![String::from("docs")],
+                    pauses: vec![ImportedPause {
+                        start_time: start_time + chrono::Duration::minutes(10),
+                        end_time: start_time + chrono::Duration::minutes(15),
+                    }],
+                    notes: vec![ImportedNote {
+                        created_at: start_time + chrono::Duration::minutes(29),
+                        text: String::from("done"),
+                    }],
+                    interruptions: Vec::new(),
+                }]
+            );
+        }
+    }
+
+    #[test]
+    fn csv_exports_import_again() {
+        let csv = concat!(
+            "id,kind,description,start_time,end_time,projected_end_time,duration,actual_duration,paused_duration,time_zone,outcome,tags\n",
+            "1,task,Write docs,2023-10-18T09:00:00+00:00,2023-10-18T09:25:00+00:00,2023-10-18T09:25:00+00:00,PT25M,PT25M,P0D,UTC,completed,docs\n",
+            "2,task,Still going,2023-10-18T10:00:00+00:00,,2023-10-18T10:25:00+00:00,PT25M,PT10M,P0D,UTC,unknown,\n",
+        );
+
+        let Parsed { sessions, open } = parse(Format::Csv, csv.as_bytes(), &kinds(&[])).unwrap();
+
+        assert_eq!(
+            open,
+            vec![OpenRow {
+                row: 2,
+                description: String::from("Still going")
+            }]
+        );
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].description, "Write docs");
+        assert_eq!(sessions[0].tags, vec!["docs"]);
+    }
+}
@@ -0,0 +1,241 @@
+use super::graphql_client::GraphQLClientOptions;
+use chrono::Duration;
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use cynic::{MutationBuilder, QueryBuilder};
+use montage_client::kinds::Kind;
+
+#[derive(Debug, clap::Parser)]
+pub struct Kinds {
+    #[command(subcommand)]
+    command: KindsCommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum KindsCommand {
+    /// Add a new kind of session
+    Add {
+        /// What sessions should call this kind
+        name: String,
+
+        /// How long sessions of this kind last if not told otherwise, in minutes
+        #[arg(long)]
+        duration: usize,
+
+        /// Don't count time in sessions of this kind as working time
+        #[arg(long)]
+        not_working: bool,
+
+        /// Keep the vexer quiet during sessions of this kind
+        #[arg(long)]
+        quiet: bool,
+
+        /// Treat sessions of this kind as breaks (long ones start a new Pomodoro set)
+        #[arg(long = "break")]
+        is_break: bool,
+
+        /// Start a new Pomodoro set after any session of this kind, no matter how long
+        #[arg(long)]
+        ends_set: bool,
+
+        /// An emoji to show for sessions of this kind
+        #[arg(long, default_value = "⏰")]
+        emoji: String,
+
+        #[command(flatten)]
+        client: GraphQLClientOptions,
+    },
+
+    /// Show the kinds of sessions
+    List(GraphQLClientOptions),
+
+    /// Change a kind of session
+    Update {
+        /// The name of the kind to change
+        name: String,
+
+        /// How long sessions of this kind last if not told otherwise, in minutes
+        #[arg(long)]
+        duration: Option<usize>,
+
+        /// Count time in sessions of this kind as working time
+        #[arg(long, conflicts_with = "not_working")]
+        working: bool,
+
+        /// Don't count time in sessions of this kind as working time
+        #[arg(long, conflicts_with = "working")]
+        not_working: bool,
+
+        /// Let the vexer nag when sessions of this kind run over
+        #[arg(long, conflicts_with = "quiet")]
+        nags: bool,
+
+        /// Keep the vexer quiet during sessions of this kind
+        #[arg(long, conflicts_with = "nags")]
+        quiet: bool,
+
+        /// Treat sessions of this kind as breaks (long ones start a new Pomodoro set)
+        #[arg(long = "break", conflicts_with = "not_break")]
+        is_break: bool,
+
+        /// Don't treat sessions of this kind as breaks
+        #[arg(long, conflicts_with = "is_break")]
+        not_break: bool,
+
+        /// Start a new Pomodoro set after any session of this kind, no matter how long
+        #[arg(long, conflicts_with = "keeps_set")]
+        ends_set: bool,
+
+        /// Don't start a new Pomodoro set after sessions of this kind just because they happened
+        #[arg(long, conflicts_with = "ends_set")]
+        keeps_set: bool,
+
+        /// An emoji to show for sessions of this kind
+        #[arg(long)]
+        emoji: Option<String>,
+
+        #[command(flatten)]
+        client: GraphQLClientOptions,
+    },
+}
+
+impl Kinds {
+    pub async fn run(&self) -> Result<()> {
+        match &self.command {
+            KindsCommand::Add {
+                name,
+                duration,
+                not_working,
+                quiet,
+                is_break,
+                ends_set,
+                emoji,
+                client,
+            } => {
+                let query = montage_client::create_kind::CreateKindMutation::build(
+                    montage_client::create_kind::CreateKindMutationVariables {
+                        name,
+                        default_duration: Self::minutes(*duration)?,
+                        is_working: !not_working,
+                        nags: !quiet,
+                        is_break: *is_break,
+                        ends_set: *ends_set,
+                        emoji,
+                    },
+                );
+
+                let kind = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .ok_or(eyre!("data was null"))?
+                    .create_kind;
+
+                println!("Added \"{}\"", kind.name);
+            }
+            KindsCommand::List(client) => {
+                let query = montage_client::kinds::KindsQuery::build(());
+
+                let kinds = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .ok_or(eyre!("data was null"))?
+                    .kinds;
+
+                for kind in kinds {
+                    println!("{}", Self::format(&kind)?);
+                }
+            }
+            KindsCommand::Update {
+                name,
+                duration,
+                working,
+                not_working,
+                nags,
+                quiet,
+                is_break,
+                not_break,
+                ends_set,
+                keeps_set,
+                emoji,
+                client,
+            } => {
+                let query = montage_client::update_kind::UpdateKindMutation::build(
+                    montage_client::update_kind::UpdateKindMutationVariables {
+                        name,
+                        default_duration: duration.map(Self::minutes).transpose()?,
+                        is_working: match (working, not_working) {
+                            (true, _) => Some(true),
+                            (_, true) => Some(false),
+                            _ => None,
+                        },
+                        nags: match (nags, quiet) {
+                            (true, _) => Some(true),
+                            (_, true) => Some(false),
+                            _ => None,
+                        },
+                        is_break: match (is_break, not_break) {
+                            (true, _) => Some(true),
+                            (_, true) => Some(false),
+                            _ => None,
+                        },
+                        ends_set: match (ends_set, keeps_set) {
+                            (true, _) => Some(true),
+                            (_, true) => Some(false),
+                            _ => None,
+                        },
+                        emoji: emoji.as_deref(),
+                    },
+                );
+
+                let kind = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .ok_or(eyre!("data was null"))?
+                    .update_kind;
+
+                println!("Updated \"{}\"", kind.name);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn minutes(minutes: usize) -> Result<iso8601::Duration> {
+        iso8601::duration(&format!("PT{}M", minutes))
+            .map_err(|err| eyre!("could not parse duration: {err}"))
+    }
+
+    fn format(kind: &Kind) -> Result<String> {
+        let mut notes = vec![format!(
+            "{} minutes",
+            Duration::from_std(std::time::Duration::from(kind.default_duration))
+                .wrap_err("could not parse duration")?
+                .num_minutes()
+        )];
+
+        if !kind.is_working {
+            notes.push(String::from("not working time"));
+        }
+
+        if !kind.nags {
+            notes.push(String::from("quiet"));
+        }
+
+        if kind.is_break {
+            notes.push(String::from("break"));
+        }
+
+        if kind.ends_set {
+            notes.push(String::from("ends the set"));
+        }
+
+        Ok(format!(
+            "{} {} ({})",
+            kind.emoji,
+            kind.name,
+            notes.join(", ")
+        ))
+    }
+}
@@ -1,12 +1,21 @@
+mod export;
+mod goals;
 mod graphql_client;
+mod import;
+mod kinds;
+mod queue;
 mod scripts;
+mod settings;
+mod token;
 mod tokio_spawner;
+mod user;
 mod vexer;
+mod webhooks;
 mod xbar;
 
 use crate::graphql_client::GraphQLClientOptions;
 use crate::tokio_spawner::TokioSpawner;
-use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone};
+use chrono::{DateTime, Duration, Local, NaiveDate};
 use clap::Parser;
 use color_eyre::eyre::{bail, eyre, Result, WrapErr};
 
@@ -40,23 +49,35 @@ impl Opts {
                 duration,
                 until,
                 client,
-                is_meeting,
-                is_break,
-                is_offline,
+                kind,
+                tags,
+                outcome,
+                then_break,
             } => {
+                if let Some(outcome) = outcome {
+                    Self::set_outcome(client, *outcome, None).await?;
+                }
+
                 let query = montage_client::start::StartMutation::build(
                     montage_client::start::StartMutationVariables {
                         description,
-                        kind: if *is_meeting {
-                            montage_client::start::Kind::Meeting
-                        } else if *is_break {
-                            montage_client::start::Kind::Break
-                        } else if *is_offline {
-                            montage_client::start::Kind::Offline
+                        kind: kind.name().unwrap_or("task"),
+                        duration: Self::duration_from_options(duration, until)?,
+                        tags: if tags.is_empty() {
+                            None
                         } else {
-                            montage_client::start::Kind::Task
+                            Some(tags.iter().map(|tag| tag.as_str()).collect())
                         },
-                        duration: Self::duration_from_options(duration, until)?,
+                        time_zone: Self::local_time_zone().map(montage_client::start::TimeZone),
+                        then: then_break
+                            .map(|minutes| -> Result<montage_client::start::FollowUp> {
+                                Ok(montage_client::start::FollowUp {
+                                    kind: "break",
+                                    description: None,
+                                    duration: Self::duration_from_options(&Some(minutes), &None)?,
+                                })
+                            })
+                            .transpose()?,
                     },
                 );
 
@@ -67,6 +88,42 @@ impl Opts {
                     .expect("a non-null session")
                     .start;
 
+                println!(
+                    "Started \"{}\", running for {} minutes until {}",
+                    session.description,
+                    Self::humanize_duration_minutes(session.duration)?,
+                    Self::humanize_time_12hr(session.projected_end_time),
+                );
+
+                if let Some(follow_up) = session.follow_up {
+                    match follow_up.duration {
+                        Some(duration) => println!(
+                            "Then \"{}\" will start automatically, running for {} minutes",
+                            follow_up.description,
+                            Self::humanize_duration_minutes(duration)?,
+                        ),
+                        None => println!(
+                            "Then \"{}\" will start automatically",
+                            follow_up.description,
+                        ),
+                    }
+                }
+            }
+            Command::Next { client } => {
+                let query = montage_client::start_next::StartNextMutation::build(
+                    montage_client::start_next::StartNextMutationVariables {
+                        time_zone: Self::local_time_zone()
+                            .map(montage_client::start_next::TimeZone),
+                    },
+                );
+
+                let session = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .ok_or(eyre!("data was null"))?
+                    .start_next;
+
                 println!(
                     "Started \"{}\", running for {} minutes until {}",
                     session.description,
@@ -74,6 +131,42 @@ impl Opts {
                     Self::humanize_time_12hr(session.projected_end_time),
                 )
             }
+            Command::Cycle(client) => {
+                let query = montage_client::cycle::CycleQuery::build(());
+
+                let cycle = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .ok_or(eyre!("data was null"))?
+                    .cycle;
+
+                println!(
+                    "{} of {} task sessions done in this set. {} ({} minutes.)",
+                    cycle.completed_in_set,
+                    cycle.set_size,
+                    if cycle.long_break_due {
+                        "Time for a long break"
+                    } else {
+                        "Next up is a short break"
+                    },
+                    Self::humanize_duration_minutes(cycle.next_break_suggestion)?,
+                );
+            }
+            Command::Queue(queue) => queue.run().await?,
+            Command::Goals(goals) => goals.run().await?,
+            Command::Kinds(kinds) => kinds.run().await?,
+            Command::Settings(settings) => settings.run().await?,
+            Command::Webhooks(webhooks) => webhooks.run().await?,
+            Command::Token(token) => {
+                token
+                    .run(self.open_sqlite_database(token.db_dir.clone()).await?)
+                    .await?
+            }
+            Command::User(user) => {
+                user.run(self.open_sqlite_database(user.db_dir.clone()).await?)
+                    .await?
+            }
             Command::Extend { by, to, client } => {
                 if let Some(duration) = by {
                     let query = montage_client::extend_by::ExtendByMutation::build(
@@ -116,6 +209,230 @@ impl Opts {
                     bail!("got neither --by nor --to. This should not happen!");
                 };
             }
+            Command::Stop { outcome, client } => {
+                let query = montage_client::stop::StopMutation::build(());
+
+                let session = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .expect("a non-null session")
+                    .stop;
+
+                println!(
+                    "Stopped \"{}\" after {} minutes",
+                    session.description,
+                    Self::humanize_duration_minutes(session.actual_duration)?,
+                );
+
+                if let Some(outcome) = outcome {
+                    Self::set_outcome(client, *outcome, Some(session.id)).await?;
+                }
+            }
+            Command::Pause { client } => {
+                let query = montage_client::pause::PauseMutation::build(());
+
+                let session = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .expect("a non-null session")
+                    .pause;
+
+                println!("Paused \"{}\"", session.description);
+            }
+            Command::Resume { client } => {
+                let query = montage_client::resume::ResumeMutation::build(());
+
+                let session = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .expect("a non-null session")
+                    .resume;
+
+                println!(
+                    "Resumed \"{}\", now running until {}",
+                    session.description,
+                    Self::humanize_time_12hr(session.projected_end_time),
+                );
+            }
+            Command::Edit {
+                id,
+                description,
+                start,
+                end,
+                duration,
+                kind,
+                client,
+            } => {
+                let query = montage_client::update_session::UpdateSessionMutation::build(
+                    montage_client::update_session::UpdateSessionMutationVariables {
+                        id: *id,
+                        description: description.as_deref(),
+                        kind: kind.name(),
+                        start_time: *start,
+                        end_time: *end,
+                        duration: Self::duration_from_options(duration, &None)?,
+                    },
+                );
+
+                let session = client
+                    .make_graphql_request(query)
+                    .await
+                    .wrap_err_with(|| format!("could not update session {id}"))?
+                    .data
+                    .expect("a non-null session")
+                    .update_session;
+
+                println!(
+                    "Updated \"{}\" ({}), which started at {} and ran for {} minutes",
+                    session.description,
+                    session.kind,
+                    Self::humanize_time_12hr(session.start_time),
+                    Self::humanize_duration_minutes(session.actual_duration)?,
+                );
+            }
+            Command::Delete { id, client } => {
+                let query = montage_client::delete_session::DeleteSessionMutation::build(
+                    montage_client::delete_session::DeleteSessionMutationVariables { id: *id },
+                );
+
+                let session = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .ok_or(eyre!("data was null"))?
+                    .delete_session;
+
+                println!(
+                    "Deleted \"{}\" (started at {}.) Run `montage undo` to bring it back.",
+                    session.description,
+                    Self::humanize_time_12hr(session.start_time),
+                );
+            }
+            Command::Restore { id, client } => {
+                let query = montage_client::restore_session::RestoreSessionMutation::build(
+                    montage_client::restore_session::RestoreSessionMutationVariables { id: *id },
+                );
+
+                let session = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .ok_or(eyre!("data was null"))?
+                    .restore_session;
+
+                println!(
+                    "Restored \"{}\" (started at {}, #{})",
+                    session.description,
+                    Self::humanize_time_12hr(session.start_time),
+                    session.id,
+                );
+            }
+            Command::Import(import) => import.run().await?,
+            Command::Export(export) => export.run().await?,
+            Command::History { id, client } => {
+                let query = montage_client::history::HistoryQuery::build(
+                    montage_client::history::HistoryQueryVariables { session_id: *id },
+                );
+
+                let events = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .ok_or(eyre!("data was null"))?
+                    .history;
+
+                if events.is_empty() {
+                    println!("No changes recorded for session #{id}");
+                }
+
+                for event in events {
+                    println!(
+                        "{} {} {}",
+                        Self::humanize_time_12hr(event.created_at),
+                        event.mutation,
+                        event.arguments,
+                    );
+                }
+            }
+            Command::Undo { client } => {
+                let query = montage_client::undo::UndoMutation::build(());
+
+                let undone = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .ok_or(eyre!("data was null"))?
+                    .undo;
+
+                println!("Undid {}", undone.mutation);
+
+                for session in undone.removed {
+                    println!(
+                        "  removed \"{}\" (started at {}, #{})",
+                        session.description,
+                        Self::humanize_time_12hr(session.start_time),
+                        session.id,
+                    );
+                }
+
+                for session in undone.restored {
+                    println!(
+                        "  restored \"{}\" (started at {}, #{})",
+                        session.description,
+                        Self::humanize_time_12hr(session.start_time),
+                        session.id,
+                    );
+                }
+            }
+            Command::Note {
+                text,
+                session,
+                client,
+            } => {
+                let query = montage_client::add_note::AddNoteMutation::build(
+                    montage_client::add_note::AddNoteMutationVariables {
+                        text,
+                        session_id: *session,
+                    },
+                );
+
+                let session = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .ok_or(eyre!("data was null"))?
+                    .add_note;
+
+                println!("Added a note to \"{}\"", session.description);
+            }
+            Command::Interrupt {
+                source,
+                note,
+                client,
+            } => {
+                let query = montage_client::log_interruption::LogInterruptionMutation::build(
+                    montage_client::log_interruption::LogInterruptionMutationVariables {
+                        source,
+                        note: note.as_deref(),
+                    },
+                );
+
+                let session = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .ok_or(eyre!("data was null"))?
+                    .log_interruption;
+
+                println!(
+                    "Logged an interruption during \"{}\" ({} so far)",
+                    session.description,
+                    session.interruptions.len(),
+                );
+            }
             Command::Report {
                 from: naive_from,
                 to: naive_to,
@@ -125,26 +442,19 @@ impl Opts {
                 template,
                 client,
             } => {
-                let from = naive_from
-                    .and_then(|date| date.and_hms_opt(0, 0, 0))
-                    .and_then(|date| date.and_local_timezone(Local).into())
-                    .unwrap_or_else(|| {
-                        let now = Local::now();
-
-                        now.timezone()
-                            .with_ymd_and_hms(now.year(), now.month(), now.day(), 0, 0, 0)
-                    })
-                    .unwrap();
+                let from =
+                    Self::on_local_date(naive_from.unwrap_or_else(|| Local::now().date_naive()))?;
 
-                let to = naive_to
-                    .and_then(|date| date.and_hms_opt(0, 0, 0))
-                    .map(|date| date.and_local_timezone(Local).unwrap())
-                    .unwrap_or(from);
+                let to = match naive_to {
+                    Some(date) => Self::on_local_date(*date)?,
+                    None => from,
+                };
 
                 let query = montage_client::report::ReportQuery::build(
                     montage_client::report::ReportQueryVariables {
                         start: from,
                         end: to,
+                        time_zone: Self::local_time_zone().map(montage_client::report::TimeZone),
                     },
                 );
 
@@ -228,7 +538,7 @@ impl Opts {
                 handlebars_helper!(lower: |input: String| input.to_ascii_lowercase());
                 handlebars.register_helper("lower", Box::new(lower));
 
-                let default_template = String::from("## Montage Sessions\n\n{{> date_range}}\n\n\n{{> totals report.totals}}\n\n{{#if include_task_totals}}\n\n### Task Totals\n\n{{#each report.totals.sessions_by_description}}- {{>task_by_description}}\n{{/each}}{{/if}}{{#if include_sessions}}\n### Log\n\n{{#each report.sessions}}- {{>session}}\n{{/each}}{{/if}}");
+                let default_template = String::from("## Montage Sessions\n\n{{> date_range}}\n\n\n{{> totals report.totals}}\n\n{{#if include_task_totals}}\n\n### Task Totals\n\n{{#each report.totals.sessions_by_description}}- {{>task_by_description}}\n{{/each}}{{/if}}{{#if include_task_totals}}{{#if report.totals.totals_by_tag}}\n### Tag Totals\n\n{{#each report.totals.totals_by_tag}}- {{>tag_totals}}\n{{/each}}{{/if}}{{/if}}{{#if report.totals.interruptions_by_kind}}\n### Interruptions\n\n{{#each report.totals.interruptions_by_kind}}- {{>interruptions_by_kind}}\n{{/each}}{{/if}}{{#if report.totals.extensions_by_kind}}\n### Extensions\n\n{{#each report.totals.extensions_by_kind}}- {{>extensions_by_kind}}\n{{/each}}{{/if}}{{#if report.goals}}\n### Goals\n\n{{#each report.goals}}- {{>goal_progress}}\n{{/each}}{{/if}}{{#if include_sessions}}\n### Log\n\n{{#each report.sessions}}- {{>session}}\n{{/each}}{{/if}}");
 
                 handlebars.register_template_string::<String>(
                     "report",
@@ -245,29 +555,52 @@ impl Opts {
 
                 handlebars.register_template_string(
                     "totals",
-                    "**{{hms task}}** spent on tasks, **{{hms meeting}}** in meetings, and **{{hms short_break}}** on short breaks for a total of **{{hms working}}**{{#if include_long_breaks_in_summary}} In addition, you tracked **{{hms long_break}}** on long breaks{{/if}}."
+                    "**{{hms task}}** spent on tasks, **{{hms meeting}}** in meetings, and **{{hms short_break}}** on short breaks for a total of **{{hms working}}**{{#if include_long_breaks_in_summary}} In addition, you tracked **{{hms long_break}}** on long breaks{{/if}}.{{#if task_outcomes.total}} **{{task_outcomes.completed}} of {{task_outcomes.total}}** task sessions were completed.{{/if}}"
                 )?;
 
                 handlebars.register_template_string(
                     "session",
-                    "**{{time start_time}}** {{description}} ({{lower kind}}, {{hms actual_duration}})",
+                    "**{{time start_time}}** {{description}} ({{lower kind}}, {{hms actual_duration}}, #{{id}}){{#each notes}}\n  - {{>note}}{{/each}}{{#each interruptions}}\n  - {{>interruption}}{{/each}}",
                 )?;
 
                 handlebars.register_template_string(
                     "task_by_description",
-                    "**{{hms total}}** {{description}} ({{lower kind}})",
+                    "**{{hms total}}** {{description}} ({{lower kind}}{{#if interruptions}}, {{interruptions}} interruptions{{/if}})",
+                )?;
+
+                handlebars.register_template_string("tag_totals", "**{{hms total}}** {{tag}}")?;
+
+                handlebars.register_template_string("note", "{{time created_at}}: {{text}}")?;
+
+                handlebars.register_template_string(
+                    "interruption",
+                    "{{time created_at}}: interrupted by {{source}}{{#if note}} ({{note}}){{/if}}",
+                )?;
+
+                handlebars.register_template_string(
+                    "interruptions_by_kind",
+                    "**{{count}}** during {{lower kind}} sessions",
+                )?;
+
+                handlebars.register_template_string(
+                    "extensions_by_kind",
+                    "**{{count}}** on {{lower kind}} sessions, adding {{hms added}}",
+                )?;
+
+                handlebars.register_template_string(
+                    "goal_progress",
+                    "**{{hms actual}}** {{#if goal.tag}}tagged {{goal.tag}}{{else}}on {{lower goal.kind}} sessions{{/if}} {{#if (eq goal.period \"DAY\")}}today{{else}}this week{{/if}} ({{lower goal.bound}} {{hms goal.target}}, {{#if met}}met{{else}}not met{{/if}})",
                 )?;
 
                 println!("{}", handlebars.render("report", &context)?);
             }
             Command::Watch(client) => {
                 let query = CurrentSessionUpdates::build(());
-                let (connection, _) = async_tungstenite::tokio::connect_async(client.request()?)
-                    .await
-                    .unwrap();
+                let connection = client.connect_websocket().await.unwrap();
 
                 let (sink, stream) = connection.split();
                 let mut client = CynicClientBuilder::new()
+                    .payload(client.connection_init_payload())
                     .build(stream, sink, TokioSpawner::current())
                     .await
                     .unwrap();
@@ -283,13 +616,115 @@ impl Opts {
 
                 // TODO: gracefully drop the connection
             }
+            Command::Events {
+                reminder_at,
+                client,
+            } => {
+                use montage_client::events::{Event, Events, EventsVariables};
+
+                let query = Events::build(EventsVariables {
+                    reminder_at: if reminder_at.is_empty() {
+                        None
+                    } else {
+                        Some(
+                            reminder_at
+                                .iter()
+                                .map(|minutes| {
+                                    iso8601::duration(&format!("PT{}M", minutes)).expect(
+                                        "configuration error: could not parse that amount of minutes",
+                                    )
+                                })
+                                .collect(),
+                        )
+                    },
+                });
+
+                let connection = client.connect_websocket().await?;
+
+                let (sink, stream) = connection.split();
+                let mut client = CynicClientBuilder::new()
+                    .payload(client.connection_init_payload())
+                    .build(stream, sink, TokioSpawner::current())
+                    .await
+                    .wrap_err("could not construct a Cynic client")?;
+
+                let mut events_stream = client
+                    .streaming_operation(query)
+                    .await
+                    .wrap_err("could not start streaming")?;
+
+                while let Some(item) = events_stream.next().await {
+                    let event = match item.wrap_err("error getting next event")?.data {
+                        Some(data) => data.events,
+                        None => continue,
+                    };
+
+                    match event {
+                        Event::SessionStarted(started) => println!(
+                            "Started \"{}\" ({}), running until {}",
+                            started.session.description,
+                            started.session.kind,
+                            Self::humanize_time_12hr(started.session.projected_end_time),
+                        ),
+                        Event::SessionExtended(extended) => println!(
+                            "Changed \"{}\" from {} to {} minutes, running until {}",
+                            extended.session.description,
+                            Self::humanize_duration_minutes(extended.previous_duration)?,
+                            Self::humanize_duration_minutes(extended.session.duration)?,
+                            Self::humanize_time_12hr(extended.session.projected_end_time),
+                        ),
+                        Event::SessionEnded(ended) => println!(
+                            "Ended \"{}\"{}",
+                            ended.session.description,
+                            match ended.session.end_time {
+                                Some(end_time) =>
+                                    format!(" at {}", Self::humanize_time_12hr(end_time)),
+                                None => String::new(),
+                            },
+                        ),
+                        Event::SessionOverTime(over_time) => {
+                            println!("\"{}\" is over time", over_time.session.description)
+                        }
+                        Event::ReminderDue(reminder) => println!(
+                            "{} minutes left in \"{}\"",
+                            Self::humanize_duration_minutes(reminder.remaining_time)?,
+                            reminder.session.description,
+                        ),
+                        Event::Unknown => tracing::warn!("got an event we don't know about"),
+                    }
+                }
+            }
             Command::Xbar(xbar) => xbar.run().await?,
             Command::Vex(vexer) => vexer.run().await?,
-            Command::Serve { addr, port, db_dir } => {
+            Command::Serve {
+                addr,
+                port,
+                socket,
+                tls_cert,
+                tls_key,
+                db_dir,
+                keep_deleted_for,
+                keep_webhook_deliveries_for,
+            } => {
+                let listener = match (socket, tls_cert, tls_key) {
+                    (Some(path), _, _) => montage_server::Listener::Unix { path: path.clone() },
+                    (None, Some(cert), Some(key)) => montage_server::Listener::Tls {
+                        addr: *addr,
+                        port: *port,
+                        cert: cert.clone(),
+                        key: key.clone(),
+                    },
+                    _ => montage_server::Listener::Tcp {
+                        addr: *addr,
+                        port: *port,
+                    },
+                };
+
                 montage_server::serve(
                     self.open_sqlite_database(db_dir.clone()).await?,
-                    *addr,
-                    *port,
+                    listener,
+                    Duration::days(*keep_deleted_for),
+                    Duration::days(*keep_webhook_deliveries_for),
                 )
                 .await?
             }
@@ -306,6 +741,48 @@ impl Opts {
         Ok(())
     }
 
+    async fn set_outcome(
+        client: &GraphQLClientOptions,
+        outcome: Outcome,
+        session_id: Option<i32>,
+    ) -> Result<()> {
+        let query = montage_client::set_outcome::SetOutcomeMutation::build(
+            montage_client::set_outcome::SetOutcomeMutationVariables {
+                outcome: outcome.into(),
+                session_id,
+            },
+        );
+
+        let session = client
+            .make_graphql_request(query)
+            .await?
+            .data
+            .ok_or(eyre!("data was null"))?
+            .set_outcome;
+
+        println!(
+            "Marked \"{}\" as {}",
+            session.description,
+            format!("{:?}", session.outcome).to_lowercase(),
+        );
+
+        Ok(())
+    }
+
+    /// The IANA name of the local time zone, if we can figure it out. The server uses this to
+    /// decide where days start and end.
+    fn local_time_zone() -> Option<String> {
+        iana_time_zone::get_timezone().ok()
+    }
+
+    /// Pick a time on the given local date to tell the server which day we mean. We use noon
+    /// instead of midnight because some days skip midnight when the clocks change.
+    fn on_local_date(date: NaiveDate) -> Result<DateTime<Local>> {
+        date.and_hms_opt(12, 0, 0)
+            .and_then(|time| time.and_local_timezone(Local).earliest())
+            .ok_or_else(|| eyre!("{date} does not exist in the local time zone"))
+    }
+
     fn humanize_time_12hr(time: DateTime<Local>) -> String {
         if Local::now().date_naive() == time.date_naive() {
             time.format("%I:%M %P").to_string()
@@ -379,6 +856,73 @@ impl Opts {
     }
 }
 
+/// Which kind of session to use. `--task`, `--meeting`, `--break`, and `--offline` are shortcuts
+/// for the kinds that come with montage.
+#[derive(clap::Args, Debug)]
+struct KindOptions {
+    /// The name of the kind of session (see `montage kinds list`)
+    #[arg(long, conflicts_with_all = ["is_task", "is_meeting", "is_break", "is_offline"])]
+    kind: Option<String>,
+
+    /// Shortcut for `--kind task`
+    #[arg(long("task"), conflicts_with_all = ["is_meeting", "is_break", "is_offline"])]
+    is_task: bool,
+
+    /// Shortcut for `--kind meeting`
+    #[arg(long("meeting"), conflicts_with_all = ["is_task", "is_break", "is_offline"])]
+    is_meeting: bool,
+
+    /// Shortcut for `--kind break`
+    #[arg(long("break"), conflicts_with_all = ["is_task", "is_meeting", "is_offline"])]
+    is_break: bool,
+
+    /// Shortcut for `--kind offline`
+    #[arg(long("offline"), conflicts_with_all = ["is_task", "is_meeting", "is_break"])]
+    is_offline: bool,
+}
+
+impl KindOptions {
+    /// The name of the kind these options picked, if they picked one
+    fn name(&self) -> Option<&str> {
+        if let Some(kind) = &self.kind {
+            Some(kind)
+        } else if self.is_task {
+            Some("task")
+        } else if self.is_meeting {
+            Some("meeting")
+        } else if self.is_break {
+            Some("break")
+        } else if self.is_offline {
+            Some("offline")
+        } else {
+            None
+        }
+    }
+}
+
+/// How a session turned out
+#[derive(clap::ValueEnum, Debug, Clone, Copy)]
+enum Outcome {
+    /// Everything planned for the session got done
+    Completed,
+
+    /// Some of what was planned got done
+    Partial,
+
+    /// The session was given up on
+    Abandoned,
+}
+
+impl From<Outcome> for montage_client::set_outcome::Outcome {
+    fn from(outcome: Outcome) -> Self {
+        match outcome {
+            Outcome::Completed => Self::Completed,
+            Outcome::Partial => Self::Partial,
+            Outcome::Abandoned => Self::Abandoned,
+        }
+    }
+}
+
 #[derive(clap::Subcommand, Debug)]
 enum Command {
     /// Start a task
@@ -394,22 +938,56 @@ enum Command {
         #[arg(long, conflicts_with = "duration")]
         until: Option<DateTime<Local>>,
 
-        /// Is this session a meeting?
-        #[arg(long("meeting"), conflicts_with_all = ["is_break", "is_offline"])]
-        is_meeting: bool,
+        #[command(flatten)]
+        kind: KindOptions,
+
+        /// Tag this session so it's grouped with related sessions in reports. Can be given more
+        /// than once.
+        #[arg(long("tag"))]
+        tags: Vec<String>,
+
+        /// How did the previous session turn out?
+        #[arg(long, value_enum)]
+        outcome: Option<Outcome>,
 
-        /// Is this session a break?
-        #[arg(long("break"), conflicts_with_all = ["is_meeting", "is_offline"])]
-        is_break: bool,
+        /// Start a break of this many minutes automatically when this session ends
+        #[arg(long)]
+        then_break: Option<usize>,
 
-        /// Is this session offline time?
-        #[arg(long("offline"), conflicts_with_all = ["is_break", "is_meeting"])]
-        is_offline: bool,
+        #[command(flatten)]
+        client: GraphQLClientOptions,
+    },
 
+    /// Start the next session in the queue
+    Next {
         #[command(flatten)]
         client: GraphQLClientOptions,
     },
 
+    /// Show where you are in the current Pomodoro set
+    Cycle(GraphQLClientOptions),
+
+    /// Plan sessions for later
+    Queue(queue::Queue),
+
+    /// Set goals for how much time to spend on things each day or week
+    Goals(goals::Goals),
+
+    /// Manage the kinds of sessions (tasks, breaks, meetings, and any you add yourself)
+    Kinds(kinds::Kinds),
+
+    /// See or change the server's settings
+    Settings(settings::Settings),
+
+    /// Send session events to other services over HTTP
+    Webhooks(webhooks::Webhooks),
+
+    /// Manage the tokens clients use to authenticate with the server
+    Token(token::Token),
+
+    /// Manage the people sharing the server, each with their own sessions
+    User(user::User),
+
     /// Add some more time onto the current session
     Extend {
         #[arg(long, conflicts_with = "to", required_unless_present = "to")]
@@ -422,6 +1000,126 @@ enum Command {
         client: GraphQLClientOptions,
     },
 
+    /// Stop the current session without starting a new one
+    Stop {
+        /// How did the session turn out?
+        #[arg(long, value_enum)]
+        outcome: Option<Outcome>,
+
+        #[command(flatten)]
+        client: GraphQLClientOptions,
+    },
+
+    /// Pause the current session. Paused time doesn't count towards the session.
+    Pause {
+        #[command(flatten)]
+        client: GraphQLClientOptions,
+    },
+
+    /// Resume the current session after pausing it
+    Resume {
+        #[command(flatten)]
+        client: GraphQLClientOptions,
+    },
+
+    /// Change a session after the fact. Use the ID shown in `montage report`'s log.
+    Edit {
+        /// The ID of the session to change
+        id: i32,
+
+        /// A new description for the session
+        #[arg(long)]
+        description: Option<String>,
+
+        /// When the session started
+        #[arg(long)]
+        start: Option<DateTime<Local>>,
+
+        /// When the session ended
+        #[arg(long)]
+        end: Option<DateTime<Local>>,
+
+        /// How long the session was planned to last, in minutes
+        #[arg(long)]
+        duration: Option<usize>,
+
+        /// Change what kind of session this was
+        #[command(flatten)]
+        kind: KindOptions,
+
+        #[command(flatten)]
+        client: GraphQLClientOptions,
+    },
+
+    /// Delete a session. Use the ID shown in `montage report`'s log.
+    Delete {
+        /// The ID of the session to delete
+        id: i32,
+
+        #[command(flatten)]
+        client: GraphQLClientOptions,
+    },
+
+    /// Bring back a deleted session, even if you've made other changes since deleting it
+    Restore {
+        /// The ID of the session to bring back. If omitted, restores the most recently deleted
+        /// session.
+        id: Option<i32>,
+
+        #[command(flatten)]
+        client: GraphQLClientOptions,
+    },
+
+    /// Bring in sessions from a CSV file, Toggl, Timewarrior, or a montage export
+    Import(import::Import),
+
+    /// Write out sessions as CSV or JSON, for spreadsheets or backups
+    Export(export::Export),
+
+    /// Show every change made to a session, like extensions and edits. Use the ID shown in
+    /// `montage report`'s log.
+    History {
+        /// The ID of the session
+        id: i32,
+
+        #[command(flatten)]
+        client: GraphQLClientOptions,
+    },
+
+    /// Undo the most recent change to your sessions, like starting one by mistake or extending it.
+    /// Run it again to keep going back.
+    Undo {
+        #[command(flatten)]
+        client: GraphQLClientOptions,
+    },
+
+    /// Write down a note about what you got done. Notes show up under their session in
+    /// `montage report`'s log.
+    Note {
+        /// What you want to remember
+        text: String,
+
+        /// The ID of the session to add the note to. If omitted, uses the current session.
+        #[arg(long)]
+        session: Option<i32>,
+
+        #[command(flatten)]
+        client: GraphQLClientOptions,
+    },
+
+    /// Log something that interrupted the current session
+    Interrupt {
+        /// What interrupted you (for example, "slack ping")
+        source: String,
+
+        /// Anything else worth remembering about the interruption
+        #[arg(long)]
+        note: Option<String>,
+
+        #[command(flatten)]
+        client: GraphQLClientOptions,
+    },
+
     /// Report on the sessions specified in the current days (inclusive).
     Report {
         /// The starting date. If omitted, uses today's date. Assumed to be in the local time zone.
@@ -457,6 +1155,17 @@ enum Command {
 
     Watch(GraphQLClientOptions),
 
+    /// Print a line whenever a session starts, ends, gets extended, or runs over time
+    Events {
+        /// Also print a reminder this many minutes before the current session ends. Can be given
+        /// more than once.
+        #[arg(long)]
+        reminder_at: Vec<usize>,
+
+        #[command(flatten)]
+        client: GraphQLClientOptions,
+    },
+
     /// Show an xbar status message
     Xbar(xbar::XBar),
 
@@ -474,9 +1183,35 @@ enum Command {
         #[arg(long, default_value = crate::graphql_client::DEFAULT_PORT, env = "MONTAGE_PORT")]
         port: u16,
 
+        /// Listen on a Unix socket at this path instead of a TCP port. Clients connect to it with
+        /// `--server-url unix:///path/to/socket`.
+        #[arg(long, env = "MONTAGE_SOCKET", conflicts_with_all = ["tls_cert", "tls_key"])]
+        socket: Option<PathBuf>,
+
+        /// Serve HTTPS with the certificate in this PEM file
+        #[arg(long, env = "MONTAGE_TLS_CERT", requires = "tls_key")]
+        tls_cert: Option<PathBuf>,
+
+        /// The private key for `--tls-cert`, as a PKCS#8 PEM file (`BEGIN PRIVATE KEY`)
+        #[arg(long, env = "MONTAGE_TLS_KEY", requires = "tls_cert")]
+        tls_key: Option<PathBuf>,
+
         /// Where to store the database
         #[arg(long, env = "MONTAGE_DB")]
         db_dir: Option<PathBuf>,
+
+        /// How long to keep deleted sessions around (so they can be restored) before removing
+        /// them for good, in days
+        #[arg(long, default_value = "30", env = "MONTAGE_KEEP_DELETED_FOR")]
+        keep_deleted_for: i64,
+
+        /// How long to keep the log of webhook delivery attempts, in days
+        #[arg(
+            long,
+            default_value = "30",
+            env = "MONTAGE_KEEP_WEBHOOK_DELIVERIES_FOR"
+        )]
+        keep_webhook_deliveries_for: i64,
     },
 
     /// Export the GraphQL SDL for the server
@@ -0,0 +1,169 @@
+use super::graphql_client::GraphQLClientOptions;
+use super::KindOptions;
+use chrono::Duration;
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use cynic::{MutationBuilder, QueryBuilder};
+use montage_client::queue::PlannedSession;
+
+#[derive(Debug, clap::Parser)]
+pub struct Queue {
+    #[command(subcommand)]
+    command: QueueCommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum QueueCommand {
+    /// Plan a session by adding it to the end of the queue
+    Add {
+        /// The task you'll be doing
+        description: String,
+
+        /// The length of the task, in minutes. If omitted, we'll decide based on the session
+        /// type when it starts.
+        #[arg(long)]
+        duration: Option<usize>,
+
+        #[command(flatten)]
+        kind: KindOptions,
+
+        #[command(flatten)]
+        client: GraphQLClientOptions,
+    },
+
+    /// Show the planned sessions, in the order they'll be started
+    List(GraphQLClientOptions),
+
+    /// Take a planned session out of the queue without starting it
+    Remove {
+        /// The ID of the planned session (shown in `montage queue list`)
+        id: i32,
+
+        #[command(flatten)]
+        client: GraphQLClientOptions,
+    },
+
+    /// Move a planned session to a new position in the queue
+    Move {
+        /// The ID of the planned session (shown in `montage queue list`)
+        id: i32,
+
+        /// The new position. 0 is the head of the queue.
+        position: i32,
+
+        #[command(flatten)]
+        client: GraphQLClientOptions,
+    },
+}
+
+impl Queue {
+    pub async fn run(&self) -> Result<()> {
+        match &self.command {
+            QueueCommand::Add {
+                description,
+                duration,
+                kind,
+                client,
+            } => {
+                let query = montage_client::enqueue::EnqueueMutation::build(
+                    montage_client::enqueue::EnqueueMutationVariables {
+                        description,
+                        kind: kind.name().unwrap_or("task"),
+                        duration: duration
+                            .map(|minutes| iso8601::duration(&format!("PT{}M", minutes)))
+                            .transpose()
+                            .map_err(|err| eyre!("could not parse duration: {err}"))?,
+                    },
+                );
+
+                let planned = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .ok_or(eyre!("data was null"))?
+                    .enqueue;
+
+                println!(
+                    "Planned \"{}\" at position {}",
+                    planned.description, planned.position
+                );
+            }
+            QueueCommand::List(client) => {
+                let query = montage_client::queue::QueueQuery::build(());
+
+                let queue = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .ok_or(eyre!("data was null"))?
+                    .queue;
+
+                if queue.is_empty() {
+                    println!("Nothing planned");
+                }
+
+                for planned in queue {
+                    println!("{}", Self::format(&planned)?);
+                }
+            }
+            QueueCommand::Remove { id, client } => {
+                let query = montage_client::dequeue::DequeueMutation::build(
+                    montage_client::dequeue::DequeueMutationVariables { id: *id },
+                );
+
+                let removed = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .ok_or(eyre!("data was null"))?
+                    .dequeue;
+
+                println!("Removed \"{}\" from the queue", removed.description);
+            }
+            QueueCommand::Move {
+                id,
+                position,
+                client,
+            } => {
+                let query = montage_client::reorder_queue::ReorderQueueMutation::build(
+                    montage_client::reorder_queue::ReorderQueueMutationVariables {
+                        id: *id,
+                        position: *position,
+                    },
+                );
+
+                let queue = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .ok_or(eyre!("data was null"))?
+                    .reorder_queue;
+
+                for planned in queue {
+                    println!(
+                        "{}. {} (#{})",
+                        planned.position, planned.description, planned.id
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn format(planned: &PlannedSession) -> Result<String> {
+        let length = match &planned.duration {
+            Some(duration) => format!(
+                "{} minutes",
+                Duration::from_std(std::time::Duration::from(*duration))
+                    .wrap_err("could not parse duration")?
+                    .num_minutes()
+            ),
+            None => String::from("default length"),
+        };
+
+        Ok(format!(
+            "{}. {} ({}, {}, #{})",
+            planned.position, planned.description, planned.kind, length, planned.id,
+        ))
+    }
+}
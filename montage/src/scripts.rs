@@ -10,7 +10,7 @@ pub enum Script<'arg> {
     },
     SessionEnded {
         session: &'arg Session,
-        next_session: &'arg Session,
+        next_session: Option<&'arg Session>,
     },
     SessionExtended {
         session: &'arg Session,
@@ -61,7 +61,9 @@ impl Script<'_> {
                 vec![
                     session.description.clone(),
                     session.kind.to_string(),
-                    next_session.kind.to_string(),
+                    next_session
+                        .map(|next| next.kind.to_string())
+                        .unwrap_or_default(),
                 ]
             }
             Self::SessionExtended { session } => vec![
@@ -0,0 +1,107 @@
+use super::graphql_client::GraphQLClientOptions;
+use chrono::Duration;
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use cynic::{MutationBuilder, QueryBuilder};
+
+#[derive(Debug, clap::Parser)]
+pub struct Settings {
+    #[command(subcommand)]
+    command: SettingsCommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum SettingsCommand {
+    /// Show the server's settings
+    Show(GraphQLClientOptions),
+
+    /// Change the server's settings. Settings left out are not changed.
+    Update {
+        /// Breaks longer than this many minutes are long breaks, which don't count as working time
+        #[arg(long)]
+        short_break_cutoff: Option<usize>,
+
+        /// How many task sessions make a Pomodoro set before we suggest a long break
+        #[arg(long)]
+        set_size: Option<i32>,
+
+        /// How many minutes breaks started without a duration last once a set is done
+        #[arg(long)]
+        long_break_duration: Option<usize>,
+
+        #[command(flatten)]
+        client: GraphQLClientOptions,
+    },
+}
+
+impl Settings {
+    pub async fn run(&self) -> Result<()> {
+        let (short_break_cutoff, set_size, long_break_duration) = match &self.command {
+            SettingsCommand::Show(client) => {
+                let query = montage_client::settings::SettingsQuery::build(());
+
+                let settings = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .ok_or(eyre!("data was null"))?
+                    .settings;
+
+                (
+                    settings.short_break_cutoff,
+                    settings.set_size,
+                    settings.long_break_duration,
+                )
+            }
+            SettingsCommand::Update {
+                short_break_cutoff,
+                set_size,
+                long_break_duration,
+                client,
+            } => {
+                let query = montage_client::update_settings::UpdateSettingsMutation::build(
+                    montage_client::update_settings::UpdateSettingsMutationVariables {
+                        short_break_cutoff: short_break_cutoff.map(Self::minutes).transpose()?,
+                        set_size: *set_size,
+                        long_break_duration: long_break_duration.map(Self::minutes).transpose()?,
+                    },
+                );
+
+                let settings = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .ok_or(eyre!("data was null"))?
+                    .update_settings;
+
+                (
+                    settings.short_break_cutoff,
+                    settings.set_size,
+                    settings.long_break_duration,
+                )
+            }
+        };
+
+        println!(
+            "Short break cutoff: {} minutes",
+            Self::num_minutes(short_break_cutoff)?
+        );
+        println!("Set size: {} task sessions", set_size);
+        println!(
+            "Long break duration: {} minutes",
+            Self::num_minutes(long_break_duration)?
+        );
+
+        Ok(())
+    }
+
+    fn minutes(minutes: usize) -> Result<iso8601::Duration> {
+        iso8601::duration(&format!("PT{}M", minutes))
+            .map_err(|err| eyre!("could not parse duration: {err}"))
+    }
+
+    fn num_minutes(duration: iso8601::Duration) -> Result<i64> {
+        Ok(Duration::from_std(std::time::Duration::from(duration))
+            .wrap_err("could not parse duration")?
+            .num_minutes())
+    }
+}
@@ -0,0 +1,105 @@
+use chrono::Local;
+use color_eyre::eyre::Result;
+use montage_server::{ApiToken, User};
+use sqlx::{Pool, Sqlite};
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Parser)]
+pub struct Token {
+    #[command(subcommand)]
+    command: TokenCommand,
+
+    /// Where the server's database is stored. Tokens are managed in the database directly, so
+    /// this works whether or not the server is running (and without needing a token yourself.)
+    #[arg(long, env = "MONTAGE_DB", global = true)]
+    pub db_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum TokenCommand {
+    /// Make a new token. Once any token exists, the server requires one on every request.
+    Create {
+        /// What the token is for (for example, "laptop" or "xbar")
+        name: String,
+
+        /// Which user requests with this token are from (see `montage user list`)
+        #[arg(long, default_value = "default")]
+        user: String,
+    },
+
+    /// Show all the tokens (but not the tokens themselves, which are only shown when created)
+    List,
+
+    /// Stop a token from working
+    Revoke {
+        /// The ID of the token (shown in `montage token list`)
+        id: i64,
+    },
+}
+
+impl Token {
+    pub async fn run(&self, pool: Pool<Sqlite>) -> Result<()> {
+        montage_server::migrate(&pool).await?;
+
+        match &self.command {
+            TokenCommand::Create { name, user } => {
+                let user = User::by_name(&pool, user).await?;
+                let (api_token, token) =
+                    ApiToken::create(&pool, user.id, name, chrono::Utc::now()).await?;
+
+                println!(
+                    "Created token #{} ({}) for {}\n",
+                    api_token.id, api_token.name, user.name
+                );
+                println!("{token}\n");
+                println!("This is the only time it will be shown. Pass it with --token or MONTAGE_TOKEN.");
+            }
+            TokenCommand::List => {
+                let tokens = ApiToken::all(&pool).await?;
+                let users = User::all(&pool).await?;
+
+                if tokens.is_empty() {
+                    println!("No tokens yet, so the server doesn't require one");
+                }
+
+                for api_token in tokens {
+                    println!(
+                        "#{}: {} for {} (created {}, {}{})",
+                        api_token.id,
+                        api_token.name,
+                        users
+                            .iter()
+                            .find(|user| user.id == api_token.user_id)
+                            .map_or("an unknown user", |user| user.name.as_str()),
+                        super::Opts::humanize_time_12hr(api_token.created_at.with_timezone(&Local)),
+                        match api_token.last_used_at {
+                            Some(used) => format!(
+                                "last used {}",
+                                super::Opts::humanize_time_12hr(used.with_timezone(&Local))
+                            ),
+                            None => String::from("never used"),
+                        },
+                        match api_token.revoked_at {
+                            Some(revoked) => format!(
+                                ", revoked {}",
+                                super::Opts::humanize_time_12hr(revoked.with_timezone(&Local))
+                            ),
+                            None => String::new(),
+                        },
+                    );
+                }
+            }
+            TokenCommand::Revoke { id } => {
+                let api_token = ApiToken::revoke(&pool, *id, chrono::Utc::now()).await?;
+
+                println!("Revoked token #{} ({})", api_token.id, api_token.name);
+            }
+        }
+
+        // writes that return rows aren't committed until sqlx is done with the statement, so
+        // make sure that happens before we exit.
+        pool.close().await;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+use chrono::Local;
+use color_eyre::eyre::Result;
+use montage_server::{ApiToken, User as ServerUser};
+use sqlx::{Pool, Sqlite};
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Parser)]
+pub struct User {
+    #[command(subcommand)]
+    command: UserCommand,
+
+    /// Where the server's database is stored. Like tokens, users are managed in the database
+    /// directly, so this works whether or not the server is running.
+    #[arg(long, env = "MONTAGE_DB", global = true)]
+    pub db_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum UserCommand {
+    /// Add a user, along with a token for them to connect with. Each user has their own sessions,
+    /// queue, goals, and webhooks. If this is the first token, the default user gets one too.
+    Add {
+        /// What to call the user
+        name: String,
+    },
+
+    /// Show everyone who uses the server
+    List,
+}
+
+impl User {
+    pub async fn run(&self, pool: Pool<Sqlite>) -> Result<()> {
+        montage_server::migrate(&pool).await?;
+
+        match &self.command {
+            UserCommand::Add { name } => {
+                let now = chrono::Utc::now();
+
+                // the first token turns on token checks, which would lock out everyone already
+                // using the server as the default user, so they get a token too.
+                let default_user = if ApiToken::is_required(&pool).await? {
+                    None
+                } else {
+                    Some(ServerUser::by_name(&pool, "default").await?)
+                };
+
+                let user = ServerUser::create(&pool, name, now).await?;
+                let (api_token, token) = ApiToken::create(&pool, user.id, name, now).await?;
+
+                println!(
+                    "Added user #{} ({}) with token #{}\n",
+                    user.id, user.name, api_token.id
+                );
+                println!("{token}\n");
+
+                if let Some(default_user) = default_user {
+                    let (api_token, token) =
+                        ApiToken::create(&pool, default_user.id, &default_user.name, now).await?;
+
+                    println!(
+                        "The server requires a token from now on, so here's token #{} for {} (existing clients need it to keep working):\n",
+                        api_token.id, default_user.name
+                    );
+                    println!("{token}\n");
+                }
+
+                println!("Tokens are only shown once. Pass them with --token or MONTAGE_TOKEN.");
+            }
+            UserCommand::List => {
+                for user in ServerUser::all(&pool).await? {
+                    println!(
+                        "#{}: {} (added {})",
+                        user.id,
+                        user.name,
+                        super::Opts::humanize_time_12hr(user.created_at.with_timezone(&Local)),
+                    );
+                }
+            }
+        }
+
+        // writes that return rows aren't committed until sqlx is done with the statement, so
+        // make sure that happens before we exit.
+        pool.close().await;
+
+        Ok(())
+    }
+}
@@ -3,11 +3,11 @@ use super::TokioSpawner;
 use chrono::{DateTime, Local};
 use clap::Parser;
 use color_eyre::eyre::{bail, Result, WrapErr};
-use cynic::SubscriptionBuilder;
+use cynic::{QueryBuilder, SubscriptionBuilder};
 use futures::StreamExt;
 use graphql_ws_client::CynicClientBuilder;
+use montage_client::current_session_updates::CurrentSessionUpdates;
 use montage_client::current_session_updates::Session;
-use montage_client::current_session_updates::{CurrentSessionUpdates, Kind};
 use rand::{rngs::ThreadRng, seq::SliceRandom};
 use std::collections::HashSet;
 use std::path::PathBuf;
@@ -69,6 +69,9 @@ struct Vexer<'config> {
 
     sent_session_ended: bool,
     current_work_session_started: Option<DateTime<Local>>,
+
+    /// The description of the next session in the queue, if there is one
+    next_planned: Option<String>,
 }
 
 impl<'config> Vexer<'config> {
@@ -87,6 +90,7 @@ impl<'config> Vexer<'config> {
             reminders_given: HashSet::with_capacity(config.reminder_at.len()),
             sent_session_ended: false,
             current_work_session_started: None,
+            next_planned: None,
         }
     }
 
@@ -104,14 +108,10 @@ impl<'config> Vexer<'config> {
             }
             tokio::time::sleep(self.backoff).await;
 
-            let (connection, _) = match async_tungstenite::tokio::connect_async(
-                self.config.client.request()?,
-            )
-            .await
-            {
+            let connection = match self.config.client.connect_websocket().await {
                 Ok(conn) => conn,
                 Err(err) => {
-                    tracing::error!(err = err.to_string(), "could not connect");
+                    tracing::error!(err = ?err, "could not connect");
                     self.increment_backoff();
                     continue;
                 }
@@ -119,6 +119,7 @@ impl<'config> Vexer<'config> {
 
             let (sink, stream) = connection.split();
             let mut client = CynicClientBuilder::new()
+                .payload(self.config.client.connection_init_payload())
                 .build(stream, sink, TokioSpawner::current())
                 .await
                 .wrap_err("could not construct a Cynic client")?;
@@ -184,15 +185,20 @@ impl<'config> Vexer<'config> {
         session_opt: Option<montage_client::current_session_updates::Session>,
     ) -> Result<()> {
         let mut same_session = false;
+        let mut extended = false;
 
         if let Some(old_session) = &self.session {
-            if let Some(session) = &session_opt {
-                if session.id == old_session.id {
+            match &session_opt {
+                Some(session) if session.id == old_session.id => {
                     same_session = true;
-                } else {
+                    // the same session comes back for all kinds of reasons (notes, outcomes,
+                    // resuming) but it's only been extended if it's longer.
+                    extended = session.duration != old_session.duration;
+                }
+                next_session => {
                     self.run_script(Script::SessionEnded {
                         session: old_session,
-                        next_session: session,
+                        next_session: next_session.as_ref(),
                     })
                     .await?;
                 }
@@ -201,24 +207,28 @@ impl<'config> Vexer<'config> {
 
         self.session = session_opt;
         self.sent_session_ended = false;
+        self.refresh_next_planned().await;
 
         if let Some(session) = &self.session {
-            self.run_script(if same_session {
-                Script::SessionExtended { session }
-            } else {
-                Script::NewSession { session }
-            })
-            .await?;
-
-            match session.kind {
-                Kind::Task | Kind::Meeting => {
-                    if self.current_work_session_started.is_none() {
-                        self.current_work_session_started = Some(Local::now());
-                    }
-                }
+            if session.is_paused {
+                // there's nothing to say about a paused session. We'll pick up the reminders
+                // again once it's resumed.
+                return Ok(());
+            }
 
-                Kind::Break | Kind::Offline => self.current_work_session_started = None,
-            };
+            if !same_session {
+                self.run_script(Script::NewSession { session }).await?;
+            } else if extended {
+                self.run_script(Script::SessionExtended { session }).await?;
+            }
+
+            if session.kind_details.is_working {
+                if self.current_work_session_started.is_none() {
+                    self.current_work_session_started = Some(Local::now());
+                }
+            } else {
+                self.current_work_session_started = None;
+            }
 
             let time_remaining = session.projected_end_time - Local::now();
 
@@ -229,13 +239,37 @@ impl<'config> Vexer<'config> {
                 }
             });
             tracing::info!(reminders=?self.reminders_to_give.difference(&self.reminders_given), "reset reminders");
+        } else {
+            // nothing is running, so there's nothing to remind about and the work session is over.
+            self.current_work_session_started = None;
+            self.reminders_given.clear();
         }
 
         Ok(())
     }
 
+    /// Find out what's next in the queue so we can mention it when the session is over. This is
+    /// only nice to have, so errors are logged instead of returned.
+    async fn refresh_next_planned(&mut self) {
+        let query = montage_client::queue::QueueQuery::build(());
+
+        match self.config.client.make_graphql_request(query).await {
+            Ok(resp) => {
+                self.next_planned = resp
+                    .data
+                    .and_then(|data| data.queue.into_iter().next())
+                    .map(|planned| planned.description)
+            }
+            Err(err) => tracing::warn!(err = ?err, "could not get the queue"),
+        }
+    }
+
     async fn tick(&mut self) -> Result<()> {
         if let Some(session) = &self.session {
+            if session.is_paused {
+                return Ok(());
+            }
+
             let time_remaining = session.projected_end_time - Local::now();
 
             for reminder in &self.reminders_to_give {
@@ -278,8 +312,13 @@ impl<'config> Vexer<'config> {
 
     async fn annoy(&mut self) -> Result<()> {
         if let Some(session) = &self.session {
-            let options = match session.kind {
-                Kind::Task => {
+            let options = match (session.kind_details.nags, session.kind_details.is_working) {
+                // We don't annoy when quiet kinds of sessions (like meetings) end because
+                // sometimes they run long and it's awkward to have the computer start saying silly
+                // things on Zoom!
+                (false, _) => vec![],
+
+                (true, true) => {
                     let mut options = vec![String::from("hey")];
                     options.push(self.config.your_name.clone());
 
@@ -296,10 +335,20 @@ impl<'config> Vexer<'config> {
                         options.push(String::from("need another couple minutes?"));
                     }
 
+                    if let Some(next) = &self.next_planned {
+                        options.push(format!("next up: {next}"));
+                    }
+
+                    // the cycle was loaded when this session started, so it doesn't count this
+                    // session yet.
+                    if session.cycle.completed_in_set + 1 >= session.cycle.set_size {
+                        options.push(String::from("that's a full set. Time for a long break!"));
+                    }
+
                     options
                 }
 
-                Kind::Break | Kind::Offline => {
+                (true, false) => {
                     let mut options = vec![
                         String::from("hey"),
                         String::from("pick a new task"),
@@ -308,12 +357,24 @@ impl<'config> Vexer<'config> {
                     ];
                     options.push(self.config.your_name.clone());
 
+                    if let Some(next) = &self.next_planned {
+                        options.push(format!("time for {next}"));
+                    }
+
+                    if session.kind_details.is_break {
+                        let left = session.cycle.set_size - session.cycle.completed_in_set;
+
+                        if session.cycle.long_break_due {
+                            options.push(String::from("new set, fresh start!"));
+                        } else if left == 1 {
+                            options.push(String::from("one more task until a long break"));
+                        } else {
+                            options.push(format!("{left} more tasks until a long break"));
+                        }
+                    }
+
                     options
                 }
-
-                // We don't annoy when meetings end because sometimes they run long and it's
-                // awkward to have the computer start saying silly things on Zoom!
-                Kind::Meeting => vec![],
             };
 
             if let Some(what_to_say) = options.choose(&mut self.rng) {
@@ -325,7 +386,7 @@ impl<'config> Vexer<'config> {
     }
 
     async fn say(&self, what_to_say: &str) -> Result<()> {
-        if self.in_meeting() {
+        if self.in_quiet_session() {
             return Ok(());
         }
 
@@ -343,10 +404,11 @@ impl<'config> Vexer<'config> {
         Ok(())
     }
 
-    fn in_meeting(&self) -> bool {
-        match self.session {
-            Some(Session { kind, .. }) => kind == Kind::Meeting,
-            _ => false,
+    /// Are we in a session whose kind says the vexer shouldn't nag (for example, a meeting)?
+    fn in_quiet_session(&self) -> bool {
+        match &self.session {
+            Some(session) => !session.kind_details.nags,
+            None => false,
         }
     }
 
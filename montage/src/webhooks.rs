@@ -0,0 +1,155 @@
+use super::graphql_client::GraphQLClientOptions;
+use color_eyre::eyre::{eyre, Result};
+use cynic::{MutationBuilder, QueryBuilder};
+
+#[derive(Debug, clap::Parser)]
+pub struct Webhooks {
+    #[command(subcommand)]
+    command: WebhooksCommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum WebhooksCommand {
+    /// Send a JSON payload to a URL whenever a session starts, gets extended, ends, or runs over
+    /// time
+    Add {
+        /// Where to send payloads
+        url: String,
+
+        /// A key to sign payloads with. Receivers can check the `X-Montage-Signature` header
+        /// (`sha256=` and a hex-encoded HMAC-SHA256 of the body) to make sure they came from us.
+        #[arg(long, env = "MONTAGE_WEBHOOK_SECRET")]
+        secret: String,
+
+        #[command(flatten)]
+        client: GraphQLClientOptions,
+    },
+
+    /// Show the webhooks we're sending payloads to
+    List(GraphQLClientOptions),
+
+    /// Stop sending payloads to a webhook
+    Remove {
+        /// The ID of the webhook (shown in `montage webhooks list`)
+        id: i32,
+
+        #[command(flatten)]
+        client: GraphQLClientOptions,
+    },
+
+    /// Show recent attempts to deliver payloads, newest first
+    Deliveries {
+        /// Only show deliveries to this webhook
+        #[arg(long)]
+        webhook: Option<i32>,
+
+        /// How many deliveries to show
+        #[arg(long, default_value = "20")]
+        limit: i32,
+
+        #[command(flatten)]
+        client: GraphQLClientOptions,
+    },
+}
+
+impl Webhooks {
+    pub async fn run(&self) -> Result<()> {
+        match &self.command {
+            WebhooksCommand::Add {
+                url,
+                secret,
+                client,
+            } => {
+                let query = montage_client::create_webhook::CreateWebhookMutation::build(
+                    montage_client::create_webhook::CreateWebhookMutationVariables { url, secret },
+                );
+
+                let webhook = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .ok_or(eyre!("data was null"))?
+                    .create_webhook;
+
+                println!("Added webhook #{} for {}", webhook.id, webhook.url);
+            }
+            WebhooksCommand::List(client) => {
+                let query = montage_client::webhooks::WebhooksQuery::build(());
+
+                let webhooks = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .ok_or(eyre!("data was null"))?
+                    .webhooks;
+
+                if webhooks.is_empty() {
+                    println!("No webhooks set up");
+                }
+
+                for webhook in webhooks {
+                    println!(
+                        "#{}: {} (added {})",
+                        webhook.id,
+                        webhook.url,
+                        super::Opts::humanize_time_12hr(webhook.created_at),
+                    );
+                }
+            }
+            WebhooksCommand::Remove { id, client } => {
+                let query = montage_client::delete_webhook::DeleteWebhookMutation::build(
+                    montage_client::delete_webhook::DeleteWebhookMutationVariables { id: *id },
+                );
+
+                let removed = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .ok_or(eyre!("data was null"))?
+                    .delete_webhook;
+
+                println!("Removed webhook #{} for {}", removed.id, removed.url);
+            }
+            WebhooksCommand::Deliveries {
+                webhook,
+                limit,
+                client,
+            } => {
+                let query = montage_client::webhook_deliveries::WebhookDeliveriesQuery::build(
+                    montage_client::webhook_deliveries::WebhookDeliveriesQueryVariables {
+                        webhook_id: *webhook,
+                        limit: *limit,
+                    },
+                );
+
+                let deliveries = client
+                    .make_graphql_request(query)
+                    .await?
+                    .data
+                    .ok_or(eyre!("data was null"))?
+                    .webhook_deliveries;
+
+                if deliveries.is_empty() {
+                    println!("No deliveries yet");
+                }
+
+                for delivery in deliveries {
+                    println!(
+                        "{} {} to #{} (attempt {}): {}",
+                        super::Opts::humanize_time_12hr(delivery.attempted_at),
+                        delivery.event,
+                        delivery.webhook_id,
+                        delivery.attempt,
+                        if delivery.succeeded {
+                            String::from("delivered")
+                        } else {
+                            delivery.error.unwrap_or_else(|| String::from("failed"))
+                        },
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,9 +1,8 @@
 use super::graphql_client::GraphQLClientOptions;
 use chrono::Duration;
 use color_eyre::eyre::{Result, WrapErr};
-use cynic::http::{CynicReqwestError, ReqwestExt};
 use cynic::QueryBuilder;
-use montage_client::current_session::{Kind, Session};
+use montage_client::current_session::Session;
 
 #[derive(Debug, clap::Parser)]
 pub struct XBar {
@@ -13,37 +12,37 @@ pub struct XBar {
 
 impl XBar {
     pub async fn run(&self) -> Result<()> {
-        let http_client = reqwest::Client::new();
-
         let query = montage_client::current_session::CurrentSessionQuery::build(());
 
-        match http_client
-            .post(self.client_options.endpoint())
-            .run_graphql(query)
-            .await
-        {
-            Err(CynicReqwestError::ReqwestError(err)) if err.is_connect() => {
+        match self.client_options.make_graphql_request(query).await {
+            Err(err) if Self::is_connection_error(&err) => {
                 // a message for the xbar status line
                 eprintln!("⚠️ failed to connect to server");
 
                 // a message to expand on
-                return Err(err).wrap_err("GraphQL request failed");
-            }
-            Err(err) => return Err(err).wrap_err("GraphQL request failed"),
-            Ok(resp) => {
-                let session = resp
-                    .data
-                    .expect("a non-null session")
-                    .current_session
-                    .expect("a current session");
-
-                println!("{}", Self::format(&session)?);
+                return Err(err);
             }
+            Err(err) => return Err(err),
+            Ok(resp) => match resp.data.expect("a non-null response").current_session {
+                Some(session) => println!("{}", Self::format(&session)?),
+                None => println!("⏹ no session"),
+            },
         };
 
         Ok(())
     }
 
+    /// Whether we couldn't reach the server at all, as opposed to the server not liking what we
+    /// asked for.
+    fn is_connection_error(err: &color_eyre::eyre::Error) -> bool {
+        err.chain().any(|cause| {
+            cause
+                .downcast_ref::<reqwest::Error>()
+                .is_some_and(|err| err.is_connect())
+                || cause.is::<std::io::Error>()
+        })
+    }
+
     fn format(session: &Session) -> Result<String> {
         let duration = Duration::from_std(std::time::Duration::from(
             session.remaining_time.expect("remaining time"),
@@ -68,13 +67,11 @@ impl XBar {
     }
 
     fn emoji(session: &Session) -> String {
-        match session.kind {
-            Kind::Task => "⏰",
-            Kind::Break => "☕️",
-            Kind::Meeting => "🗣",
-            Kind::Offline => "💤",
+        if session.is_paused {
+            return "⏸".to_string();
         }
-        .to_string()
+
+        session.kind_details.emoji.clone()
     }
 }
 
@@ -92,14 +89,39 @@ mod tests {
             // none of the rest of these are coherent. Don't worry about it.
             duration: iso8601::duration("PT5M").unwrap(),
             end_time: None,
-            kind: Kind::Task,
+            kind: String::from("task"),
+            kind_details: Kind {
+                emoji: String::from("⏰"),
+            },
             projected_end_time: Local::now(),
             remaining_time: Some(iso8601::duration("PT5M").unwrap()),
             start_time: Local::now(),
+            is_paused: false,
         };
 
         let formatted = XBar::format(&session).unwrap();
 
         assert_eq!(formatted.lines().next().unwrap(), "⏰ A \\| B \\| C (5:00)")
     }
+
+    #[test]
+    fn format_shows_paused_sessions() {
+        let session = Session {
+            description: String::from("A"),
+            duration: iso8601::duration("PT5M").unwrap(),
+            end_time: None,
+            kind: String::from("task"),
+            kind_details: Kind {
+                emoji: String::from("⏰"),
+            },
+            projected_end_time: Local::now(),
+            remaining_time: Some(iso8601::duration("PT5M").unwrap()),
+            start_time: Local::now(),
+            is_paused: true,
+        };
+
+        let formatted = XBar::format(&session).unwrap();
+
+        assert_eq!(formatted.lines().next().unwrap(), "⏸ A (5:00)")
+    }
 }
@@ -0,0 +1,37 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryVariables, Debug)]
+pub struct AddNoteMutationVariables<'a> {
+    pub text: &'a str,
+    pub session_id: Option<i32>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Mutation", variables = "AddNoteMutationVariables")]
+pub struct AddNoteMutation {
+    #[arguments(text: $text, sessionId: $session_id)]
+    pub add_note: Session,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Session {
+    pub id: i32,
+    pub description: String,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::MutationBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = AddNoteMutation::build(AddNoteMutationVariables {
+            text: "fixed the flaky test",
+            session_id: None,
+        });
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
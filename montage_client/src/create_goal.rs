@@ -0,0 +1,57 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryVariables, Debug)]
+pub struct CreateGoalMutationVariables<'a> {
+    pub kind: Option<&'a str>,
+    pub tag: Option<&'a str>,
+    pub period: GoalPeriod,
+    pub target: Duration,
+    pub bound: GoalBound,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Mutation", variables = "CreateGoalMutationVariables")]
+pub struct CreateGoalMutation {
+    #[arguments(kind: $kind, tag: $tag, period: $period, target: $target, bound: $bound)]
+    pub create_goal: Goal,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Goal {
+    pub id: i32,
+}
+
+#[derive(cynic::Enum, Clone, Copy, Debug)]
+pub enum GoalPeriod {
+    Day,
+    Week,
+}
+
+#[derive(cynic::Enum, Clone, Copy, Debug)]
+pub enum GoalBound {
+    Min,
+    Max,
+}
+
+type Duration = iso8601::Duration;
+cynic::impl_scalar!(Duration, schema::Duration);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::MutationBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = CreateGoalMutation::build(CreateGoalMutationVariables {
+            kind: Some("task"),
+            tag: None,
+            period: GoalPeriod::Day,
+            target: iso8601::duration("PT4H").unwrap(),
+            bound: GoalBound::Min,
+        });
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
@@ -0,0 +1,49 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryVariables, Debug)]
+pub struct CreateKindMutationVariables<'a> {
+    pub name: &'a str,
+    pub default_duration: Duration,
+    pub is_working: bool,
+    pub nags: bool,
+    pub is_break: bool,
+    pub ends_set: bool,
+    pub emoji: &'a str,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Mutation", variables = "CreateKindMutationVariables")]
+pub struct CreateKindMutation {
+    #[arguments(name: $name, defaultDuration: $default_duration, isWorking: $is_working, nags: $nags, isBreak: $is_break, endsSet: $ends_set, emoji: $emoji)]
+    pub create_kind: Kind,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Kind {
+    pub name: String,
+}
+
+type Duration = iso8601::Duration;
+cynic::impl_scalar!(Duration, schema::Duration);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::MutationBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = CreateKindMutation::build(CreateKindMutationVariables {
+            name: "pairing",
+            default_duration: iso8601::duration("PT50M").unwrap(),
+            is_working: true,
+            nags: false,
+            is_break: false,
+            ends_set: false,
+            emoji: "👯",
+        });
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
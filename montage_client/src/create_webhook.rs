@@ -0,0 +1,40 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryVariables, Debug)]
+pub struct CreateWebhookMutationVariables<'a> {
+    pub url: &'a str,
+    pub secret: &'a str,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(
+    graphql_type = "Mutation",
+    variables = "CreateWebhookMutationVariables"
+)]
+pub struct CreateWebhookMutation {
+    #[arguments(url: $url, secret: $secret)]
+    pub create_webhook: Webhook,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Webhook {
+    pub id: i32,
+    pub url: String,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::MutationBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = CreateWebhookMutation::build(CreateWebhookMutationVariables {
+            url: "https://example.com/hook",
+            secret: "hunter2",
+        });
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
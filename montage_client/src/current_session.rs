@@ -12,18 +12,17 @@ pub struct Session {
     pub description: String,
     pub duration: Duration,
     pub end_time: Option<DateTime>,
-    pub kind: Kind,
+    pub kind: String,
+    pub kind_details: Kind,
     pub projected_end_time: DateTime,
     pub remaining_time: Option<Duration>,
+    pub is_paused: bool,
     pub start_time: DateTime,
 }
 
-#[derive(cynic::Enum, Clone, Copy, Debug)]
-pub enum Kind {
-    Task,
-    Break,
-    Meeting,
-    Offline,
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Kind {
+    pub emoji: String,
 }
 
 type DateTime = chrono::DateTime<chrono::Local>;
@@ -1,5 +1,3 @@
-use std::fmt::Display;
-
 #[cynic::schema("montage")]
 mod schema {}
 
@@ -14,35 +12,27 @@ pub struct Session {
     pub id: i32,
     pub description: String,
     pub duration: Duration,
-    pub kind: Kind,
+    pub kind: String,
+    pub kind_details: Kind,
     pub start_time: DateTime,
     pub projected_end_time: DateTime,
     pub remaining_time: Option<Duration>,
+    pub is_paused: bool,
+    pub cycle: Cycle,
 }
 
-impl Session {
-    pub fn is_meeting(&self) -> bool {
-        self.kind == Kind::Meeting
-    }
-}
-
-#[derive(cynic::Enum, Clone, Copy, Debug, PartialEq, Eq)]
-pub enum Kind {
-    Task,
-    Break,
-    Meeting,
-    Offline,
+#[derive(cynic::QueryFragment, Debug, serde::Serialize)]
+pub struct Kind {
+    pub is_working: bool,
+    pub nags: bool,
+    pub is_break: bool,
 }
 
-impl Display for Kind {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Task => f.write_str("task"),
-            Self::Break => f.write_str("break"),
-            Self::Meeting => f.write_str("meeeting"),
-            Self::Offline => f.write_str("offline"),
-        }
-    }
+#[derive(cynic::QueryFragment, Debug, serde::Serialize)]
+pub struct Cycle {
+    pub completed_in_set: i32,
+    pub set_size: i32,
+    pub long_break_due: bool,
 }
 
 type DateTime = chrono::DateTime<chrono::Local>;
@@ -0,0 +1,32 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Query")]
+pub struct CycleQuery {
+    pub cycle: Cycle,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Cycle {
+    pub completed_in_set: i32,
+    pub set_size: i32,
+    pub long_break_due: bool,
+    pub next_break_suggestion: Duration,
+}
+
+type Duration = iso8601::Duration;
+cynic::impl_scalar!(Duration, schema::Duration);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::QueryBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = CycleQuery::build(());
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
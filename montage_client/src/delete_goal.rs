@@ -0,0 +1,32 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryVariables, Debug)]
+pub struct DeleteGoalMutationVariables {
+    pub id: i32,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Mutation", variables = "DeleteGoalMutationVariables")]
+pub struct DeleteGoalMutation {
+    #[arguments(id: $id)]
+    pub delete_goal: Goal,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Goal {
+    pub id: i32,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::MutationBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = DeleteGoalMutation::build(DeleteGoalMutationVariables { id: 1 });
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
@@ -0,0 +1,40 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryVariables, Debug)]
+pub struct DeleteSessionMutationVariables {
+    pub id: i32,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(
+    graphql_type = "Mutation",
+    variables = "DeleteSessionMutationVariables"
+)]
+pub struct DeleteSessionMutation {
+    #[arguments(id: $id)]
+    pub delete_session: Session,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Session {
+    pub id: i32,
+    pub description: String,
+    pub start_time: DateTime,
+}
+
+type DateTime = chrono::DateTime<chrono::Local>;
+cynic::impl_scalar!(DateTime, schema::DateTime);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::MutationBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = DeleteSessionMutation::build(DeleteSessionMutationVariables { id: 1 });
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
@@ -0,0 +1,36 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryVariables, Debug)]
+pub struct DeleteWebhookMutationVariables {
+    pub id: i32,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(
+    graphql_type = "Mutation",
+    variables = "DeleteWebhookMutationVariables"
+)]
+pub struct DeleteWebhookMutation {
+    #[arguments(id: $id)]
+    pub delete_webhook: Webhook,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Webhook {
+    pub id: i32,
+    pub url: String,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::MutationBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = DeleteWebhookMutation::build(DeleteWebhookMutationVariables { id: 1 });
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
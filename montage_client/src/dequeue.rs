@@ -0,0 +1,32 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryVariables, Debug)]
+pub struct DequeueMutationVariables {
+    pub id: i32,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Mutation", variables = "DequeueMutationVariables")]
+pub struct DequeueMutation {
+    #[arguments(id: $id)]
+    pub dequeue: PlannedSession,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct PlannedSession {
+    pub description: String,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::MutationBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = DequeueMutation::build(DequeueMutationVariables { id: 1 });
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
@@ -0,0 +1,45 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryVariables, Debug)]
+pub struct EnqueueMutationVariables<'a> {
+    pub description: &'a str,
+    pub kind: &'a str,
+    pub duration: Option<Duration>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Mutation", variables = "EnqueueMutationVariables")]
+pub struct EnqueueMutation {
+    #[arguments(description: $description, kind: $kind, duration: $duration)]
+    pub enqueue: PlannedSession,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct PlannedSession {
+    pub id: i32,
+    pub position: i32,
+    pub kind: String,
+    pub description: String,
+    pub duration: Option<Duration>,
+}
+
+type Duration = iso8601::Duration;
+cynic::impl_scalar!(Duration, schema::Duration);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::MutationBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = EnqueueMutation::build(EnqueueMutationVariables {
+            description: "write RFC",
+            kind: "task",
+            duration: Some(iso8601::duration("PT50M").unwrap()),
+        });
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
@@ -0,0 +1,87 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryVariables, Debug)]
+pub struct EventsVariables {
+    pub reminder_at: Option<Vec<Duration>>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Subscription", variables = "EventsVariables")]
+pub struct Events {
+    #[arguments(reminderAt: $reminder_at)]
+    pub events: Event,
+}
+
+#[derive(cynic::InlineFragments, Debug)]
+pub enum Event {
+    SessionStarted(SessionStarted),
+    SessionExtended(SessionExtended),
+    SessionEnded(SessionEnded),
+    SessionOverTime(SessionOverTime),
+    ReminderDue(ReminderDue),
+    #[cynic(fallback)]
+    Unknown,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct SessionStarted {
+    pub session: Session,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct SessionExtended {
+    pub session: Session,
+    pub previous_duration: Duration,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct SessionEnded {
+    pub session: Session,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct SessionOverTime {
+    pub session: Session,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct ReminderDue {
+    pub session: Session,
+    pub remaining_time: Duration,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Session {
+    pub id: i32,
+    pub description: String,
+    pub kind: String,
+    pub duration: Duration,
+    pub projected_end_time: DateTime,
+    pub end_time: Option<DateTime>,
+}
+
+type DateTime = chrono::DateTime<chrono::Local>;
+cynic::impl_scalar!(DateTime, schema::DateTime);
+
+type Duration = iso8601::Duration;
+cynic::impl_scalar!(Duration, schema::Duration);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn gql_output() {
+        // `SubscriptionBuilder` gives us a `StreamingOperation`, which doesn't let us see the
+        // query, so we build a plain operation instead.
+        let operation = cynic::OperationBuilder::<Events, EventsVariables>::subscription()
+            .with_variables(EventsVariables {
+                reminder_at: Some(vec![iso8601::duration("PT5M").unwrap()]),
+            })
+            .build()
+            .unwrap();
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
@@ -0,0 +1,93 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryVariables, Debug)]
+pub struct ExportQueryVariables {
+    pub start: DateTime,
+    pub end: DateTime,
+    pub time_zone: Option<TimeZone>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Query", variables = "ExportQueryVariables")]
+pub struct ExportQuery {
+    #[arguments(end: $end, start: $start, timeZone: $time_zone)]
+    pub report: Report,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Report {
+    pub sessions: Vec<Session>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Session {
+    pub id: i32,
+    pub kind: String,
+    pub description: String,
+    pub start_time: DateTime,
+    pub end_time: Option<DateTime>,
+    pub projected_end_time: DateTime,
+    pub duration: Duration,
+    pub actual_duration: Duration,
+    pub paused_duration: Duration,
+    pub time_zone: TimeZone,
+    pub outcome: Outcome,
+    pub tags: Vec<String>,
+    pub pauses: Vec<Pause>,
+    pub notes: Vec<Note>,
+    pub interruptions: Vec<Interruption>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Pause {
+    pub start_time: DateTime,
+    pub end_time: Option<DateTime>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Note {
+    pub created_at: DateTime,
+    pub text: String,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Interruption {
+    pub created_at: DateTime,
+    pub source: String,
+    pub note: Option<String>,
+}
+
+#[derive(cynic::Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Completed,
+    Partial,
+    Abandoned,
+    Unknown,
+}
+
+type DateTime = chrono::DateTime<chrono::Local>;
+cynic::impl_scalar!(DateTime, schema::DateTime);
+
+type Duration = iso8601::Duration;
+cynic::impl_scalar!(Duration, schema::Duration);
+
+#[derive(cynic::Scalar, Debug, Clone, PartialEq, Eq)]
+pub struct TimeZone(pub String);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::QueryBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = ExportQuery::build(ExportQueryVariables {
+            start: chrono::Local::now(),
+            end: chrono::Local::now(),
+            time_zone: Some(TimeZone(String::from("America/Chicago"))),
+        });
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
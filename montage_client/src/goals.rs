@@ -0,0 +1,64 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryVariables, Debug)]
+pub struct GoalsQueryVariables {
+    pub time_zone: Option<TimeZone>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Query", variables = "GoalsQueryVariables")]
+pub struct GoalsQuery {
+    #[arguments(timeZone: $time_zone)]
+    pub goals: Vec<GoalProgress>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct GoalProgress {
+    pub goal: Goal,
+    pub actual: Duration,
+    pub met: bool,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Goal {
+    pub id: i32,
+    pub kind: Option<String>,
+    pub tag: Option<String>,
+    pub period: GoalPeriod,
+    pub target: Duration,
+    pub bound: GoalBound,
+}
+
+#[derive(cynic::Enum, Clone, Copy, Debug)]
+pub enum GoalPeriod {
+    Day,
+    Week,
+}
+
+#[derive(cynic::Enum, Clone, Copy, Debug)]
+pub enum GoalBound {
+    Min,
+    Max,
+}
+
+type Duration = iso8601::Duration;
+cynic::impl_scalar!(Duration, schema::Duration);
+
+#[derive(cynic::Scalar, Debug)]
+pub struct TimeZone(pub String);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::QueryBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = GoalsQuery::build(GoalsQueryVariables {
+            time_zone: Some(TimeZone(String::from("America/Chicago"))),
+        });
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
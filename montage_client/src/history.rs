@@ -0,0 +1,37 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryVariables, Debug)]
+pub struct HistoryQueryVariables {
+    pub session_id: i32,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Query", variables = "HistoryQueryVariables")]
+pub struct HistoryQuery {
+    #[arguments(sessionId: $session_id)]
+    pub history: Vec<AuditEvent>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct AuditEvent {
+    pub mutation: String,
+    pub arguments: String,
+    pub created_at: DateTime,
+}
+
+type DateTime = chrono::DateTime<chrono::Local>;
+cynic::impl_scalar!(DateTime, schema::DateTime);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::QueryBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = HistoryQuery::build(HistoryQueryVariables { session_id: 1 });
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
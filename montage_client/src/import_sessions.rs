@@ -0,0 +1,117 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryVariables, Debug)]
+pub struct ImportSessionsMutationVariables {
+    pub sessions: Vec<ImportedSession>,
+    pub on_overlap: OnOverlap,
+    pub dry_run: bool,
+}
+
+#[derive(cynic::InputObject, Debug, Clone, PartialEq)]
+pub struct ImportedSession {
+    pub kind: String,
+    pub description: String,
+    pub start_time: DateTime,
+    pub end_time: DateTime,
+    pub duration: Option<Duration>,
+    pub time_zone: Option<TimeZone>,
+    pub tags: Vec<String>,
+    pub outcome: Option<Outcome>,
+    pub pauses: Vec<ImportedPause>,
+    pub notes: Vec<ImportedNote>,
+    pub interruptions: Vec<ImportedInterruption>,
+}
+
+#[derive(cynic::InputObject, Debug, Clone, PartialEq)]
+pub struct ImportedPause {
+    pub start_time: DateTime,
+    pub end_time: DateTime,
+}
+
+#[derive(cynic::InputObject, Debug, Clone, PartialEq)]
+pub struct ImportedNote {
+    pub created_at: DateTime,
+    pub text: String,
+}
+
+#[derive(cynic::InputObject, Debug, Clone, PartialEq)]
+pub struct ImportedInterruption {
+    pub created_at: DateTime,
+    pub source: String,
+    pub note: Option<String>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(
+    graphql_type = "Mutation",
+    variables = "ImportSessionsMutationVariables"
+)]
+pub struct ImportSessionsMutation {
+    #[arguments(sessions: $sessions, onOverlap: $on_overlap, dryRun: $dry_run)]
+    pub import_sessions: ImportResult,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct ImportResult {
+    pub created: Vec<Session>,
+    pub skipped: Vec<SkippedSession>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Session {
+    pub id: i32,
+    pub kind: String,
+    pub description: String,
+    pub start_time: DateTime,
+    pub end_time: Option<DateTime>,
+    pub tags: Vec<String>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct SkippedSession {
+    pub row: i32,
+    pub description: String,
+    pub start_time: DateTime,
+    pub overlaps: i32,
+}
+
+#[derive(cynic::Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnOverlap {
+    Skip,
+    Reject,
+}
+
+#[derive(cynic::Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Completed,
+    Partial,
+    Abandoned,
+    Unknown,
+}
+
+type DateTime = chrono::DateTime<chrono::Local>;
+cynic::impl_scalar!(DateTime, schema::DateTime);
+
+type Duration = iso8601::Duration;
+cynic::impl_scalar!(Duration, schema::Duration);
+
+#[derive(cynic::Scalar, Debug, Clone, PartialEq, Eq)]
+pub struct TimeZone(pub String);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::MutationBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = ImportSessionsMutation::build(ImportSessionsMutationVariables {
+            sessions: Vec::new(),
+            on_overlap: OnOverlap::Skip,
+            dry_run: true,
+        });
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
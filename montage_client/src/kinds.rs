@@ -0,0 +1,35 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Query")]
+pub struct KindsQuery {
+    pub kinds: Vec<Kind>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Kind {
+    pub name: String,
+    pub default_duration: Duration,
+    pub is_working: bool,
+    pub nags: bool,
+    pub is_break: bool,
+    pub ends_set: bool,
+    pub emoji: String,
+}
+
+type Duration = iso8601::Duration;
+cynic::impl_scalar!(Duration, schema::Duration);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::QueryBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = KindsQuery::build(());
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
@@ -1,6 +1,38 @@
+pub mod add_note;
+pub mod create_goal;
+pub mod create_kind;
+pub mod create_webhook;
 pub mod current_session;
 pub mod current_session_updates;
+pub mod cycle;
+pub mod delete_goal;
+pub mod delete_session;
+pub mod delete_webhook;
+pub mod dequeue;
+pub mod enqueue;
+pub mod events;
+pub mod export;
 pub mod extend_by;
 pub mod extend_to;
+pub mod goals;
+pub mod history;
+pub mod import_sessions;
+pub mod kinds;
+pub mod log_interruption;
+pub mod pause;
+pub mod queue;
+pub mod reorder_queue;
 pub mod report;
+pub mod restore_session;
+pub mod resume;
+pub mod set_outcome;
+pub mod settings;
 pub mod start;
+pub mod start_next;
+pub mod stop;
+pub mod undo;
+pub mod update_kind;
+pub mod update_session;
+pub mod update_settings;
+pub mod webhook_deliveries;
+pub mod webhooks;
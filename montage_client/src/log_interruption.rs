@@ -0,0 +1,45 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryVariables, Debug)]
+pub struct LogInterruptionMutationVariables<'a> {
+    pub source: &'a str,
+    pub note: Option<&'a str>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(
+    graphql_type = "Mutation",
+    variables = "LogInterruptionMutationVariables"
+)]
+pub struct LogInterruptionMutation {
+    #[arguments(source: $source, note: $note)]
+    pub log_interruption: Session,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Session {
+    pub description: String,
+    pub interruptions: Vec<Interruption>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Interruption {
+    pub source: String,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::MutationBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = LogInterruptionMutation::build(LogInterruptionMutationVariables {
+            source: "slack ping",
+            note: None,
+        });
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
@@ -0,0 +1,30 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Mutation")]
+pub struct PauseMutation {
+    pub pause: Session,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Session {
+    pub description: String,
+    pub projected_end_time: DateTime,
+}
+
+type DateTime = chrono::DateTime<chrono::Local>;
+cynic::impl_scalar!(DateTime, schema::DateTime);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::MutationBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = PauseMutation::build(());
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
@@ -0,0 +1,33 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Query")]
+pub struct QueueQuery {
+    pub queue: Vec<PlannedSession>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct PlannedSession {
+    pub id: i32,
+    pub position: i32,
+    pub kind: String,
+    pub description: String,
+    pub duration: Option<Duration>,
+}
+
+type Duration = iso8601::Duration;
+cynic::impl_scalar!(Duration, schema::Duration);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::QueryBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = QueueQuery::build(());
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
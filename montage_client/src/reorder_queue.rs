@@ -0,0 +1,41 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryVariables, Debug)]
+pub struct ReorderQueueMutationVariables {
+    pub id: i32,
+    pub position: i32,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Mutation", variables = "ReorderQueueMutationVariables")]
+pub struct ReorderQueueMutation {
+    #[arguments(id: $id, position: $position)]
+    pub reorder_queue: Vec<PlannedSession>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct PlannedSession {
+    pub id: i32,
+    pub position: i32,
+    pub kind: String,
+    pub description: String,
+    pub duration: Option<Duration>,
+}
+
+type Duration = iso8601::Duration;
+cynic::impl_scalar!(Duration, schema::Duration);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::MutationBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation =
+            ReorderQueueMutation::build(ReorderQueueMutationVariables { id: 1, position: 0 });
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
@@ -7,12 +7,13 @@ mod schema {}
 pub struct ReportQueryVariables {
     pub start: DateTime,
     pub end: DateTime,
+    pub time_zone: Option<TimeZone>,
 }
 
 #[derive(cynic::QueryFragment, Debug)]
 #[cynic(graphql_type = "Query", variables = "ReportQueryVariables")]
 pub struct ReportQuery {
-    #[arguments(end: $end, start: $start)]
+    #[arguments(end: $end, start: $start, timeZone: $time_zone)]
     pub report: Report,
 }
 
@@ -22,6 +23,23 @@ pub struct Report {
     pub end: DateTime,
     pub totals: Totals,
     pub sessions: Vec<Session>,
+    pub goals: Vec<GoalProgress>,
+}
+
+#[derive(cynic::QueryFragment, Debug, Serialize)]
+pub struct GoalProgress {
+    pub goal: Goal,
+    pub actual: Duration,
+    pub met: bool,
+}
+
+#[derive(cynic::QueryFragment, Debug, Serialize)]
+pub struct Goal {
+    pub kind: Option<String>,
+    pub tag: Option<String>,
+    pub period: GoalPeriod,
+    pub target: Duration,
+    pub bound: GoalBound,
 }
 
 #[derive(cynic::QueryFragment, Debug, Serialize)]
@@ -32,29 +50,80 @@ pub struct Totals {
     pub meeting: Duration,
     pub working: Duration,
     pub sessions_by_description: Vec<TotalByDescription>,
+    pub totals_by_tag: Vec<TotalByTag>,
+    pub task_outcomes: OutcomeCounts,
+    pub interruptions_by_kind: Vec<InterruptionsByKind>,
+    pub extensions_by_kind: Vec<ExtensionsByKind>,
+}
+
+#[derive(cynic::QueryFragment, Debug, Serialize)]
+pub struct OutcomeCounts {
+    pub completed: i32,
+    pub total: i32,
 }
 
 #[derive(cynic::QueryFragment, Debug, Serialize)]
 pub struct TotalByDescription {
     pub description: String,
-    pub kind: Kind,
+    pub kind: String,
+    pub total: Duration,
+    pub interruptions: i32,
+}
+
+#[derive(cynic::QueryFragment, Debug, Serialize)]
+pub struct InterruptionsByKind {
+    pub kind: String,
+    pub count: i32,
+}
+
+#[derive(cynic::QueryFragment, Debug, Serialize)]
+pub struct ExtensionsByKind {
+    pub kind: String,
+    pub count: i32,
+    pub added: Duration,
+}
+
+#[derive(cynic::QueryFragment, Debug, Serialize)]
+pub struct TotalByTag {
+    pub tag: String,
     pub total: Duration,
 }
 
 #[derive(cynic::QueryFragment, Debug, Serialize)]
 pub struct Session {
+    pub id: i32,
     pub description: String,
     pub actual_duration: Duration,
-    pub kind: Kind,
+    pub kind: String,
     pub start_time: DateTime,
+    pub tags: Vec<String>,
+    pub notes: Vec<Note>,
+    pub interruptions: Vec<Interruption>,
+}
+
+#[derive(cynic::QueryFragment, Debug, Serialize)]
+pub struct Interruption {
+    pub created_at: DateTime,
+    pub source: String,
+    pub note: Option<String>,
+}
+
+#[derive(cynic::QueryFragment, Debug, Serialize)]
+pub struct Note {
+    pub created_at: DateTime,
+    pub text: String,
 }
 
 #[derive(cynic::Enum, Clone, Copy, Debug)]
-pub enum Kind {
-    Task,
-    Break,
-    Meeting,
-    Offline,
+pub enum GoalPeriod {
+    Day,
+    Week,
+}
+
+#[derive(cynic::Enum, Clone, Copy, Debug)]
+pub enum GoalBound {
+    Min,
+    Max,
 }
 
 type DateTime = chrono::DateTime<chrono::Local>;
@@ -63,6 +132,9 @@ cynic::impl_scalar!(DateTime, schema::DateTime);
 type Duration = iso8601::Duration;
 cynic::impl_scalar!(Duration, schema::Duration);
 
+#[derive(cynic::Scalar, Debug)]
+pub struct TimeZone(pub String);
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -73,6 +145,7 @@ mod test {
         let operation = ReportQuery::build(ReportQueryVariables {
             start: chrono::Local::now(),
             end: chrono::Local::now(),
+            time_zone: Some(TimeZone(String::from("America/Chicago"))),
         });
 
         insta::assert_snapshot!(operation.query);
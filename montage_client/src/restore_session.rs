@@ -0,0 +1,40 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryVariables, Debug)]
+pub struct RestoreSessionMutationVariables {
+    pub id: Option<i32>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(
+    graphql_type = "Mutation",
+    variables = "RestoreSessionMutationVariables"
+)]
+pub struct RestoreSessionMutation {
+    #[arguments(id: $id)]
+    pub restore_session: Session,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Session {
+    pub id: i32,
+    pub description: String,
+    pub start_time: DateTime,
+}
+
+type DateTime = chrono::DateTime<chrono::Local>;
+cynic::impl_scalar!(DateTime, schema::DateTime);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::MutationBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = RestoreSessionMutation::build(RestoreSessionMutationVariables { id: None });
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
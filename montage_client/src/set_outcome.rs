@@ -0,0 +1,46 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryVariables, Debug)]
+pub struct SetOutcomeMutationVariables {
+    pub outcome: Outcome,
+    pub session_id: Option<i32>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Mutation", variables = "SetOutcomeMutationVariables")]
+pub struct SetOutcomeMutation {
+    #[arguments(outcome: $outcome, sessionId: $session_id)]
+    pub set_outcome: Session,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Session {
+    pub id: i32,
+    pub description: String,
+    pub outcome: Outcome,
+}
+
+#[derive(cynic::Enum, Clone, Copy, Debug)]
+pub enum Outcome {
+    Completed,
+    Partial,
+    Abandoned,
+    Unknown,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::MutationBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = SetOutcomeMutation::build(SetOutcomeMutationVariables {
+            outcome: Outcome::Completed,
+            session_id: None,
+        });
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
@@ -0,0 +1,31 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Query")]
+pub struct SettingsQuery {
+    pub settings: Settings,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Settings {
+    pub short_break_cutoff: Duration,
+    pub set_size: i32,
+    pub long_break_duration: Duration,
+}
+
+type Duration = iso8601::Duration;
+cynic::impl_scalar!(Duration, schema::Duration);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::QueryBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = SettingsQuery::build(());
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
@@ -4,14 +4,24 @@ mod schema {}
 #[derive(cynic::QueryVariables, Debug)]
 pub struct StartMutationVariables<'a> {
     pub description: &'a str,
-    pub kind: Kind,
+    pub kind: &'a str,
+    pub duration: Option<Duration>,
+    pub tags: Option<Vec<&'a str>>,
+    pub time_zone: Option<TimeZone>,
+    pub then: Option<FollowUp<'a>>,
+}
+
+#[derive(cynic::InputObject, Debug)]
+pub struct FollowUp<'a> {
+    pub kind: &'a str,
+    pub description: Option<&'a str>,
     pub duration: Option<Duration>,
 }
 
 #[derive(cynic::QueryFragment, Debug)]
 #[cynic(graphql_type = "Mutation", variables = "StartMutationVariables")]
 pub struct StartMutation {
-    #[arguments(description: $description, kind: $kind, duration: $duration)]
+    #[arguments(description: $description, kind: $kind, duration: $duration, tags: $tags, timeZone: $time_zone, then: $then)]
     pub start: Session,
 }
 
@@ -20,14 +30,13 @@ pub struct Session {
     pub projected_end_time: DateTime,
     pub duration: Duration,
     pub description: String,
+    pub follow_up: Option<Chain>,
 }
 
-#[derive(cynic::Enum, Clone, Copy, Debug)]
-pub enum Kind {
-    Task,
-    Break,
-    Meeting,
-    Offline,
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Chain {
+    pub description: String,
+    pub duration: Option<Duration>,
 }
 
 type DateTime = chrono::DateTime<chrono::Local>;
@@ -36,6 +45,9 @@ cynic::impl_scalar!(DateTime, schema::DateTime);
 type Duration = iso8601::Duration;
 cynic::impl_scalar!(Duration, schema::Duration);
 
+#[derive(cynic::Scalar, Debug)]
+pub struct TimeZone(pub String);
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -45,8 +57,15 @@ mod test {
     fn gql_output() {
         let operation = StartMutation::build(StartMutationVariables {
             description: "test description",
-            kind: Kind::Task,
+            kind: "task",
             duration: None,
+            tags: Some(vec!["test tag"]),
+            time_zone: Some(TimeZone(String::from("America/Chicago"))),
+            then: Some(FollowUp {
+                kind: "break",
+                description: None,
+                duration: None,
+            }),
         });
 
         insta::assert_snapshot!(operation.query);
@@ -0,0 +1,45 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryVariables, Debug)]
+pub struct StartNextMutationVariables {
+    pub time_zone: Option<TimeZone>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Mutation", variables = "StartNextMutationVariables")]
+pub struct StartNextMutation {
+    #[arguments(timeZone: $time_zone)]
+    pub start_next: Session,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Session {
+    pub projected_end_time: DateTime,
+    pub duration: Duration,
+    pub description: String,
+}
+
+type DateTime = chrono::DateTime<chrono::Local>;
+cynic::impl_scalar!(DateTime, schema::DateTime);
+
+type Duration = iso8601::Duration;
+cynic::impl_scalar!(Duration, schema::Duration);
+
+#[derive(cynic::Scalar, Debug)]
+pub struct TimeZone(pub String);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::MutationBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = StartNextMutation::build(StartNextMutationVariables {
+            time_zone: Some(TimeZone(String::from("America/Chicago"))),
+        });
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
@@ -0,0 +1,38 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Mutation")]
+pub struct UndoMutation {
+    pub undo: Undone,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Undone {
+    pub mutation: String,
+    pub restored: Vec<Session>,
+    pub removed: Vec<Session>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Session {
+    pub id: i32,
+    pub description: String,
+    pub start_time: DateTime,
+}
+
+type DateTime = chrono::DateTime<chrono::Local>;
+cynic::impl_scalar!(DateTime, schema::DateTime);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::MutationBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = UndoMutation::build(());
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
@@ -0,0 +1,49 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryVariables, Debug)]
+pub struct UpdateKindMutationVariables<'a> {
+    pub name: &'a str,
+    pub default_duration: Option<Duration>,
+    pub is_working: Option<bool>,
+    pub nags: Option<bool>,
+    pub is_break: Option<bool>,
+    pub ends_set: Option<bool>,
+    pub emoji: Option<&'a str>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Mutation", variables = "UpdateKindMutationVariables")]
+pub struct UpdateKindMutation {
+    #[arguments(name: $name, defaultDuration: $default_duration, isWorking: $is_working, nags: $nags, isBreak: $is_break, endsSet: $ends_set, emoji: $emoji)]
+    pub update_kind: Kind,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Kind {
+    pub name: String,
+}
+
+type Duration = iso8601::Duration;
+cynic::impl_scalar!(Duration, schema::Duration);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::MutationBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = UpdateKindMutation::build(UpdateKindMutationVariables {
+            name: "pairing",
+            default_duration: None,
+            is_working: None,
+            nags: Some(true),
+            is_break: None,
+            ends_set: None,
+            emoji: None,
+        });
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
@@ -0,0 +1,58 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryVariables, Debug)]
+pub struct UpdateSessionMutationVariables<'a> {
+    pub id: i32,
+    pub description: Option<&'a str>,
+    pub kind: Option<&'a str>,
+    pub start_time: Option<DateTime>,
+    pub end_time: Option<DateTime>,
+    pub duration: Option<Duration>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(
+    graphql_type = "Mutation",
+    variables = "UpdateSessionMutationVariables"
+)]
+pub struct UpdateSessionMutation {
+    #[arguments(id: $id, description: $description, kind: $kind, startTime: $start_time, endTime: $end_time, duration: $duration)]
+    pub update_session: Session,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Session {
+    pub id: i32,
+    pub description: String,
+    pub kind: String,
+    pub start_time: DateTime,
+    pub end_time: Option<DateTime>,
+    pub actual_duration: Duration,
+}
+
+type DateTime = chrono::DateTime<chrono::Local>;
+cynic::impl_scalar!(DateTime, schema::DateTime);
+
+type Duration = iso8601::Duration;
+cynic::impl_scalar!(Duration, schema::Duration);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::MutationBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = UpdateSessionMutation::build(UpdateSessionMutationVariables {
+            id: 1,
+            description: Some("test description"),
+            kind: None,
+            start_time: None,
+            end_time: None,
+            duration: None,
+        });
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
@@ -0,0 +1,46 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryVariables, Debug)]
+pub struct UpdateSettingsMutationVariables {
+    pub short_break_cutoff: Option<Duration>,
+    pub set_size: Option<i32>,
+    pub long_break_duration: Option<Duration>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(
+    graphql_type = "Mutation",
+    variables = "UpdateSettingsMutationVariables"
+)]
+pub struct UpdateSettingsMutation {
+    #[arguments(shortBreakCutoff: $short_break_cutoff, setSize: $set_size, longBreakDuration: $long_break_duration)]
+    pub update_settings: Settings,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Settings {
+    pub short_break_cutoff: Duration,
+    pub set_size: i32,
+    pub long_break_duration: Duration,
+}
+
+type Duration = iso8601::Duration;
+cynic::impl_scalar!(Duration, schema::Duration);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::MutationBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = UpdateSettingsMutation::build(UpdateSettingsMutationVariables {
+            short_break_cutoff: Some(iso8601::duration("PT20M").unwrap()),
+            set_size: Some(4),
+            long_break_duration: None,
+        });
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
@@ -0,0 +1,45 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryVariables, Debug)]
+pub struct WebhookDeliveriesQueryVariables {
+    pub webhook_id: Option<i32>,
+    pub limit: i32,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Query", variables = "WebhookDeliveriesQueryVariables")]
+pub struct WebhookDeliveriesQuery {
+    #[arguments(webhookId: $webhook_id, limit: $limit)]
+    pub webhook_deliveries: Vec<WebhookDelivery>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct WebhookDelivery {
+    pub webhook_id: i32,
+    pub event: String,
+    pub attempt: i32,
+    pub attempted_at: DateTime,
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+    pub succeeded: bool,
+}
+
+type DateTime = chrono::DateTime<chrono::Local>;
+cynic::impl_scalar!(DateTime, schema::DateTime);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::QueryBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = WebhookDeliveriesQuery::build(WebhookDeliveriesQueryVariables {
+            webhook_id: Some(1),
+            limit: 10,
+        });
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
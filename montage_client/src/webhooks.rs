@@ -0,0 +1,31 @@
+#[cynic::schema("montage")]
+mod schema {}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Query")]
+pub struct WebhooksQuery {
+    pub webhooks: Vec<Webhook>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Webhook {
+    pub id: i32,
+    pub url: String,
+    pub created_at: DateTime,
+}
+
+type DateTime = chrono::DateTime<chrono::Local>;
+cynic::impl_scalar!(DateTime, schema::DateTime);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cynic::QueryBuilder;
+
+    #[test]
+    fn gql_output() {
+        let operation = WebhooksQuery::build(());
+
+        insta::assert_snapshot!(operation.query);
+    }
+}
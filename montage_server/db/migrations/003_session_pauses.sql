@@ -0,0 +1,8 @@
+CREATE TABLE session_pauses (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id INTEGER NOT NULL REFERENCES sessions(id),
+    start_time DATETIME NOT NULL,
+    end_time DATETIME
+);
+
+CREATE INDEX idx_session_pauses_session_id ON session_pauses (session_id);
@@ -0,0 +1,12 @@
+CREATE TABLE tags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE session_tags (
+    session_id INTEGER NOT NULL REFERENCES sessions(id),
+    tag_id INTEGER NOT NULL REFERENCES tags(id),
+    PRIMARY KEY (session_id, tag_id)
+);
+
+CREATE INDEX idx_session_tags_tag_id ON session_tags (tag_id);
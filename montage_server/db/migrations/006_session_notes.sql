@@ -0,0 +1,8 @@
+CREATE TABLE session_notes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id INTEGER NOT NULL REFERENCES sessions(id),
+    created_at DATETIME NOT NULL,
+    text TEXT NOT NULL
+);
+
+CREATE INDEX idx_session_notes_session_id ON session_notes (session_id);
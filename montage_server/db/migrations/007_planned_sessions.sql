@@ -0,0 +1,9 @@
+CREATE TABLE planned_sessions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    position INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    description TEXT NOT NULL,
+    duration STRING
+);
+
+CREATE INDEX idx_planned_sessions_position ON planned_sessions (position);
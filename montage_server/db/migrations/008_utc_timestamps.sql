@@ -0,0 +1,19 @@
+-- Timestamps used to be stored with whatever offset the server had at the
+-- time. Normalize them all to UTC so they sort and compare correctly across
+-- DST changes. (`strftime` gives us millisecond precision; we drop ".000" to
+-- match how whole seconds are written by the server.)
+UPDATE sessions
+SET start_time = REPLACE(strftime('%Y-%m-%dT%H:%M:%f', start_time), '.000', '') || '+00:00',
+    end_time = REPLACE(strftime('%Y-%m-%dT%H:%M:%f', end_time), '.000', '') || '+00:00',
+    deleted_at = REPLACE(strftime('%Y-%m-%dT%H:%M:%f', deleted_at), '.000', '') || '+00:00';
+
+UPDATE session_pauses
+SET start_time = REPLACE(strftime('%Y-%m-%dT%H:%M:%f', start_time), '.000', '') || '+00:00',
+    end_time = REPLACE(strftime('%Y-%m-%dT%H:%M:%f', end_time), '.000', '') || '+00:00';
+
+UPDATE session_notes
+SET created_at = REPLACE(strftime('%Y-%m-%dT%H:%M:%f', created_at), '.000', '') || '+00:00';
+
+-- The IANA time zone the session was started in. Sessions from before we
+-- recorded this are filled in with the server's zone on startup.
+ALTER TABLE sessions ADD COLUMN time_zone TEXT;
@@ -0,0 +1,39 @@
+-- Durations used to be stored as ISO 8601 strings (like `PT1500S` or
+-- `P1DT600S`), which meant parsing every row and made it impossible to add
+-- them up in SQL. Store them as whole seconds instead.
+ALTER TABLE sessions ADD COLUMN duration_seconds INTEGER NOT NULL DEFAULT 0;
+
+UPDATE sessions
+SET duration_seconds = CAST(
+    CASE WHEN instr(duration, 'D') > 0
+        THEN CAST(substr(duration, instr(duration, 'P') + 1, instr(duration, 'D') - instr(duration, 'P') - 1) AS INTEGER) * 86400
+        ELSE 0
+    END
+    + CASE WHEN instr(duration, 'T') > 0
+        THEN CAST(substr(duration, instr(duration, 'T') + 1, instr(duration, 'S') - instr(duration, 'T') - 1) AS REAL)
+        ELSE 0
+    END
+    AS INTEGER
+) * CASE WHEN duration LIKE '-%' THEN -1 ELSE 1 END;
+
+ALTER TABLE sessions DROP COLUMN duration;
+ALTER TABLE sessions RENAME COLUMN duration_seconds TO duration;
+
+ALTER TABLE planned_sessions ADD COLUMN duration_seconds INTEGER;
+
+UPDATE planned_sessions
+SET duration_seconds = CAST(
+    CASE WHEN instr(duration, 'D') > 0
+        THEN CAST(substr(duration, instr(duration, 'P') + 1, instr(duration, 'D') - instr(duration, 'P') - 1) AS INTEGER) * 86400
+        ELSE 0
+    END
+    + CASE WHEN instr(duration, 'T') > 0
+        THEN CAST(substr(duration, instr(duration, 'T') + 1, instr(duration, 'S') - instr(duration, 'T') - 1) AS REAL)
+        ELSE 0
+    END
+    AS INTEGER
+) * CASE WHEN duration LIKE '-%' THEN -1 ELSE 1 END
+WHERE duration IS NOT NULL;
+
+ALTER TABLE planned_sessions DROP COLUMN duration;
+ALTER TABLE planned_sessions RENAME COLUMN duration_seconds TO duration;
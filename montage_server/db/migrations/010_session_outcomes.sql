@@ -0,0 +1 @@
+ALTER TABLE sessions ADD COLUMN outcome TEXT NOT NULL DEFAULT 'unknown';
@@ -0,0 +1,9 @@
+CREATE TABLE interruptions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id INTEGER NOT NULL REFERENCES sessions(id),
+    created_at DATETIME NOT NULL,
+    source TEXT NOT NULL,
+    note TEXT
+);
+
+CREATE INDEX idx_interruptions_session_id ON interruptions (session_id);
@@ -0,0 +1,11 @@
+CREATE TABLE goals (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind TEXT,
+    tag TEXT,
+    period TEXT NOT NULL,
+    target INTEGER NOT NULL,
+    bound TEXT NOT NULL,
+
+    -- a goal counts either a kind of session or a tag, but not both
+    CHECK ((kind IS NULL) != (tag IS NULL))
+);
@@ -0,0 +1,25 @@
+-- Kinds of sessions used to be a fixed list in the server. Now they live here
+-- so people can add their own (pairing, lunch, etc.) Sessions, planned
+-- sessions, and goals refer to kinds by name.
+CREATE TABLE kinds (
+    name TEXT PRIMARY KEY NOT NULL,
+
+    -- in seconds
+    default_duration INTEGER NOT NULL,
+
+    -- does time spent in this kind of session count towards working time?
+    -- (short breaks always count, no matter what this says.)
+    is_working BOOLEAN NOT NULL,
+
+    -- should the vexer nag when this kind of session runs over? If not, it
+    -- stays quiet for the whole session.
+    nags BOOLEAN NOT NULL,
+
+    emoji TEXT NOT NULL
+);
+
+INSERT INTO kinds (name, default_duration, is_working, nags, emoji)
+VALUES ('task', 1500, TRUE, TRUE, '⏰'),
+       ('break', 300, FALSE, TRUE, '☕️'),
+       ('meeting', 3600, TRUE, FALSE, '🗣'),
+       ('offline', 3600, FALSE, TRUE, '💤');
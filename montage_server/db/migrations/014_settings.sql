@@ -0,0 +1,10 @@
+-- Knobs that used to be compiled into the server. There's only ever one row.
+CREATE TABLE settings (
+    id INTEGER PRIMARY KEY NOT NULL CHECK (id = 1),
+
+    -- in seconds. Breaks longer than this are long breaks, which don't count
+    -- as working time.
+    short_break_cutoff INTEGER NOT NULL
+);
+
+INSERT INTO settings (id, short_break_cutoff) VALUES (1, 900);
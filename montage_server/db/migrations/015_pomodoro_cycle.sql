@@ -0,0 +1,5 @@
+-- How many task sessions make a set before we suggest a long break
+ALTER TABLE settings ADD COLUMN set_size INTEGER NOT NULL DEFAULT 4;
+
+-- in seconds. How long breaks are once a set is done, if not told otherwise.
+ALTER TABLE settings ADD COLUMN long_break_duration INTEGER NOT NULL DEFAULT 1800;
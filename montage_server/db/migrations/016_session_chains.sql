@@ -0,0 +1,11 @@
+-- Sessions to start automatically when the session they follow reaches its
+-- projected end time. Each session can have at most one.
+CREATE TABLE session_chains (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id INTEGER NOT NULL UNIQUE REFERENCES sessions(id),
+    kind TEXT NOT NULL,
+    description TEXT NOT NULL,
+
+    -- in seconds. NULL means we'll pick when it starts.
+    duration INTEGER
+);
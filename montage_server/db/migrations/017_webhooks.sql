@@ -0,0 +1,28 @@
+-- HTTP endpoints to tell about session lifecycle events
+CREATE TABLE webhooks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    url TEXT NOT NULL,
+
+    -- used to sign payloads so receivers can tell they came from us
+    secret TEXT NOT NULL,
+
+    created_at DATETIME NOT NULL
+);
+
+-- Every attempt to deliver an event to a webhook, successful or not
+CREATE TABLE webhook_deliveries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    webhook_id INTEGER NOT NULL REFERENCES webhooks(id),
+    event TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    attempt INTEGER NOT NULL,
+    attempted_at DATETIME NOT NULL,
+
+    -- the HTTP status we got back, if we got that far
+    status_code INTEGER,
+
+    -- what went wrong, if the request didn't go through at all
+    error TEXT
+);
+
+CREATE INDEX idx_webhook_deliveries_webhook_id ON webhook_deliveries (webhook_id);
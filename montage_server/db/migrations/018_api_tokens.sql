@@ -0,0 +1,13 @@
+-- Tokens for authenticating with the GraphQL API. Once any token has been created, the server
+-- requires one on every request.
+CREATE TABLE api_tokens (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+
+    -- SHA-256 of the token, hex-encoded. We never store the token itself.
+    token_hash TEXT NOT NULL UNIQUE,
+
+    created_at DATETIME NOT NULL,
+    last_used_at DATETIME,
+    revoked_at DATETIME
+);
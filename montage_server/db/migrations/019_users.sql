@@ -0,0 +1,23 @@
+-- People sharing one server. Sessions, the queue, goals, webhooks, and API
+-- tokens belong to a user; kinds and settings are shared by everyone.
+CREATE TABLE users (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    created_at DATETIME NOT NULL
+);
+
+-- Everything from before there were users belongs to this one. Requests act as
+-- them too, as long as no API tokens exist.
+INSERT INTO users (id, name, created_at)
+VALUES (1, 'default', strftime('%Y-%m-%dT%H:%M:%S', 'now') || '+00:00');
+
+-- (SQLite won't add a column that references another table with a non-NULL
+-- default, so these don't say they point at `users`.)
+ALTER TABLE sessions ADD COLUMN user_id INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE planned_sessions ADD COLUMN user_id INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE goals ADD COLUMN user_id INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE webhooks ADD COLUMN user_id INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE api_tokens ADD COLUMN user_id INTEGER NOT NULL DEFAULT 1;
+
+CREATE INDEX idx_sessions_user_id_start_time ON sessions (user_id, start_time);
+CREATE INDEX idx_planned_sessions_user_id_position ON planned_sessions (user_id, position);
@@ -0,0 +1,40 @@
+-- Every change made to a session, so we can tell what happened to it after the
+-- fact (for example, how many times it was extended.) Rows are never changed or
+-- removed, even when the session they're about is purged.
+CREATE TABLE events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL REFERENCES users(id),
+
+    -- rows written by the same mutation (say, starting a session, which also
+    -- ends the one before it) point at the first of them. That row leaves this
+    -- empty.
+    change_id INTEGER REFERENCES events(id),
+
+    -- not a foreign key, so purging a session doesn't have to take its history
+    -- with it
+    session_id INTEGER NOT NULL,
+
+    -- the name of the GraphQL mutation, like `start` or `extendBy`
+    mutation TEXT NOT NULL,
+
+    -- JSON: the mutation's arguments, and the session before and after the
+    -- change (NULL before it was created)
+    arguments TEXT NOT NULL,
+    before TEXT,
+    after TEXT,
+
+    created_at DATETIME NOT NULL
+);
+
+CREATE INDEX idx_events_session_id ON events (session_id);
+CREATE INDEX idx_events_user_id ON events (user_id);
+
+CREATE TRIGGER events_cannot_be_updated BEFORE UPDATE ON events
+BEGIN
+    SELECT RAISE(ABORT, 'events are append-only');
+END;
+
+CREATE TRIGGER events_cannot_be_deleted BEFORE DELETE ON events
+BEGIN
+    SELECT RAISE(ABORT, 'events are append-only');
+END;
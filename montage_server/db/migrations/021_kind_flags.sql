@@ -0,0 +1,13 @@
+-- The Pomodoro cycle and reports used to look for the `break` and `offline`
+-- kinds by name. Now kinds say what part they play, so people can add their
+-- own breaks (lunch, a walk) without the cycle losing track.
+
+-- is this kind of session a break? Breaks longer than the short break cutoff
+-- are long breaks, which start a new set. Short ones count as working time.
+ALTER TABLE kinds ADD COLUMN is_break BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- does any session of this kind start a new set, no matter how long it is?
+ALTER TABLE kinds ADD COLUMN ends_set BOOLEAN NOT NULL DEFAULT FALSE;
+
+UPDATE kinds SET is_break = TRUE WHERE name = 'break';
+UPDATE kinds SET ends_set = TRUE WHERE name = 'offline';
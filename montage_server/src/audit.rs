@@ -0,0 +1,327 @@
+use super::error::{Error, Result};
+use async_graphql::SimpleObject;
+use chrono::{DateTime, Utc};
+use indoc::indoc;
+use sqlx::{FromRow, Pool, Sqlite, SqliteConnection};
+
+/// A session as JSON: its own columns plus its pauses, tags, notes, and interruptions, which is
+/// everything a mutation can change. Takes the session's ID.
+const SNAPSHOT: &str = indoc! {"
+    SELECT json_object(
+        'kind', kind,
+        'description', description,
+        'start_time', start_time,
+        'duration', duration,
+        'end_time', end_time,
+        'time_zone', time_zone,
+        'outcome', outcome,
+        'deleted_at', deleted_at,
+        'pauses', json((
+            SELECT json_group_array(json_object('start_time', start_time, 'end_time', end_time))
+            FROM (
+                SELECT start_time, end_time
+                FROM session_pauses
+                WHERE session_id = sessions.id
+                ORDER BY start_time ASC
+            )
+        )),
+        'tags', json((
+            SELECT json_group_array(name)
+            FROM (
+                SELECT tags.name
+                FROM session_tags
+                JOIN tags ON tags.id = session_tags.tag_id
+                WHERE session_tags.session_id = sessions.id
+                ORDER BY tags.name ASC
+            )
+        )),
+        'notes', json((
+            SELECT json_group_array(json_object('created_at', created_at, 'text', text))
+            FROM (
+                SELECT created_at, text
+                FROM session_notes
+                WHERE session_id = sessions.id
+                ORDER BY id ASC
+            )
+        )),
+        'interruptions', json((
+            SELECT json_group_array(
+                json_object('created_at', created_at, 'source', source, 'note', note)
+            )
+            FROM (
+                SELECT created_at, source, note
+                FROM interruptions
+                WHERE session_id = sessions.id
+                ORDER BY id ASC
+            )
+        ))
+    )
+    FROM sessions
+    WHERE id = ?
+"};
+
+/// Something a mutation did to a session. Events are only ever added, so they're a full history
+/// of how a session got to where it is.
+#[derive(SimpleObject, FromRow, Debug, PartialEq, Eq, Clone)]
+pub struct AuditEvent {
+    pub id: i64,
+
+    /// Who made the change
+    #[graphql(skip)]
+    pub user_id: i64,
+
+    /// Events written by the same mutation share this (it's the ID of the first of them.)
+    pub change_id: i64,
+
+    /// The session that changed
+    pub session_id: i64,
+
+    /// The GraphQL mutation that made the change, like `start` or `extendBy`
+    pub mutation: String,
+
+    /// The mutation's arguments, as JSON
+    pub arguments: String,
+
+    /// The session before the change, as JSON. Empty if the change created the session.
+    pub before: Option<String>,
+
+    /// The session after the change, as JSON
+    pub after: Option<String>,
+
+    /// When was the change made?
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditEvent {
+    /// Every change made to one of a user's sessions, oldest first.
+    pub async fn history(pool: &Pool<Sqlite>, user_id: i64, session_id: i64) -> Result<Vec<Self>> {
+        sqlx::query_as::<_, Self>(indoc! {"
+            SELECT
+                id,
+                user_id,
+                COALESCE(change_id, id) AS change_id,
+                session_id,
+                mutation,
+                arguments,
+                before,
+                after,
+                created_at
+            FROM events
+            WHERE user_id = ? AND session_id = ?
+            ORDER BY id ASC
+        "})
+        .bind(user_id)
+        .bind(session_id)
+        .fetch_all(pool)
+        .await
+        .map_err(Error::Query)
+    }
+}
+
+/// The events for a single mutation. Take a snapshot of each session before changing it, then
+/// record it afterwards, all in the same transaction as the change itself.
+pub struct Change {
+    user_id: i64,
+    mutation: &'static str,
+    arguments: serde_json::Value,
+
+    /// The ID of the first event we recorded, once there is one
+    change_id: Option<i64>,
+}
+
+impl Change {
+    pub fn new(user_id: i64, mutation: &'static str, arguments: serde_json::Value) -> Self {
+        Self {
+            user_id,
+            mutation,
+            arguments,
+            change_id: None,
+        }
+    }
+
+    /// Get a session as it is right now, to record as what it was before the change.
+    pub async fn snapshot(conn: &mut SqliteConnection, session_id: i64) -> Result<Option<String>> {
+        sqlx::query_scalar(SNAPSHOT)
+            .bind(session_id)
+            .fetch_optional(conn)
+            .await
+            .map_err(Error::Query)
+    }
+
+    /// Write down what happened to a session, given what it was before.
+    pub async fn record(
+        &mut self,
+        conn: &mut SqliteConnection,
+        session_id: i64,
+        before: Option<String>,
+    ) -> Result<()> {
+        let id: i64 = sqlx::query_scalar(&format!(
+            indoc! {"
+                INSERT INTO events
+                    (user_id, change_id, session_id, mutation, arguments, before, after, created_at)
+                VALUES (?, ?, ?, ?, ?, ?, ({snapshot}), ?)
+                RETURNING id
+            "},
+            snapshot = SNAPSHOT
+        ))
+        .bind(self.user_id)
+        .bind(self.change_id)
+        .bind(session_id)
+        .bind(self.mutation)
+        .bind(self.arguments.to_string())
+        .bind(before)
+        .bind(session_id)
+        .bind(Utc::now())
+        .fetch_one(conn)
+        .await
+        .map_err(Error::Query)?;
+
+        self.change_id.get_or_insert(id);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::session::Session;
+    use crate::user::{User, DEFAULT_USER_ID};
+    use chrono::Duration;
+    use chrono_tz::Tz;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn get_pool() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new().connect(":memory:").await.unwrap();
+
+        sqlx::migrate!("db/migrations").run(&pool).await.unwrap();
+
+        pool
+    }
+
+    async fn start(pool: &Pool<Sqlite>, user_id: i64, start_time: DateTime<Utc>) -> Session {
+        Session::start(
+            pool,
+            user_id,
+            "task",
+            "description",
+            start_time,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap()
+    }
+
+    fn field(json: &Option<String>, name: &str) -> serde_json::Value {
+        let parsed: serde_json::Value = serde_json::from_str(json.as_ref().unwrap()).unwrap();
+
+        parsed[name].clone()
+    }
+
+    #[tokio::test]
+    async fn starting_records_the_new_session_and_the_one_it_ended() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        let first = start(&pool, DEFAULT_USER_ID, now).await;
+        let second = start(&pool, DEFAULT_USER_ID, now + Duration::minutes(10)).await;
+
+        let second_history = AuditEvent::history(&pool, DEFAULT_USER_ID, second.id)
+            .await
+            .unwrap();
+        assert_eq!(second_history.len(), 1);
+        assert_eq!(second_history[0].mutation, "start");
+        assert_eq!(second_history[0].before, None);
+        assert_eq!(
+            field(&second_history[0].after, "description"),
+            "description"
+        );
+
+        let first_history = AuditEvent::history(&pool, DEFAULT_USER_ID, first.id)
+            .await
+            .unwrap();
+        assert_eq!(first_history.len(), 2);
+        assert_eq!(
+            field(&first_history[1].before, "end_time"),
+            serde_json::Value::Null
+        );
+        assert_ne!(
+            field(&first_history[1].after, "end_time"),
+            serde_json::Value::Null
+        );
+
+        // ending the first session was part of starting the second
+        assert_eq!(first_history[1].change_id, second_history[0].change_id);
+        assert_ne!(first_history[0].change_id, second_history[0].change_id);
+    }
+
+    #[tokio::test]
+    async fn extending_records_the_duration_before_and_after() {
+        let pool = get_pool().await;
+        let session = start(&pool, DEFAULT_USER_ID, Utc::now()).await;
+
+        Session::extend_by(&pool, DEFAULT_USER_ID, Duration::minutes(5))
+            .await
+            .unwrap();
+
+        let history = AuditEvent::history(&pool, DEFAULT_USER_ID, session.id)
+            .await
+            .unwrap();
+        let extension = history.last().unwrap();
+
+        assert_eq!(extension.mutation, "extendBy");
+        assert_eq!(extension.arguments, r#"{"duration":300}"#);
+        assert_eq!(field(&extension.before, "duration"), 25 * 60);
+        assert_eq!(field(&extension.after, "duration"), 30 * 60);
+    }
+
+    #[tokio::test]
+    async fn pausing_records_the_pause() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+        let session = start(&pool, DEFAULT_USER_ID, now).await;
+
+        Session::pause(&pool, DEFAULT_USER_ID, now + Duration::minutes(1))
+            .await
+            .unwrap();
+
+        let history = AuditEvent::history(&pool, DEFAULT_USER_ID, session.id)
+            .await
+            .unwrap();
+        let pause = history.last().unwrap();
+
+        assert_eq!(pause.mutation, "pause");
+        assert_eq!(field(&pause.before, "pauses"), serde_json::json!([]));
+        assert_eq!(field(&pause.after, "pauses").as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn history_only_includes_the_users_own_sessions() {
+        let pool = get_pool().await;
+        let alice = User::create(&pool, "alice", Utc::now()).await.unwrap();
+        let session = start(&pool, DEFAULT_USER_ID, Utc::now()).await;
+
+        assert_eq!(
+            AuditEvent::history(&pool, alice.id, session.id)
+                .await
+                .unwrap(),
+            Vec::new()
+        );
+    }
+
+    #[tokio::test]
+    async fn events_cannot_be_changed() {
+        let pool = get_pool().await;
+        start(&pool, DEFAULT_USER_ID, Utc::now()).await;
+
+        assert!(sqlx::query("UPDATE events SET mutation = 'stop'")
+            .execute(&pool)
+            .await
+            .is_err());
+        assert!(sqlx::query("DELETE FROM events")
+            .execute(&pool)
+            .await
+            .is_err());
+    }
+}
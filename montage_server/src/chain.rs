@@ -0,0 +1,453 @@
+use super::cycle::Cycle;
+use super::error::{Error, Result, StartSessionError};
+use super::kind::Kind;
+use super::session::Session;
+use async_graphql::{InputObject, SimpleObject};
+use chrono::{DateTime, Duration, Utc};
+use indoc::indoc;
+use sqlx::{sqlite::SqliteRow, FromRow, Pool, Row, Sqlite, SqliteConnection};
+use std::sync::Arc;
+use tokio::sync::watch::Sender;
+
+/// The longest the scheduler sleeps before checking for chained sessions again, even if nothing
+/// has changed.
+static MAXIMUM_WAIT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// A session to start automatically when another session reaches its projected end time
+#[derive(SimpleObject, Debug, PartialEq, Eq, Clone)]
+pub struct Chain {
+    #[graphql(skip)]
+    pub id: i64,
+
+    /// The session this one follows
+    #[graphql(skip)]
+    pub session_id: i64,
+
+    /// What kind of session will this be?
+    pub kind: String,
+
+    /// What will be going on in this session?
+    pub description: String,
+
+    /// How long will this session last? (If empty, we'll decide when it starts.)
+    pub duration: Option<Duration>,
+}
+
+/// A session to start automatically when the one being started ends
+#[derive(InputObject, Debug)]
+pub struct FollowUp {
+    /// What kind of session will this be?
+    pub kind: String,
+
+    /// What will be going on in this session? (If omitted, we'll use the name of the kind.)
+    pub description: Option<String>,
+
+    /// How long will this session last? (If omitted, we'll use the kind's default duration, or
+    /// a long break once a Pomodoro set is done)
+    pub duration: Option<Duration>,
+}
+
+/// What the scheduler found when it checked on a user's chained sessions
+#[derive(Debug, PartialEq, Eq)]
+pub enum ChainState {
+    /// The current session reached its projected end time, so its chained session started
+    Started(Session),
+
+    /// The current session has a chained session, which is due at this time
+    WaitUntil(DateTime<Utc>),
+
+    /// There's no chained session to wait for
+    Idle,
+}
+
+impl FromRow<'_, SqliteRow> for Chain {
+    fn from_row(row: &SqliteRow) -> std::result::Result<Self, sqlx::Error> {
+        Ok(Self {
+            id: row.try_get("id")?,
+            session_id: row.try_get("session_id")?,
+            kind: row.try_get("kind")?,
+            description: row.try_get("description")?,
+            duration: row
+                .try_get::<Option<i64>, _>("duration")?
+                .map(Duration::seconds),
+        })
+    }
+}
+
+impl FollowUp {
+    fn description(&self) -> &str {
+        self.description.as_deref().unwrap_or(&self.kind).trim()
+    }
+}
+
+impl Chain {
+    /// Check that `follow_up` could be chained to a session, without chaining it. Call this before
+    /// starting the session it follows, so a typo doesn't leave a session running that we reported
+    /// as failed.
+    pub async fn validate(pool: &Pool<Sqlite>, follow_up: &FollowUp) -> Result<()> {
+        Kind::by_name(pool, &follow_up.kind).await?;
+
+        if follow_up.description().is_empty() {
+            return Err(Error::StartSessionError(
+                StartSessionError::DescriptionWasBlank,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Start `follow_up` when the given session ends, as part of a larger transaction (like the
+    /// one that starts the session.) Replaces anything already chained to it. `follow_up` should
+    /// already have passed `validate`.
+    pub(crate) async fn create_in(
+        conn: &mut SqliteConnection,
+        session_id: i64,
+        follow_up: &FollowUp,
+    ) -> Result<Self> {
+        let chain = sqlx::query_as::<_, Self>(indoc! {"
+            INSERT INTO session_chains (session_id, kind, description, duration)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT (session_id) DO UPDATE
+            SET kind = excluded.kind,
+                description = excluded.description,
+                duration = excluded.duration
+            RETURNING *
+        "})
+        .bind(session_id)
+        .bind(&follow_up.kind)
+        .bind(follow_up.description())
+        .bind(follow_up.duration.map(|duration| duration.num_seconds()))
+        .fetch_one(conn)
+        .await
+        .map_err(Error::Query)?;
+
+        tracing::info!(session_id, kind = chain.kind, "chained session");
+
+        Ok(chain)
+    }
+
+    pub async fn for_session(pool: &Pool<Sqlite>, session_id: i64) -> Result<Option<Self>> {
+        sqlx::query_as::<_, Self>("SELECT * FROM session_chains WHERE session_id = ?")
+            .bind(session_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(Error::Query)
+    }
+
+    /// Forget chains for sessions that ended (or were deleted) some other way, like being stopped
+    /// by hand.
+    async fn discard_stale(pool: &Pool<Sqlite>) -> Result<()> {
+        let receipt = sqlx::query(indoc! {"
+            DELETE FROM session_chains
+            WHERE session_id IN (
+                SELECT id
+                FROM sessions
+                WHERE end_time IS NOT NULL
+                   OR deleted_at IS NOT NULL
+            )
+        "})
+        .execute(pool)
+        .await
+        .map_err(Error::Query)?;
+
+        if receipt.rows_affected() > 0 {
+            tracing::info!(count = receipt.rows_affected(), "discarded stale chains");
+        }
+
+        Ok(())
+    }
+
+    async fn delete(&self, conn: &mut SqliteConnection) -> Result<()> {
+        sqlx::query("DELETE FROM session_chains WHERE id = ?")
+            .bind(self.id)
+            .execute(conn)
+            .await
+            .map_err(Error::Query)?;
+
+        Ok(())
+    }
+
+    /// If the user's current session has a chained session and has reached its projected end
+    /// time, start the chained session right at that time.
+    pub async fn start_due(
+        pool: &Pool<Sqlite>,
+        user_id: i64,
+        now: DateTime<Utc>,
+    ) -> Result<ChainState> {
+        Self::discard_stale(pool).await?;
+
+        let current = match Session::current_session(pool, user_id).await? {
+            // paused sessions don't have an end time yet. We'll hear about it when they resume.
+            Some(current) if !current.get_is_paused() => current,
+            _ => return Ok(ChainState::Idle),
+        };
+
+        let chain = match Self::for_session(pool, current.id).await? {
+            Some(chain) => chain,
+            None => return Ok(ChainState::Idle),
+        };
+
+        let end = current.get_projected_end_time();
+        if end > now {
+            return Ok(ChainState::WaitUntil(end));
+        }
+
+        match chain.start(pool, user_id, &current, end).await {
+            Ok(session) => Ok(ChainState::Started(session)),
+            Err(err) => {
+                // whatever stopped it starting (say, its kind going away) will stop it next time
+                // too, so we give up on it instead of failing every time the scheduler checks.
+                let mut conn = pool.acquire().await.map_err(Error::Query)?;
+                chain.delete(&mut conn).await?;
+
+                Err(err)
+            }
+        }
+    }
+
+    /// Start the chained session at `end`, ending `current` and forgetting the chain
+    async fn start(
+        &self,
+        pool: &Pool<Sqlite>,
+        user_id: i64,
+        current: &Session,
+        end: DateTime<Utc>,
+    ) -> Result<Session> {
+        let duration = match self.duration {
+            Some(duration) => duration,
+            None => Cycle::default_duration(pool, user_id, &self.kind).await?,
+        };
+
+        Session::validate_start(pool, &self.kind, &self.description).await?;
+
+        // the chain goes away with the start, so it can't start twice (or not at all)
+        let mut transaction = pool.begin().await.map_err(Error::Query)?;
+        let session = Session::start_in(
+            &mut transaction,
+            user_id,
+            &self.kind,
+            &self.description,
+            end,
+            duration,
+            current.time_zone,
+            &[],
+            None,
+        )
+        .await?;
+        self.delete(&mut transaction).await?;
+        transaction.commit().await.map_err(Error::Query)?;
+
+        Ok(session)
+    }
+
+    /// Start the user's chained sessions as the sessions they follow end, telling subscribers
+    /// about them. Chains live in the database, so any that came due while the server was down
+    /// start as soon as this does.
+    pub async fn run_scheduler(
+        pool: Pool<Sqlite>,
+        user_id: i64,
+        sender: Arc<Sender<Option<Session>>>,
+    ) {
+        let mut receiver = sender.subscribe();
+
+        loop {
+            let wait = match Self::start_due(&pool, user_id, Utc::now()).await {
+                Ok(ChainState::Started(session)) => {
+                    sender.send_replace(Some(session));
+                    continue;
+                }
+                Ok(ChainState::WaitUntil(next)) => (next - Utc::now())
+                    .to_std()
+                    .unwrap_or_default()
+                    .min(MAXIMUM_WAIT),
+                Ok(ChainState::Idle) => MAXIMUM_WAIT,
+                Err(err) => {
+                    tracing::error!(err = ?err, "could not start chained session, so discarded it");
+                    MAXIMUM_WAIT
+                }
+            };
+
+            // any change to the current session (extending, pausing, starting something else)
+            // might change when the next chained session is due, so we check again.
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => (),
+                changed = receiver.changed() => {
+                    if changed.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::user::DEFAULT_USER_ID;
+    use chrono_tz::Tz;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn get_pool() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new().connect(":memory:").await.unwrap();
+
+        sqlx::migrate!("db/migrations").run(&pool).await.unwrap();
+
+        pool
+    }
+
+    async fn chain_break(pool: &Pool<Sqlite>, session_id: i64) {
+        let follow_up = FollowUp {
+            kind: String::from("break"),
+            description: None,
+            duration: Some(Duration::minutes(5)),
+        };
+
+        Chain::validate(pool, &follow_up).await.unwrap();
+        Chain::create_in(&mut pool.acquire().await.unwrap(), session_id, &follow_up)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn waits_for_the_projected_end_time() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        let task = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        chain_break(&pool, task.id).await;
+
+        assert_eq!(
+            Chain::start_due(&pool, DEFAULT_USER_ID, now).await.unwrap(),
+            ChainState::WaitUntil(now + Duration::minutes(25))
+        );
+    }
+
+    #[tokio::test]
+    async fn starts_the_chained_session_at_the_projected_end_time() {
+        let pool = get_pool().await;
+        let start = Utc::now() - Duration::minutes(30);
+
+        let task = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            start,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        chain_break(&pool, task.id).await;
+
+        let started = match Chain::start_due(&pool, DEFAULT_USER_ID, Utc::now())
+            .await
+            .unwrap()
+        {
+            ChainState::Started(session) => session,
+            other => panic!("expected Started, got {other:?}"),
+        };
+
+        assert_eq!(started.kind, "break");
+        assert_eq!(started.description, "break");
+        assert_eq!(started.start_time, start + Duration::minutes(25));
+        assert_eq!(started.duration, Duration::minutes(5));
+
+        let task = Session::by_id(&pool, DEFAULT_USER_ID, task.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(task.end_time, Some(start + Duration::minutes(25)));
+
+        assert_eq!(Chain::for_session(&pool, task.id).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn discards_chains_that_cannot_start() {
+        let pool = get_pool().await;
+        let start = Utc::now() - Duration::minutes(30);
+
+        let task = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            start,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        // the chain was fine when it was made, but its kind has gone away since
+        sqlx::query(
+            "INSERT INTO session_chains (session_id, kind, description) VALUES (?, 'nap', 'nap')",
+        )
+        .bind(task.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        match Chain::start_due(&pool, DEFAULT_USER_ID, Utc::now()).await {
+            Err(Error::NoSuchKind(kind)) => assert_eq!(kind, "nap"),
+            other => panic!("expected NoSuchKind, got {other:?}"),
+        }
+
+        assert_eq!(Chain::for_session(&pool, task.id).await.unwrap(), None);
+        assert_eq!(
+            Chain::start_due(&pool, DEFAULT_USER_ID, Utc::now())
+                .await
+                .unwrap(),
+            ChainState::Idle
+        );
+        assert_eq!(
+            Session::current_session(&pool, DEFAULT_USER_ID)
+                .await
+                .unwrap()
+                .map(|session| session.id),
+            Some(task.id)
+        );
+    }
+
+    #[tokio::test]
+    async fn forgets_chains_for_sessions_that_were_stopped() {
+        let pool = get_pool().await;
+        let start = Utc::now() - Duration::minutes(30);
+
+        let task = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            start,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        chain_break(&pool, task.id).await;
+
+        Session::stop(&pool, DEFAULT_USER_ID, start + Duration::minutes(10))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            Chain::start_due(&pool, DEFAULT_USER_ID, Utc::now())
+                .await
+                .unwrap(),
+            ChainState::Idle
+        );
+        assert_eq!(Chain::for_session(&pool, task.id).await.unwrap(), None);
+    }
+}
@@ -0,0 +1,130 @@
+use super::chain::Chain;
+use super::error::Result;
+use super::session::Session;
+use super::webhook;
+use sqlx::{Pool, Sqlite};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{watch, Mutex};
+
+pub type SessionSender = Arc<watch::Sender<Option<Session>>>;
+pub type SessionReceiver = watch::Receiver<Option<Session>>;
+
+/// Each user's current session, so their subscribers hear about changes as they happen. A user's
+/// channel is made the first time anything needs it, starting with whatever they're doing now.
+#[derive(Clone)]
+pub struct Channels {
+    pool: Pool<Sqlite>,
+    senders: Arc<Mutex<HashMap<i64, SessionSender>>>,
+
+    /// Should each user's chained session scheduler and webhook dispatcher start along with their
+    /// channel? Only a running server wants them.
+    run_background_tasks: bool,
+}
+
+impl Channels {
+    pub fn new(pool: Pool<Sqlite>, run_background_tasks: bool) -> Self {
+        Self {
+            pool,
+            senders: Arc::new(Mutex::new(HashMap::new())),
+            run_background_tasks,
+        }
+    }
+
+    pub async fn sender(&self, user_id: i64) -> Result<SessionSender> {
+        let mut senders = self.senders.lock().await;
+
+        if let Some(sender) = senders.get(&user_id) {
+            return Ok(sender.clone());
+        }
+
+        let initial = Session::current_session(&self.pool, user_id).await?;
+        let (sender, _) = watch::channel(initial);
+        let sender = Arc::new(sender);
+
+        if self.run_background_tasks {
+            tokio::spawn(Chain::run_scheduler(
+                self.pool.clone(),
+                user_id,
+                sender.clone(),
+            ));
+            tokio::spawn(webhook::run_dispatcher(
+                self.pool.clone(),
+                user_id,
+                sender.subscribe(),
+            ));
+        }
+
+        senders.insert(user_id, sender.clone());
+
+        Ok(sender)
+    }
+
+    pub async fn subscribe(&self, user_id: i64) -> Result<SessionReceiver> {
+        Ok(self.sender(user_id).await?.subscribe())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::user::{User, DEFAULT_USER_ID};
+    use chrono::{Duration, Utc};
+    use chrono_tz::Tz;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn get_pool() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new().connect(":memory:").await.unwrap();
+
+        sqlx::migrate!("db/migrations").run(&pool).await.unwrap();
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn channels_start_with_each_users_current_session() {
+        let pool = get_pool().await;
+        let alice = User::create(&pool, "alice", Utc::now()).await.unwrap();
+
+        let session = Session::start(
+            &pool,
+            alice.id,
+            "task",
+            "alice's thing",
+            Utc::now(),
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        let channels = Channels::new(pool, false);
+
+        assert_eq!(
+            *channels.subscribe(alice.id).await.unwrap().borrow(),
+            Some(session)
+        );
+        assert_eq!(
+            *channels.subscribe(DEFAULT_USER_ID).await.unwrap().borrow(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn users_only_hear_about_their_own_sessions() {
+        let pool = get_pool().await;
+        let alice = User::create(&pool, "alice", Utc::now()).await.unwrap();
+        let channels = Channels::new(pool, false);
+
+        let default_receiver = channels.subscribe(DEFAULT_USER_ID).await.unwrap();
+        let alice_sender = channels.sender(alice.id).await.unwrap();
+
+        alice_sender.send_replace(None);
+
+        assert!(!default_receiver.has_changed().unwrap());
+        assert!(Arc::ptr_eq(
+            &alice_sender,
+            &channels.sender(alice.id).await.unwrap()
+        ));
+    }
+}
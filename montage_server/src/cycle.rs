@@ -0,0 +1,410 @@
+use super::error::{Error, Result};
+use super::kind::Kind;
+use super::settings::Settings;
+use async_graphql::SimpleObject;
+use chrono::{DateTime, Duration, Utc};
+use indoc::indoc;
+use sqlx::{Pool, Sqlite};
+
+/// Where we are in the current Pomodoro set. A set is made of working sessions; once enough of
+/// them are done, it's time for a long break. Long breaks and kinds that end the set (like
+/// offline time) start a new set.
+#[derive(SimpleObject, Debug, PartialEq, Eq)]
+pub struct Cycle {
+    /// How many working sessions have been done since the last long break or offline time?
+    /// (Abandoned sessions don't count.)
+    pub completed_in_set: i64,
+
+    /// How many working sessions make a set
+    pub set_size: i64,
+
+    /// Is the set done, so the next break should be a long one?
+    pub long_break_due: bool,
+
+    /// How long the next break should be
+    pub next_break_suggestion: Duration,
+}
+
+/// The number of a user's working sessions since their last long break (not counting time spent
+/// paused, like reports) or session of a kind that ends the set. ?1 is whether to count a working session that's still running, ?2 is the user, and ?3 is the time to
+/// count as of (sessions that hadn't ended by then don't count), or NULL for right now.
+const COMPLETED_IN_SET: &str = indoc! {"
+    SELECT COUNT(*)
+    FROM sessions
+    WHERE user_id = ?2
+      AND deleted_at IS NULL
+      AND kind IN (SELECT name FROM kinds WHERE is_working)
+      AND outcome != 'abandoned'
+      AND (end_time IS NOT NULL OR ?1)
+      AND (?3 IS NULL OR end_time <= ?3)
+      AND start_time >= COALESCE((
+          SELECT MAX(ended.end_time)
+          FROM sessions AS ended
+          WHERE ended.user_id = ?2
+            AND ended.deleted_at IS NULL
+            AND ended.end_time IS NOT NULL
+            AND (?3 IS NULL OR ended.end_time <= ?3)
+            AND (ended.kind IN (SELECT name FROM kinds WHERE ends_set)
+              OR (ended.kind IN (SELECT name FROM kinds WHERE is_break)
+                AND unixepoch(ended.end_time) - unixepoch(ended.start_time) - COALESCE((
+                    SELECT SUM(MAX(
+                        MIN(
+                            unixepoch(ended.end_time),
+                            COALESCE(unixepoch(session_pauses.end_time), unixepoch(ended.end_time))
+                        ) - MAX(unixepoch(ended.start_time), unixepoch(session_pauses.start_time)),
+                        0
+                    ))
+                    FROM session_pauses
+                    WHERE session_pauses.session_id = ended.id
+                ), 0) > (SELECT short_break_cutoff FROM settings)))
+      ), '')
+"};
+
+impl Cycle {
+    /// Where we are in the set right now. A working session that's still running doesn't count
+    /// yet.
+    pub async fn current(pool: &Pool<Sqlite>, user_id: i64) -> Result<Self> {
+        Self::load(pool, user_id, false, None).await
+    }
+
+    /// Where we were in the set at a given time, like when a session started. Only sessions that
+    /// had ended by then count.
+    pub async fn as_of(pool: &Pool<Sqlite>, user_id: i64, at: DateTime<Utc>) -> Result<Self> {
+        Self::load(pool, user_id, false, Some(at)).await
+    }
+
+    /// Where we'll be in the set once the current session stops, which happens when a new session
+    /// starts.
+    pub async fn after_current_session(pool: &Pool<Sqlite>, user_id: i64) -> Result<Self> {
+        Self::load(pool, user_id, true, None).await
+    }
+
+    async fn load(
+        pool: &Pool<Sqlite>,
+        user_id: i64,
+        count_running: bool,
+        as_of: Option<DateTime<Utc>>,
+    ) -> Result<Self> {
+        let settings = Settings::load(pool).await?;
+
+        let completed_in_set: i64 = sqlx::query_scalar(COMPLETED_IN_SET)
+            .bind(count_running)
+            .bind(user_id)
+            .bind(as_of)
+            .fetch_one(pool)
+            .await
+            .map_err(Error::Query)?;
+
+        let long_break_due = completed_in_set >= settings.set_size;
+
+        Ok(Self {
+            completed_in_set,
+            set_size: settings.set_size,
+            long_break_due,
+            next_break_suggestion: if long_break_due {
+                settings.long_break_duration
+            } else {
+                Kind::by_name(pool, "break").await?.default_duration
+            },
+        })
+    }
+
+    /// How long a new session of the given kind should last if we weren't told. Breaks are long
+    /// once the set is done; everything else uses the kind's default duration.
+    pub async fn default_duration(
+        pool: &Pool<Sqlite>,
+        user_id: i64,
+        kind: &str,
+    ) -> Result<Duration> {
+        let kind = Kind::by_name(pool, kind).await?;
+
+        if kind.is_break {
+            Ok(Self::after_current_session(pool, user_id)
+                .await?
+                .next_break_suggestion)
+        } else {
+            Ok(kind.default_duration)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::outcome::Outcome;
+    use crate::session::Session;
+    use crate::user::DEFAULT_USER_ID;
+    use chrono_tz::Tz;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn get_pool() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new().connect(":memory:").await.unwrap();
+
+        sqlx::migrate!("db/migrations").run(&pool).await.unwrap();
+
+        pool
+    }
+
+    /// Run back-to-back sessions, leaving the last one running
+    async fn run(pool: &Pool<Sqlite>, start: DateTime<Utc>, sessions: &[(&str, i64)]) {
+        let mut next = start;
+
+        for (kind, minutes) in sessions {
+            Session::start(
+                pool,
+                DEFAULT_USER_ID,
+                kind,
+                "description",
+                next,
+                Duration::minutes(*minutes),
+                Tz::UTC,
+            )
+            .await
+            .unwrap();
+
+            next += Duration::minutes(*minutes);
+        }
+    }
+
+    #[tokio::test]
+    async fn counts_finished_tasks() {
+        let pool = get_pool().await;
+
+        run(
+            &pool,
+            Utc::now() - Duration::hours(2),
+            &[("task", 25), ("break", 5), ("task", 25)],
+        )
+        .await;
+
+        let cycle = Cycle::current(&pool, DEFAULT_USER_ID).await.unwrap();
+
+        assert_eq!(cycle.completed_in_set, 1);
+        assert!(!cycle.long_break_due);
+        assert_eq!(cycle.next_break_suggestion, Duration::minutes(5));
+
+        assert_eq!(
+            Cycle::after_current_session(&pool, DEFAULT_USER_ID)
+                .await
+                .unwrap()
+                .completed_in_set,
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn suggests_a_long_break_once_the_set_is_done() {
+        let pool = get_pool().await;
+
+        run(
+            &pool,
+            Utc::now() - Duration::hours(3),
+            &[
+                ("task", 25),
+                ("break", 5),
+                ("task", 25),
+                ("break", 5),
+                ("task", 25),
+                ("break", 5),
+                ("task", 25),
+            ],
+        )
+        .await;
+
+        assert_eq!(
+            Cycle::default_duration(&pool, DEFAULT_USER_ID, "break")
+                .await
+                .unwrap(),
+            Duration::minutes(30)
+        );
+        assert_eq!(
+            Cycle::default_duration(&pool, DEFAULT_USER_ID, "task")
+                .await
+                .unwrap(),
+            Duration::minutes(25)
+        );
+    }
+
+    #[tokio::test]
+    async fn long_breaks_start_a_new_set() {
+        let pool = get_pool().await;
+
+        run(
+            &pool,
+            Utc::now() - Duration::hours(3),
+            &[("task", 25), ("task", 25), ("break", 30), ("task", 25)],
+        )
+        .await;
+
+        assert_eq!(
+            Cycle::current(&pool, DEFAULT_USER_ID)
+                .await
+                .unwrap()
+                .completed_in_set,
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn paused_time_does_not_make_a_break_long() {
+        let pool = get_pool().await;
+        let start = Utc::now() - Duration::hours(3);
+
+        run(
+            &pool,
+            start,
+            &[("task", 25), ("task", 25), ("break", 30), ("task", 25)],
+        )
+        .await;
+
+        // the break was paused for all but five minutes of it
+        sqlx::query(
+            "INSERT INTO session_pauses (session_id, start_time, end_time) VALUES (3, ?, ?)",
+        )
+        .bind(start + Duration::minutes(55))
+        .bind(start + Duration::minutes(80))
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        assert_eq!(
+            Cycle::current(&pool, DEFAULT_USER_ID)
+                .await
+                .unwrap()
+                .completed_in_set,
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn added_kinds_play_their_part_in_the_set() {
+        let pool = get_pool().await;
+
+        Kind::create(
+            &pool,
+            "pairing",
+            Duration::minutes(50),
+            true,
+            false,
+            false,
+            false,
+            "👯",
+        )
+        .await
+        .unwrap();
+        Kind::create(
+            &pool,
+            "walk",
+            Duration::minutes(10),
+            false,
+            true,
+            true,
+            false,
+            "🚶",
+        )
+        .await
+        .unwrap();
+        Kind::create(
+            &pool,
+            "sleep",
+            Duration::hours(8),
+            false,
+            true,
+            false,
+            true,
+            "😴",
+        )
+        .await
+        .unwrap();
+
+        run(
+            &pool,
+            Utc::now() - Duration::hours(12),
+            &[
+                ("task", 25),
+                ("sleep", 5),
+                ("pairing", 50),
+                ("walk", 30),
+                ("pairing", 50),
+                ("walk", 5),
+                ("task", 25),
+            ],
+        )
+        .await;
+
+        // the long walk started a new set, and the short one didn't
+        assert_eq!(
+            Cycle::current(&pool, DEFAULT_USER_ID)
+                .await
+                .unwrap()
+                .completed_in_set,
+            1
+        );
+
+        // sleep ends the set no matter how short it was
+        assert_eq!(
+            Cycle::as_of(
+                &pool,
+                DEFAULT_USER_ID,
+                Utc::now() - Duration::hours(12) + Duration::minutes(80)
+            )
+            .await
+            .unwrap()
+            .completed_in_set,
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn as_of_only_counts_sessions_that_had_ended() {
+        let pool = get_pool().await;
+        let start = Utc::now() - Duration::hours(3);
+
+        run(
+            &pool,
+            start,
+            &[("task", 25), ("break", 5), ("task", 25), ("break", 5)],
+        )
+        .await;
+
+        // as of when the second task started, only the first one was done
+        assert_eq!(
+            Cycle::as_of(&pool, DEFAULT_USER_ID, start + Duration::minutes(30))
+                .await
+                .unwrap()
+                .completed_in_set,
+            1
+        );
+        assert_eq!(
+            Cycle::current(&pool, DEFAULT_USER_ID)
+                .await
+                .unwrap()
+                .completed_in_set,
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn abandoned_tasks_do_not_count() {
+        let pool = get_pool().await;
+
+        run(
+            &pool,
+            Utc::now() - Duration::hours(3),
+            &[("task", 25), ("task", 25)],
+        )
+        .await;
+
+        Session::set_outcome(&pool, DEFAULT_USER_ID, Some(1), Outcome::Abandoned)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            Cycle::current(&pool, DEFAULT_USER_ID)
+                .await
+                .unwrap()
+                .completed_in_set,
+            0
+        );
+    }
+}
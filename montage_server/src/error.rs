@@ -1,8 +1,7 @@
-use super::session::Session;
-
 pub type Result<Whatever> = std::result::Result<Whatever, Error>;
 
 #[derive(Debug, thiserror::Error)]
+#[allow(clippy::enum_variant_names)]
 pub enum Error {
     #[error("query error: {0}")]
     Query(sqlx::Error),
@@ -13,11 +12,94 @@ pub enum Error {
     #[error("there is no current session")]
     NoCurrentSession,
 
-    #[error("error sending new session: {0}")]
-    SendError(tokio::sync::watch::error::SendError<Option<Session>>),
+    #[error("there is no session with ID {0}")]
+    NoSuchSession(i64),
+
+    #[error("there are no sessions yet")]
+    NoSessions,
+
+    #[error("there are no deleted sessions to restore")]
+    NothingToRestore,
+
+    #[error("there's nothing to undo")]
+    NothingToUndo,
+
+    #[error("session {0} has been purged, so the last change can't be undone")]
+    SessionWasPurged(i64),
+
+    #[error("note cannot be blank")]
+    NoteWasBlank,
+
+    #[error("interruption source cannot be blank")]
+    InterruptionSourceWasBlank,
+
+    #[error("there is no planned session with ID {0}")]
+    NoSuchPlannedSession(i64),
+
+    #[error("there are no planned sessions in the queue")]
+    QueueIsEmpty,
+
+    #[error("there is no goal with ID {0}")]
+    NoSuchGoal(i64),
+
+    #[error("there is no kind of session named \"{0}\"")]
+    NoSuchKind(String),
+
+    #[error("there is no webhook with ID {0}")]
+    NoSuchWebhook(i64),
+
+    #[error("there is no API token with ID {0}")]
+    NoSuchToken(i64),
+
+    #[error("there is no user named \"{0}\"")]
+    NoSuchUser(String),
+
+    #[error(
+        "kinds and settings are shared by every user, so only the default user can change them"
+    )]
+    OnlyDefaultUserCanChangeShared,
+
+    #[error("the current session is already paused")]
+    AlreadyPaused,
+
+    #[error("the current session is not paused")]
+    NotPaused,
+
+    #[error("the current session cannot be stopped before it started")]
+    StoppedBeforeStart,
+
+    #[error("the current session cannot be paused before it started")]
+    PausedBeforeStart,
+
+    #[error("the current session cannot be resumed before it was paused")]
+    ResumedBeforePause,
 
     #[error("validation error starting a session: {0}")]
     StartSessionError(StartSessionError),
+
+    #[error("validation error updating a session: {0}")]
+    UpdateSessionError(UpdateSessionError),
+
+    #[error("validation error creating a goal: {0}")]
+    GoalError(GoalError),
+
+    #[error("validation error changing a kind: {0}")]
+    KindError(KindError),
+
+    #[error("validation error changing settings: {0}")]
+    SettingsError(SettingsError),
+
+    #[error("validation error creating a webhook: {0}")]
+    WebhookError(WebhookError),
+
+    #[error("validation error creating an API token: {0}")]
+    TokenError(TokenError),
+
+    #[error("validation error creating a user: {0}")]
+    UserError(UserError),
+
+    #[error("validation error importing sessions: {0}")]
+    ImportError(ImportError),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -25,3 +107,93 @@ pub enum StartSessionError {
     #[error("description cannot be blank")]
     DescriptionWasBlank,
 }
+
+#[derive(Debug, thiserror::Error)]
+pub enum UpdateSessionError {
+    #[error("description cannot be blank")]
+    DescriptionWasBlank,
+
+    #[error("duration must be more than zero")]
+    DurationWasNotPositive,
+
+    #[error("session cannot end before it starts")]
+    EndedBeforeStart,
+
+    #[error("session would overlap session {0}")]
+    OverlapsSession(i64),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GoalError {
+    #[error("goals need either a kind or a tag, but not both")]
+    NeedsKindOrTag,
+
+    #[error("target must be more than zero")]
+    TargetWasNotPositive,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum KindError {
+    #[error("name was blank")]
+    NameWasBlank,
+
+    #[error("there is already a kind named \"{0}\"")]
+    AlreadyExists(String),
+
+    #[error("default duration must be more than zero")]
+    DefaultDurationWasNotPositive,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SettingsError {
+    #[error("short break cutoff must not be negative")]
+    ShortBreakCutoffWasNegative,
+
+    #[error("set size must be at least one")]
+    SetSizeWasNotPositive,
+
+    #[error("long break duration must be more than zero")]
+    LongBreakDurationWasNotPositive,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WebhookError {
+    #[error("URL must start with http:// or https://")]
+    UrlWasNotHttp,
+
+    #[error("secret cannot be blank")]
+    SecretWasBlank,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TokenError {
+    #[error("name cannot be blank")]
+    NameWasBlank,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum UserError {
+    #[error("name cannot be blank")]
+    NameWasBlank,
+
+    #[error("there is already a user named \"{0}\"")]
+    AlreadyExists(String),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ImportError {
+    #[error("description cannot be blank (row {0})")]
+    DescriptionWasBlank(usize),
+
+    #[error("duration must be more than zero (row {0})")]
+    DurationWasNotPositive(usize),
+
+    #[error("session cannot end before it starts (row {0})")]
+    EndedBeforeStart(usize),
+
+    #[error("pause cannot end before it starts (row {0})")]
+    PauseEndedBeforeStart(usize),
+
+    #[error("session would overlap session {1} (row {0})")]
+    OverlapsSession(usize, i64),
+}
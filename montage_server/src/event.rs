@@ -0,0 +1,406 @@
+use super::session::Session;
+use async_graphql::{SimpleObject, Union};
+use chrono::{DateTime, Duration, Utc};
+use futures::stream::Stream;
+use sqlx::{Pool, Sqlite};
+use std::collections::{HashSet, VecDeque};
+use tokio::sync::watch::Receiver;
+
+/// Something that happened to the current session
+#[derive(Union, Debug, PartialEq, Eq, Clone)]
+pub enum Event {
+    SessionStarted(SessionStarted),
+    SessionExtended(SessionExtended),
+    SessionEnded(SessionEnded),
+    SessionOverTime(SessionOverTime),
+    ReminderDue(ReminderDue),
+}
+
+/// A new session became the current session
+#[derive(SimpleObject, Debug, PartialEq, Eq, Clone)]
+pub struct SessionStarted {
+    pub session: Session,
+}
+
+/// The current session's duration changed, moving its projected end time
+#[derive(SimpleObject, Debug, PartialEq, Eq, Clone)]
+pub struct SessionExtended {
+    pub session: Session,
+
+    /// How long the session was supposed to last before the change
+    pub previous_duration: Duration,
+}
+
+/// The current session stopped, either on its own or because another one started
+#[derive(SimpleObject, Debug, PartialEq, Eq, Clone)]
+pub struct SessionEnded {
+    pub session: Session,
+}
+
+/// The current session ran past its projected end time
+#[derive(SimpleObject, Debug, PartialEq, Eq, Clone)]
+pub struct SessionOverTime {
+    pub session: Session,
+}
+
+/// The current session is getting close to its projected end time
+#[derive(SimpleObject, Debug, PartialEq, Eq, Clone)]
+pub struct ReminderDue {
+    pub session: Session,
+
+    /// How long before the projected end time this reminder was asked for
+    pub remaining_time: Duration,
+}
+
+/// Keeps track of what a single subscriber has heard about the current session, so we can tell
+/// them only about what changed.
+#[derive(Debug)]
+struct Tracker {
+    current: Option<Session>,
+    reminders: Vec<Duration>,
+    reminders_given: HashSet<Duration>,
+    over_time_announced: bool,
+}
+
+impl Tracker {
+    fn new(current: Option<Session>, reminders: Vec<Duration>, now: DateTime<Utc>) -> Self {
+        let mut tracker = Self {
+            current,
+            reminders,
+            reminders_given: HashSet::new(),
+            over_time_announced: false,
+        };
+
+        tracker.reset_timers(now);
+
+        tracker
+    }
+
+    /// Skip reminders whose time has already passed (but not the over time announcement, since
+    /// that's still true.)
+    fn reset_timers(&mut self, now: DateTime<Utc>) {
+        self.reminders_given.clear();
+        self.over_time_announced = false;
+
+        if let Some(current) = &self.current {
+            let end = current.get_projected_end_time();
+
+            for reminder in &self.reminders {
+                if end - *reminder <= now {
+                    self.reminders_given.insert(*reminder);
+                }
+            }
+        }
+    }
+
+    fn changed(&mut self, new: Option<Session>, now: DateTime<Utc>) -> Vec<Event> {
+        let mut events = Vec::new();
+
+        // only a different session or a new end time changes which reminders are still to come.
+        // Anything else (a new tag, say) would otherwise repeat the ones already given.
+        let moved = self
+            .current
+            .as_ref()
+            .map(|current| (current.id, current.get_projected_end_time()))
+            != new
+                .as_ref()
+                .map(|new| (new.id, new.get_projected_end_time()));
+
+        match (&self.current, &new) {
+            (None, None) => (),
+            (Some(old), Some(new)) if old.id == new.id => {
+                if old.duration != new.duration {
+                    events.push(Event::SessionExtended(SessionExtended {
+                        session: new.clone(),
+                        previous_duration: old.duration,
+                    }))
+                }
+            }
+            (old, new) => {
+                if let Some(old) = old {
+                    events.push(Event::SessionEnded(SessionEnded {
+                        session: old.clone(),
+                    }))
+                }
+
+                if let Some(new) = new {
+                    events.push(Event::SessionStarted(SessionStarted {
+                        session: new.clone(),
+                    }))
+                }
+            }
+        }
+
+        self.current = new;
+        if moved {
+            self.reset_timers(now);
+        }
+
+        events
+    }
+
+    /// When will the next reminder or over time announcement be due? Paused sessions don't have
+    /// one, since their end time keeps moving until they're resumed.
+    fn next_deadline(&self) -> Option<DateTime<Utc>> {
+        let current = self
+            .current
+            .as_ref()
+            .filter(|current| !current.get_is_paused())?;
+        let end = current.get_projected_end_time();
+
+        self.reminders
+            .iter()
+            .filter(|reminder| !self.reminders_given.contains(reminder))
+            .map(|reminder| end - *reminder)
+            .chain((!self.over_time_announced).then_some(end))
+            .min()
+    }
+
+    fn due(&mut self, now: DateTime<Utc>) -> Vec<Event> {
+        let mut events = Vec::new();
+
+        let current = match self
+            .current
+            .as_ref()
+            .filter(|current| !current.get_is_paused())
+        {
+            Some(current) => current,
+            None => return events,
+        };
+        let end = current.get_projected_end_time();
+
+        for reminder in &self.reminders {
+            if !self.reminders_given.contains(reminder) && end - *reminder <= now {
+                events.push(Event::ReminderDue(ReminderDue {
+                    session: current.clone(),
+                    remaining_time: *reminder,
+                }));
+                self.reminders_given.insert(*reminder);
+            }
+        }
+
+        if !self.over_time_announced && end <= now {
+            events.push(Event::SessionOverTime(SessionOverTime {
+                session: current.clone(),
+            }));
+            self.over_time_announced = true;
+        }
+
+        events
+    }
+}
+
+/// Events about the current session from the time of subscribing on. (Query `currentSession` to
+/// find out what's going on right now.)
+pub fn stream(
+    mut receiver: Receiver<Option<Session>>,
+    pool: Pool<Sqlite>,
+    reminders: Vec<Duration>,
+) -> impl Stream<Item = Event> {
+    let tracker = Tracker::new(receiver.borrow_and_update().clone(), reminders, Utc::now());
+
+    futures::stream::unfold(
+        (receiver, pool, tracker, VecDeque::new()),
+        |(mut receiver, pool, mut tracker, mut pending)| async move {
+            loop {
+                if let Some(event) = pending.pop_front() {
+                    return Some((event, (receiver, pool, tracker, pending)));
+                }
+
+                let wait = tracker
+                    .next_deadline()
+                    .map(|deadline| (deadline - Utc::now()).to_std().unwrap_or_default());
+
+                tokio::select! {
+                    changed = receiver.changed() => {
+                        if changed.is_err() {
+                            return None;
+                        }
+
+                        let new = receiver.borrow_and_update().clone();
+                        for event in tracker.changed(new, Utc::now()) {
+                            pending.push_back(with_end_time(event, &pool).await);
+                        }
+                    }
+                    _ = tokio::time::sleep(wait.unwrap_or_default()), if wait.is_some() => {
+                        pending.extend(tracker.due(Utc::now()));
+                    }
+                }
+            }
+        },
+    )
+}
+
+/// We only have the version of an ended session from before it ended, so look it up again to
+/// get its end time.
+async fn with_end_time(event: Event, pool: &Pool<Sqlite>) -> Event {
+    match event {
+        Event::SessionEnded(ended) => {
+            match Session::by_id(pool, ended.session.user_id, ended.session.id).await {
+                Ok(Some(session)) => Event::SessionEnded(SessionEnded { session }),
+                Ok(None) => Event::SessionEnded(ended),
+                Err(err) => {
+                    tracing::error!(err = ?err, "could not look up ended session");
+                    Event::SessionEnded(ended)
+                }
+            }
+        }
+        event => event,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::user::DEFAULT_USER_ID;
+    use chrono_tz::Tz;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn get_pool() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new().connect(":memory:").await.unwrap();
+
+        sqlx::migrate!("db/migrations").run(&pool).await.unwrap();
+
+        pool
+    }
+
+    async fn start(pool: &Pool<Sqlite>, start_time: DateTime<Utc>) -> Session {
+        Session::start(
+            pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            start_time,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn starting_after_nothing() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+        let session = start(&pool, now).await;
+
+        let mut tracker = Tracker::new(None, Vec::new(), now);
+
+        assert_eq!(
+            tracker.changed(Some(session.clone()), now),
+            vec![Event::SessionStarted(SessionStarted { session })]
+        );
+    }
+
+    #[tokio::test]
+    async fn starting_after_another_session() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+        let first = start(&pool, now).await;
+        let second = start(&pool, now).await;
+
+        let mut tracker = Tracker::new(Some(first.clone()), Vec::new(), now);
+
+        assert_eq!(
+            tracker.changed(Some(second.clone()), now),
+            vec![
+                Event::SessionEnded(SessionEnded { session: first }),
+                Event::SessionStarted(SessionStarted { session: second }),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn extending() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+        let session = start(&pool, now).await;
+        let extended = Session::extend_by(&pool, DEFAULT_USER_ID, Duration::minutes(5))
+            .await
+            .unwrap();
+
+        let mut tracker = Tracker::new(Some(session), Vec::new(), now);
+
+        assert_eq!(
+            tracker.changed(Some(extended.clone()), now),
+            vec![Event::SessionExtended(SessionExtended {
+                session: extended,
+                previous_duration: Duration::minutes(25),
+            })]
+        );
+    }
+
+    #[tokio::test]
+    async fn unrelated_changes_are_quiet() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+        let session = start(&pool, now - Duration::minutes(30)).await;
+
+        let mut tracker = Tracker::new(Some(session.clone()), vec![Duration::minutes(5)], now);
+
+        assert_eq!(
+            tracker.due(now),
+            vec![Event::SessionOverTime(SessionOverTime {
+                session: session.clone()
+            })]
+        );
+
+        assert_eq!(tracker.changed(Some(session), now), Vec::new());
+        assert_eq!(tracker.next_deadline(), None);
+        assert_eq!(tracker.due(now), Vec::new());
+    }
+
+    #[tokio::test]
+    async fn reminders_and_over_time() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+        let session = start(&pool, now).await;
+        let end = now + Duration::minutes(25);
+
+        let mut tracker = Tracker::new(
+            Some(session.clone()),
+            vec![Duration::minutes(5), Duration::minutes(1)],
+            now,
+        );
+
+        assert_eq!(tracker.next_deadline(), Some(end - Duration::minutes(5)));
+        assert_eq!(tracker.due(now), Vec::new());
+
+        assert_eq!(
+            tracker.due(end - Duration::minutes(5)),
+            vec![Event::ReminderDue(ReminderDue {
+                session: session.clone(),
+                remaining_time: Duration::minutes(5),
+            })]
+        );
+        assert_eq!(tracker.next_deadline(), Some(end - Duration::minutes(1)));
+
+        assert_eq!(
+            tracker.due(end),
+            vec![
+                Event::ReminderDue(ReminderDue {
+                    session: session.clone(),
+                    remaining_time: Duration::minutes(1),
+                }),
+                Event::SessionOverTime(SessionOverTime { session }),
+            ]
+        );
+        assert_eq!(tracker.next_deadline(), None);
+        assert_eq!(tracker.due(end + Duration::minutes(1)), Vec::new());
+    }
+
+    #[tokio::test]
+    async fn skips_reminders_that_already_passed() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+        let session = start(&pool, now - Duration::minutes(22)).await;
+
+        let tracker = Tracker::new(
+            Some(session),
+            vec![Duration::minutes(5), Duration::minutes(1)],
+            now,
+        );
+
+        assert_eq!(tracker.next_deadline(), Some(now + Duration::minutes(2)));
+    }
+}
@@ -0,0 +1,339 @@
+use super::error::{Error, GoalError, Result};
+use super::kind::Kind;
+use super::report::Totals;
+use super::time_zone;
+use async_graphql::SimpleObject;
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, Utc};
+use chrono_tz::Tz;
+use indoc::indoc;
+use sqlx::{sqlite::SqliteRow, FromRow, Pool, Row, Sqlite};
+use std::collections::HashMap;
+
+/// How often does a goal start over?
+#[derive(async_graphql::Enum, Debug, PartialEq, Eq, Copy, Clone, sqlx::Type, Hash)]
+#[sqlx(rename_all = "lowercase")]
+pub enum GoalPeriod {
+    /// Every day, starting at midnight
+    Day,
+
+    /// Every week, starting at midnight on Monday
+    Week,
+}
+
+impl GoalPeriod {
+    /// The start and end of the period containing the given date.
+    pub fn range_containing(
+        &self,
+        date: NaiveDate,
+        time_zone: Tz,
+    ) -> (DateTime<Utc>, DateTime<Utc>) {
+        let (first_day, days) = match self {
+            Self::Day => (date, 1),
+            Self::Week => (
+                date - Duration::days(date.weekday().num_days_from_monday().into()),
+                7,
+            ),
+        };
+
+        (
+            time_zone::start_of_day(first_day, time_zone),
+            time_zone::start_of_day(first_day + Duration::days(days), time_zone),
+        )
+    }
+}
+
+/// Is a goal's target something to reach or something to stay under?
+#[derive(async_graphql::Enum, Debug, PartialEq, Eq, Copy, Clone, sqlx::Type)]
+#[sqlx(rename_all = "lowercase")]
+pub enum GoalBound {
+    /// Spend at least the target amount of time
+    Min,
+
+    /// Spend no more than the target amount of time
+    Max,
+}
+
+/// An amount of time to spend (or not spend) on a kind of session or a tag every day or week
+#[derive(SimpleObject, Debug, PartialEq, Eq, Clone)]
+pub struct Goal {
+    /// The ID of this goal
+    pub id: i64,
+
+    /// Which kind of session counts towards this goal? Either this or `tag` is set.
+    pub kind: Option<String>,
+
+    /// Which tag counts towards this goal? Either this or `kind` is set.
+    pub tag: Option<String>,
+
+    /// How often does this goal start over?
+    pub period: GoalPeriod,
+
+    /// How much time are we aiming for?
+    pub target: Duration,
+
+    /// Is the target a minimum or a maximum?
+    pub bound: GoalBound,
+}
+
+impl FromRow<'_, SqliteRow> for Goal {
+    fn from_row(row: &SqliteRow) -> std::result::Result<Self, sqlx::Error> {
+        Ok(Self {
+            id: row.try_get("id")?,
+            kind: row.try_get("kind")?,
+            tag: row.try_get("tag")?,
+            period: row.try_get("period")?,
+            target: Duration::seconds(row.try_get("target")?),
+            bound: row.try_get("bound")?,
+        })
+    }
+}
+
+/// How a goal is going in a single period
+#[derive(SimpleObject, Debug, PartialEq, Eq)]
+pub struct GoalProgress {
+    /// The goal we're measuring
+    pub goal: Goal,
+
+    /// When does this period start?
+    pub start: DateTime<FixedOffset>,
+
+    /// When does this period end?
+    pub end: DateTime<FixedOffset>,
+
+    /// How much time has counted towards the goal in this period?
+    pub actual: Duration,
+
+    /// Is the goal met? Minimum goals are met once the target is reached. Maximum goals are met as
+    /// long as the time stays at or under the target.
+    pub met: bool,
+}
+
+impl Goal {
+    pub async fn all(pool: &Pool<Sqlite>, user_id: i64) -> Result<Vec<Self>> {
+        sqlx::query_as::<_, Self>("SELECT * FROM goals WHERE user_id = ? ORDER BY id ASC")
+            .bind(user_id)
+            .fetch_all(pool)
+            .await
+            .map_err(Error::Query)
+    }
+
+    pub async fn create(
+        pool: &Pool<Sqlite>,
+        user_id: i64,
+        kind: Option<&str>,
+        tag: Option<&str>,
+        period: GoalPeriod,
+        target: Duration,
+        bound: GoalBound,
+    ) -> Result<Self> {
+        let tag = tag.map(str::trim).filter(|tag| !tag.is_empty());
+
+        if kind.is_some() == tag.is_some() {
+            return Err(Error::GoalError(GoalError::NeedsKindOrTag));
+        }
+
+        if target <= Duration::zero() {
+            return Err(Error::GoalError(GoalError::TargetWasNotPositive));
+        }
+
+        if let Some(kind) = kind {
+            Kind::by_name(pool, kind).await?;
+        }
+
+        let goal = sqlx::query_as::<_, Self>(indoc! {"
+            INSERT INTO goals (user_id, kind, tag, period, target, bound)
+            VALUES (?, ?, ?, ?, ?, ?)
+            RETURNING *
+        "})
+        .bind(user_id)
+        .bind(kind)
+        .bind(tag)
+        .bind(period)
+        .bind(target.num_seconds())
+        .bind(bound)
+        .fetch_one(pool)
+        .await
+        .map_err(Error::Query)?;
+
+        tracing::info!(id = goal.id, "created goal");
+
+        Ok(goal)
+    }
+
+    pub async fn delete(pool: &Pool<Sqlite>, user_id: i64, id: i64) -> Result<Self> {
+        let goal =
+            sqlx::query_as::<_, Self>("DELETE FROM goals WHERE id = ? AND user_id = ? RETURNING *")
+                .bind(id)
+                .bind(user_id)
+                .fetch_optional(pool)
+                .await
+                .map_err(Error::Query)?
+                .ok_or(Error::NoSuchGoal(id))?;
+
+        tracing::info!(id = goal.id, "deleted goal");
+
+        Ok(goal)
+    }
+
+    /// How are all the user's goals going in the periods that contain the given date?
+    pub async fn progress(
+        pool: &Pool<Sqlite>,
+        user_id: i64,
+        date: NaiveDate,
+        time_zone: Tz,
+    ) -> Result<Vec<GoalProgress>> {
+        let mut totals_by_period: HashMap<GoalPeriod, Totals> = HashMap::new();
+        let mut out = Vec::new();
+
+        for goal in Self::all(pool, user_id).await? {
+            let (start, end) = goal.period.range_containing(date, time_zone);
+
+            let totals = match totals_by_period.entry(goal.period) {
+                std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                std::collections::hash_map::Entry::Vacant(entry) => {
+                    entry.insert(Totals::for_range(pool, user_id, start, end).await?)
+                }
+            };
+
+            let actual = match (&goal.kind, &goal.tag) {
+                (Some(kind), _) => totals.time_for_kind(kind),
+                (None, Some(tag)) => totals.time_for_tag(tag),
+                (None, None) => Duration::zero(),
+            };
+
+            let met = match goal.bound {
+                GoalBound::Min => actual >= goal.target,
+                GoalBound::Max => actual <= goal.target,
+            };
+
+            out.push(GoalProgress {
+                goal,
+                start: start.with_timezone(&time_zone).fixed_offset(),
+                end: end.with_timezone(&time_zone).fixed_offset(),
+                actual,
+                met,
+            });
+        }
+
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::session::Session;
+    use crate::user::DEFAULT_USER_ID;
+    use chrono::TimeZone;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn get_pool() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new().connect(":memory:").await.unwrap();
+
+        sqlx::migrate!("db/migrations").run(&pool).await.unwrap();
+
+        pool
+    }
+
+    #[test]
+    fn weeks_start_on_monday() {
+        // October 18th, 2023 was a Wednesday
+        let (start, end) = GoalPeriod::Week
+            .range_containing(NaiveDate::from_ymd_opt(2023, 10, 18).unwrap(), Tz::UTC);
+
+        assert_eq!(start, Utc.with_ymd_and_hms(2023, 10, 16, 0, 0, 0).unwrap());
+        assert_eq!(end, Utc.with_ymd_and_hms(2023, 10, 23, 0, 0, 0).unwrap());
+    }
+
+    #[tokio::test]
+    async fn goals_need_exactly_one_of_kind_or_tag() {
+        let pool = get_pool().await;
+
+        for (kind, tag) in [(None, None), (Some("task"), Some("ops"))] {
+            match Goal::create(
+                &pool,
+                DEFAULT_USER_ID,
+                kind,
+                tag,
+                GoalPeriod::Day,
+                Duration::hours(4),
+                GoalBound::Min,
+            )
+            .await
+            {
+                Err(Error::GoalError(GoalError::NeedsKindOrTag)) => (),
+                other => panic!("expected NeedsKindOrTag, got {other:?}"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn progress_counts_time_in_the_current_period() {
+        let pool = get_pool().await;
+        let morning = Utc.with_ymd_and_hms(2023, 10, 18, 9, 0, 0).unwrap();
+
+        Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "write code",
+            morning,
+            Duration::hours(3),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "meeting",
+            "standup",
+            morning + Duration::hours(3),
+            Duration::hours(3),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        Session::stop(&pool, DEFAULT_USER_ID, morning + Duration::hours(6))
+            .await
+            .unwrap();
+
+        let tasks = Goal::create(
+            &pool,
+            DEFAULT_USER_ID,
+            Some("task"),
+            None,
+            GoalPeriod::Day,
+            Duration::hours(4),
+            GoalBound::Min,
+        )
+        .await
+        .unwrap();
+        let meetings = Goal::create(
+            &pool,
+            DEFAULT_USER_ID,
+            Some("meeting"),
+            None,
+            GoalPeriod::Week,
+            Duration::hours(2),
+            GoalBound::Max,
+        )
+        .await
+        .unwrap();
+
+        let progress = Goal::progress(&pool, DEFAULT_USER_ID, morning.date_naive(), Tz::UTC)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            progress
+                .iter()
+                .map(|progress| (progress.goal.id, progress.actual, progress.met))
+                .collect::<Vec<_>>(),
+            vec![
+                (tasks.id, Duration::hours(3), false),
+                (meetings.id, Duration::hours(3), false),
+            ]
+        );
+    }
+}
@@ -0,0 +1,508 @@
+use super::audit::Change;
+use super::error::{Error, ImportError, Result};
+use super::interruption::Interruption;
+use super::kind::Kind;
+use super::note::Note;
+use super::outcome::Outcome;
+use super::pause::Pause;
+use super::session::Session;
+use super::tag::Tag;
+use super::time_zone;
+use async_graphql::{Enum, InputObject, SimpleObject};
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use indoc::indoc;
+use serde_json::json;
+use sqlx::{Pool, Sqlite};
+
+/// A finished session from somewhere else, like another time tracker or a montage export
+#[derive(InputObject, Debug, Clone)]
+pub struct ImportedSession {
+    /// What kind of session is this? (The name of a kind that already exists.)
+    pub kind: String,
+
+    /// What was going on in this session?
+    pub description: String,
+
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+
+    /// How much time was committed to this session? (If omitted, the time between the start and
+    /// end.)
+    pub duration: Option<Duration>,
+
+    /// The IANA time zone this session happened in (If omitted, the server's time zone)
+    pub time_zone: Option<Tz>,
+
+    /// Tags for grouping this session with related sessions in reports
+    #[graphql(default)]
+    pub tags: Vec<String>,
+
+    /// How did this session turn out? (If omitted, unknown)
+    pub outcome: Option<Outcome>,
+
+    /// When the session was paused
+    #[graphql(default)]
+    pub pauses: Vec<ImportedPause>,
+
+    /// Notes about what got done during the session
+    #[graphql(default)]
+    pub notes: Vec<ImportedNote>,
+
+    /// Things that broke focus during the session
+    #[graphql(default)]
+    pub interruptions: Vec<ImportedInterruption>,
+}
+
+/// A finished pause in an imported session
+#[derive(InputObject, Debug, Clone)]
+pub struct ImportedPause {
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+}
+
+/// A note on an imported session
+#[derive(InputObject, Debug, Clone)]
+pub struct ImportedNote {
+    pub created_at: DateTime<Utc>,
+    pub text: String,
+}
+
+/// An interruption logged during an imported session
+#[derive(InputObject, Debug, Clone)]
+pub struct ImportedInterruption {
+    pub created_at: DateTime<Utc>,
+    pub source: String,
+    pub note: Option<String>,
+}
+
+/// What to do with imported sessions that overlap sessions we already have (including ones
+/// earlier in the same import)
+#[derive(Enum, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OnOverlap {
+    /// Leave them out and import the rest
+    Skip,
+
+    /// Don't import anything
+    Reject,
+}
+
+/// An imported session that was left out because it overlapped another
+#[derive(SimpleObject, Debug, PartialEq, Eq)]
+pub struct SkippedSession {
+    /// Where this session was in the import, starting at 1
+    pub row: i64,
+
+    pub description: String,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+
+    /// The ID of the session it overlapped
+    pub overlaps: i64,
+}
+
+/// What happened (or would happen, in a dry run) to the sessions in an import
+#[derive(SimpleObject, Debug)]
+pub struct ImportResult {
+    /// The sessions that were created. In a dry run nothing is saved, so their IDs don't mean
+    /// anything.
+    pub created: Vec<Session>,
+
+    /// The sessions that were left out because they overlapped others
+    pub skipped: Vec<SkippedSession>,
+}
+
+impl ImportResult {
+    /// Add sessions to a user's history, all at once: if any of them can't be imported (or they
+    /// overlap and we're rejecting overlaps) none of them are. A dry run checks everything the
+    /// same way but doesn't save anything.
+    pub async fn import(
+        pool: &Pool<Sqlite>,
+        user_id: i64,
+        sessions: &[ImportedSession],
+        on_overlap: OnOverlap,
+        dry_run: bool,
+    ) -> Result<Self> {
+        for (index, session) in sessions.iter().enumerate() {
+            let row = index + 1;
+
+            if session.description.trim().is_empty() {
+                return Err(Error::ImportError(ImportError::DescriptionWasBlank(row)));
+            }
+
+            if session.end_time <= session.start_time {
+                return Err(Error::ImportError(ImportError::EndedBeforeStart(row)));
+            }
+
+            if session
+                .duration
+                .is_some_and(|duration| duration <= Duration::zero())
+            {
+                return Err(Error::ImportError(ImportError::DurationWasNotPositive(row)));
+            }
+
+            if session
+                .pauses
+                .iter()
+                .any(|pause| pause.end_time <= pause.start_time)
+            {
+                return Err(Error::ImportError(ImportError::PauseEndedBeforeStart(row)));
+            }
+
+            Kind::by_name(pool, &session.kind).await?;
+        }
+
+        let mut transaction = pool.begin().await.map_err(Error::Query)?;
+        let mut change = Change::new(
+            user_id,
+            "importSessions",
+            json!({
+                "count": sessions.len(),
+                "onOverlap": format!("{on_overlap:?}").to_lowercase(),
+            }),
+        );
+
+        let mut created = Vec::new();
+        let mut skipped = Vec::new();
+
+        for (index, imported) in sessions.iter().enumerate() {
+            let row = index + 1;
+
+            let overlapping: Option<i64> = sqlx::query_scalar(indoc! {"
+                SELECT id
+                FROM sessions
+                WHERE user_id = ?
+                  AND deleted_at IS NULL
+                  AND start_time < ?
+                  AND (end_time IS NULL OR end_time > ?)
+                ORDER BY start_time ASC
+                LIMIT 1
+            "})
+            .bind(user_id)
+            .bind(imported.end_time)
+            .bind(imported.start_time)
+            .fetch_optional(&mut *transaction)
+            .await
+            .map_err(Error::Query)?;
+
+            if let Some(overlaps) = overlapping {
+                match on_overlap {
+                    OnOverlap::Reject => {
+                        return Err(Error::ImportError(ImportError::OverlapsSession(
+                            row, overlaps,
+                        )))
+                    }
+                    OnOverlap::Skip => {
+                        skipped.push(SkippedSession {
+                            row: row as i64,
+                            description: imported.description.clone(),
+                            start_time: imported.start_time,
+                            end_time: imported.end_time,
+                            overlaps,
+                        });
+                        continue;
+                    }
+                }
+            }
+
+            let mut session = sqlx::query_as::<_, Session>(indoc! {"
+                INSERT INTO sessions
+                    (user_id, kind, description, start_time, duration, end_time, time_zone, outcome)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                RETURNING id, user_id, kind, description, start_time, duration, end_time, time_zone, outcome;
+            "})
+            .bind(user_id)
+            .bind(&imported.kind)
+            .bind(imported.description.trim())
+            .bind(imported.start_time)
+            .bind(
+                imported
+                    .duration
+                    .unwrap_or(imported.end_time - imported.start_time)
+                    .num_seconds(),
+            )
+            .bind(imported.end_time)
+            .bind(imported.time_zone.unwrap_or_else(time_zone::local).name())
+            .bind(imported.outcome.unwrap_or_default())
+            .fetch_one(&mut *transaction)
+            .await
+            .map_err(Error::Query)?;
+
+            session.tags = Tag::normalize(&imported.tags);
+            Tag::attach(&mut transaction, session.id, &session.tags).await?;
+            session.tags.sort();
+
+            for pause in &imported.pauses {
+                sqlx::query(
+                    "INSERT INTO session_pauses (session_id, start_time, end_time) VALUES (?, ?, ?)",
+                )
+                .bind(session.id)
+                .bind(pause.start_time)
+                .bind(pause.end_time)
+                .execute(&mut *transaction)
+                .await
+                .map_err(Error::Query)?;
+
+                session.pauses.push(Pause {
+                    session_id: session.id,
+                    start_time: pause.start_time,
+                    end_time: Some(pause.end_time),
+                });
+            }
+
+            for note in &imported.notes {
+                sqlx::query(
+                    "INSERT INTO session_notes (session_id, created_at, text) VALUES (?, ?, ?)",
+                )
+                .bind(session.id)
+                .bind(note.created_at)
+                .bind(&note.text)
+                .execute(&mut *transaction)
+                .await
+                .map_err(Error::Query)?;
+
+                session.notes.push(Note {
+                    session_id: session.id,
+                    created_at: note.created_at,
+                    text: note.text.clone(),
+                });
+            }
+
+            for interruption in &imported.interruptions {
+                sqlx::query(indoc! {"
+                    INSERT INTO interruptions (session_id, created_at, source, note)
+                    VALUES (?, ?, ?, ?)
+                "})
+                .bind(session.id)
+                .bind(interruption.created_at)
+                .bind(&interruption.source)
+                .bind(&interruption.note)
+                .execute(&mut *transaction)
+                .await
+                .map_err(Error::Query)?;
+
+                session.interruptions.push(Interruption {
+                    session_id: session.id,
+                    created_at: interruption.created_at,
+                    source: interruption.source.clone(),
+                    note: interruption.note.clone(),
+                });
+            }
+
+            change.record(&mut transaction, session.id, None).await?;
+
+            created.push(session);
+        }
+
+        if dry_run {
+            transaction.rollback().await.map_err(Error::Query)?;
+        } else {
+            transaction.commit().await.map_err(Error::Query)?;
+        }
+
+        tracing::info!(
+            created = created.len(),
+            skipped = skipped.len(),
+            dry_run,
+            "imported sessions"
+        );
+
+        Ok(Self { created, skipped })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::user::DEFAULT_USER_ID;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn get_pool() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new().connect(":memory:").await.unwrap();
+
+        sqlx::migrate!("db/migrations").run(&pool).await.unwrap();
+
+        pool
+    }
+
+    fn imported(description: &str, start_time: DateTime<Utc>, minutes: i64) -> ImportedSession {
+        ImportedSession {
+            kind: String::from("task"),
+            description: description.to_string(),
+            start_time,
+            end_time: start_time + Duration::minutes(minutes),
+            duration: None,
+            time_zone: Some(Tz::UTC),
+            tags: vec![String::from("imported")],
+            outcome: None,
+            pauses: Vec::new(),
+            notes: Vec::new(),
+            interruptions: Vec::new(),
+        }
+    }
+
+    async fn all_sessions(pool: &Pool<Sqlite>, around: DateTime<Utc>) -> Vec<Session> {
+        Session::for_range(
+            pool,
+            DEFAULT_USER_ID,
+            around - Duration::days(1),
+            around + Duration::days(1),
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn import_creates_finished_sessions() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        let result = ImportResult::import(
+            &pool,
+            DEFAULT_USER_ID,
+            &[
+                imported("first", now, 25),
+                imported("second", now + Duration::minutes(30), 10),
+            ],
+            OnOverlap::Reject,
+            false,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.created.len(), 2);
+        assert_eq!(result.skipped, Vec::new());
+        assert_eq!(all_sessions(&pool, now).await, result.created);
+        assert_eq!(result.created[0].duration, Duration::minutes(25));
+        assert_eq!(result.created[0].tags, vec![String::from("imported")]);
+    }
+
+    #[tokio::test]
+    async fn dry_runs_do_not_save_anything() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        let result = ImportResult::import(
+            &pool,
+            DEFAULT_USER_ID,
+            &[imported("first", now, 25)],
+            OnOverlap::Reject,
+            true,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.created.len(), 1);
+        assert_eq!(all_sessions(&pool, now).await, Vec::new());
+    }
+
+    #[tokio::test]
+    async fn overlapping_sessions_can_be_skipped() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        let existing = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "existing",
+            now,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        Session::stop(&pool, DEFAULT_USER_ID, now + Duration::minutes(25))
+            .await
+            .unwrap();
+
+        let result = ImportResult::import(
+            &pool,
+            DEFAULT_USER_ID,
+            &[
+                imported("before", now - Duration::minutes(30), 30),
+                imported("during", now + Duration::minutes(10), 30),
+            ],
+            OnOverlap::Skip,
+            false,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.created.len(), 1);
+        assert_eq!(result.created[0].description, "before");
+        assert_eq!(result.skipped.len(), 1);
+        assert_eq!(result.skipped[0].row, 2);
+        assert_eq!(result.skipped[0].overlaps, existing.id);
+    }
+
+    #[tokio::test]
+    async fn overlapping_sessions_can_reject_the_whole_import() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        // these two overlap each other
+        let result = ImportResult::import(
+            &pool,
+            DEFAULT_USER_ID,
+            &[imported("first", now, 30), imported("second", now, 30)],
+            OnOverlap::Reject,
+            false,
+        )
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::ImportError(ImportError::OverlapsSession(2, _)))
+        ));
+        assert_eq!(all_sessions(&pool, now).await, Vec::new());
+    }
+
+    #[tokio::test]
+    async fn sessions_must_end_after_they_start() {
+        let pool = get_pool().await;
+
+        assert!(matches!(
+            ImportResult::import(
+                &pool,
+                DEFAULT_USER_ID,
+                &[imported("backwards", Utc::now(), -5)],
+                OnOverlap::Reject,
+                false,
+            )
+            .await,
+            Err(Error::ImportError(ImportError::EndedBeforeStart(1)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn import_keeps_pauses_notes_and_interruptions() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        let mut session = imported("paused", now, 30);
+        session.pauses.push(ImportedPause {
+            start_time: now + Duration::minutes(5),
+            end_time: now + Duration::minutes(10),
+        });
+        session.notes.push(ImportedNote {
+            created_at: now + Duration::minutes(20),
+            text: String::from("got it done"),
+        });
+        session.interruptions.push(ImportedInterruption {
+            created_at: now + Duration::minutes(15),
+            source: String::from("slack ping"),
+            note: None,
+        });
+
+        let result =
+            ImportResult::import(&pool, DEFAULT_USER_ID, &[session], OnOverlap::Reject, false)
+                .await
+                .unwrap();
+
+        assert_eq!(all_sessions(&pool, now).await, result.created);
+        assert_eq!(result.created[0].pauses.len(), 1);
+        assert_eq!(result.created[0].notes.len(), 1);
+        assert_eq!(result.created[0].interruptions.len(), 1);
+    }
+}
@@ -0,0 +1,68 @@
+use super::error::{Error, Result};
+use async_graphql::SimpleObject;
+use chrono::{DateTime, Utc};
+use indoc::indoc;
+use sqlx::{FromRow, Pool, Sqlite, SqliteConnection};
+
+/// Something that broke your focus during a session
+#[derive(SimpleObject, FromRow, Debug, PartialEq, Eq, Clone)]
+pub struct Interruption {
+    /// The session this interruption happened during
+    #[graphql(skip)]
+    pub session_id: i64,
+
+    /// When did the interruption happen?
+    pub created_at: DateTime<Utc>,
+
+    /// Where did the interruption come from? (For example, "slack ping" or "coworker")
+    pub source: String,
+
+    /// Anything else worth remembering about the interruption
+    pub note: Option<String>,
+}
+
+impl Interruption {
+    pub async fn log(
+        conn: &mut SqliteConnection,
+        session_id: i64,
+        source: &str,
+        note: Option<&str>,
+        created_at: DateTime<Utc>,
+    ) -> Result<Self> {
+        sqlx::query_as::<_, Self>(indoc! {"
+            INSERT INTO interruptions (session_id, created_at, source, note)
+            VALUES (?, ?, ?, ?)
+            RETURNING session_id, created_at, source, note;
+        "})
+        .bind(session_id)
+        .bind(created_at)
+        .bind(source)
+        .bind(note)
+        .fetch_one(conn)
+        .await
+        .map_err(Error::Query)
+    }
+
+    pub async fn for_sessions(pool: &Pool<Sqlite>, session_ids: &[i64]) -> Result<Vec<Self>> {
+        if session_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let ids = session_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+
+        sqlx::query_as::<_, Self>(indoc! {"
+            SELECT session_id, created_at, source, note
+            FROM interruptions
+            WHERE session_id IN (SELECT value FROM json_each(?))
+            ORDER BY created_at ASC
+        "})
+        .bind(format!("[{ids}]"))
+        .fetch_all(pool)
+        .await
+        .map_err(Error::Query)
+    }
+}
@@ -1,68 +1,283 @@
+use super::error::{Error, KindError, Result};
+use async_graphql::SimpleObject;
 use chrono::Duration;
-use once_cell::sync::Lazy;
+use indoc::indoc;
+use sqlx::{sqlite::SqliteRow, FromRow, Pool, Row, Sqlite};
 
-/// What kind of session are we going to have?
-#[derive(async_graphql::Enum, Debug, PartialEq, Eq, Copy, Clone, sqlx::Type, Hash)]
-#[sqlx(rename_all = "lowercase")]
-pub enum Kind {
-    /// A session focused on doing something
-    Task,
+/// What kind of session are we going to have? Sessions refer to kinds by name. Out of the box we
+/// have `task`, `break`, `meeting`, and `offline`, but you can add your own.
+#[derive(SimpleObject, Debug, PartialEq, Eq, Clone)]
+pub struct Kind {
+    /// The name sessions use to refer to this kind
+    pub name: String,
 
-    /// A recovery session
-    Break,
+    /// How long sessions of this kind last if we're not told otherwise
+    pub default_duration: Duration,
 
-    /// A session where you're in a meeting (helpful for reporting and for silencing the vexer.)
-    Meeting,
+    /// Does time spent in sessions of this kind count as working time? (Short breaks count no
+    /// matter what this says.)
+    pub is_working: bool,
 
-    /// Time when you're totally offline
-    Offline,
+    /// Should the vexer nag when a session of this kind runs over? If not, it stays quiet for the
+    /// whole session (helpful for meetings.)
+    pub nags: bool,
+
+    /// Are sessions of this kind breaks? Breaks longer than the short break cutoff are long breaks,
+    /// which start a new Pomodoro set.
+    pub is_break: bool,
+
+    /// Does any session of this kind start a new Pomodoro set, no matter how long it is? (Like
+    /// going offline for the night.)
+    pub ends_set: bool,
+
+    /// An emoji to show for sessions of this kind
+    pub emoji: String,
+}
+
+/// Changes to make to a kind. Fields left as `None` are not changed.
+#[derive(Debug, Default)]
+pub struct KindChanges {
+    pub default_duration: Option<Duration>,
+    pub is_working: Option<bool>,
+    pub nags: Option<bool>,
+    pub is_break: Option<bool>,
+    pub ends_set: Option<bool>,
+    pub emoji: Option<String>,
+}
+
+impl FromRow<'_, SqliteRow> for Kind {
+    fn from_row(row: &SqliteRow) -> std::result::Result<Self, sqlx::Error> {
+        Ok(Self {
+            name: row.try_get("name")?,
+            default_duration: Duration::seconds(row.try_get("default_duration")?),
+            is_working: row.try_get("is_working")?,
+            nags: row.try_get("nags")?,
+            is_break: row.try_get("is_break")?,
+            ends_set: row.try_get("ends_set")?,
+            emoji: row.try_get("emoji")?,
+        })
+    }
 }
 
 impl Kind {
-    pub fn default_session_length(&self) -> Duration {
-        match self {
-            Self::Task => Duration::minutes(25),
-            Self::Break => Duration::minutes(5),
-            Self::Meeting => Duration::minutes(60),
-            Self::Offline => Duration::minutes(60),
+    pub async fn all(pool: &Pool<Sqlite>) -> Result<Vec<Self>> {
+        sqlx::query_as::<_, Self>("SELECT * FROM kinds ORDER BY name ASC")
+            .fetch_all(pool)
+            .await
+            .map_err(Error::Query)
+    }
+
+    pub async fn by_name(pool: &Pool<Sqlite>, name: &str) -> Result<Self> {
+        sqlx::query_as::<_, Self>("SELECT * FROM kinds WHERE name = ?")
+            .bind(name)
+            .fetch_optional(pool)
+            .await
+            .map_err(Error::Query)?
+            .ok_or_else(|| Error::NoSuchKind(name.to_string()))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create(
+        pool: &Pool<Sqlite>,
+        name: &str,
+        default_duration: Duration,
+        is_working: bool,
+        nags: bool,
+        is_break: bool,
+        ends_set: bool,
+        emoji: &str,
+    ) -> Result<Self> {
+        let name = name.trim();
+
+        if name.is_empty() {
+            return Err(Error::KindError(KindError::NameWasBlank));
+        }
+
+        if default_duration <= Duration::zero() {
+            return Err(Error::KindError(KindError::DefaultDurationWasNotPositive));
         }
+
+        // fetch every row so the insert finishes (and commits) before we answer (see
+        // `User::create`.)
+        let kind = sqlx::query_as::<_, Self>(indoc! {"
+            INSERT INTO kinds (name, default_duration, is_working, nags, is_break, ends_set, emoji)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (name) DO NOTHING
+            RETURNING *
+        "})
+        .bind(name)
+        .bind(default_duration.num_seconds())
+        .bind(is_working)
+        .bind(nags)
+        .bind(is_break)
+        .bind(ends_set)
+        .bind(emoji.trim())
+        .fetch_all(pool)
+        .await
+        .map_err(Error::Query)?
+        .pop()
+        .ok_or_else(|| Error::KindError(KindError::AlreadyExists(name.to_string())))?;
+
+        tracing::info!(name = kind.name, "created kind");
+
+        Ok(kind)
     }
-}
 
-#[derive(Debug, PartialEq, Eq)]
-pub enum BreakKind {
-    Short,
-    Long,
-}
+    pub async fn update(pool: &Pool<Sqlite>, name: &str, changes: KindChanges) -> Result<Self> {
+        let mut kind = Self::by_name(pool, name).await?;
+
+        if let Some(default_duration) = changes.default_duration {
+            if default_duration <= Duration::zero() {
+                return Err(Error::KindError(KindError::DefaultDurationWasNotPositive));
+            }
 
-static SHORT_VS_LONG_CUTOFF: Lazy<Duration> = Lazy::new(|| Duration::minutes(15));
+            kind.default_duration = default_duration;
+        }
+
+        if let Some(is_working) = changes.is_working {
+            kind.is_working = is_working;
+        }
 
-impl From<chrono::Duration> for BreakKind {
-    fn from(value: chrono::Duration) -> Self {
-        if value <= *SHORT_VS_LONG_CUTOFF {
-            Self::Short
-        } else {
-            Self::Long
+        if let Some(nags) = changes.nags {
+            kind.nags = nags;
         }
+
+        if let Some(is_break) = changes.is_break {
+            kind.is_break = is_break;
+        }
+
+        if let Some(ends_set) = changes.ends_set {
+            kind.ends_set = ends_set;
+        }
+
+        if let Some(emoji) = changes.emoji {
+            kind.emoji = emoji.trim().to_string();
+        }
+
+        let kind = sqlx::query_as::<_, Self>(indoc! {"
+            UPDATE kinds
+            SET default_duration = ?, is_working = ?, nags = ?, is_break = ?, ends_set = ?, emoji = ?
+            WHERE name = ?
+            RETURNING *
+        "})
+        .bind(kind.default_duration.num_seconds())
+        .bind(kind.is_working)
+        .bind(kind.nags)
+        .bind(kind.is_break)
+        .bind(kind.ends_set)
+        .bind(&kind.emoji)
+        .bind(&kind.name)
+        .fetch_one(pool)
+        .await
+        .map_err(Error::Query)?;
+
+        tracing::info!(name = kind.name, "updated kind");
+
+        Ok(kind)
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
 
-    #[test]
-    fn break_kind_short() {
-        assert_eq!(BreakKind::from(Duration::zero()), BreakKind::Short)
+    async fn get_pool() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new().connect(":memory:").await.unwrap();
+
+        sqlx::migrate!("db/migrations").run(&pool).await.unwrap();
+
+        pool
     }
 
-    #[test]
-    fn break_kind_at_threshold() {
-        assert_eq!(BreakKind::from(*SHORT_VS_LONG_CUTOFF), BreakKind::Short)
+    #[tokio::test]
+    async fn seeds_the_original_kinds() {
+        let pool = get_pool().await;
+
+        let task = Kind::by_name(&pool, "task").await.unwrap();
+
+        assert_eq!(task.default_duration, Duration::minutes(25));
+        assert!(task.is_working);
+        assert!(task.nags);
+        assert!(!task.is_break);
+
+        let brk = Kind::by_name(&pool, "break").await.unwrap();
+
+        assert!(brk.is_break);
+        assert!(!brk.ends_set);
+        assert!(Kind::by_name(&pool, "offline").await.unwrap().ends_set);
+
+        assert_eq!(
+            Kind::all(&pool)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|kind| kind.name)
+                .collect::<Vec<_>>(),
+            vec!["break", "meeting", "offline", "task"],
+        );
     }
 
-    #[test]
-    fn break_kind_long() {
-        assert_eq!(BreakKind::from(Duration::days(1)), BreakKind::Long)
+    #[tokio::test]
+    async fn create_rejects_duplicate_names() {
+        let pool = get_pool().await;
+
+        match Kind::create(
+            &pool,
+            " meeting ",
+            Duration::minutes(50),
+            true,
+            false,
+            false,
+            false,
+            "👯",
+        )
+        .await
+        {
+            Err(Error::KindError(KindError::AlreadyExists(name))) => assert_eq!(name, "meeting"),
+            other => panic!("expected AlreadyExists, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn update_only_changes_given_fields() {
+        let pool = get_pool().await;
+
+        Kind::create(
+            &pool,
+            "pairing",
+            Duration::minutes(50),
+            true,
+            false,
+            false,
+            false,
+            "👯",
+        )
+        .await
+        .unwrap();
+
+        let updated = Kind::update(
+            &pool,
+            "pairing",
+            KindChanges {
+                nags: Some(true),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            updated,
+            Kind {
+                name: String::from("pairing"),
+                default_duration: Duration::minutes(50),
+                is_working: true,
+                nags: true,
+                is_break: false,
+                ends_set: false,
+                emoji: String::from("👯"),
+            }
+        );
     }
 }
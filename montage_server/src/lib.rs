@@ -1,13 +1,35 @@
+mod audit;
+mod chain;
+mod channels;
+mod cycle;
 mod error;
+mod event;
+mod goal;
+mod import;
+mod interruption;
 mod kind;
+mod listener;
 mod mutation;
+mod note;
+mod outcome;
+mod pause;
+mod planned_session;
 mod query;
 mod report;
 mod session;
+mod settings;
 mod subscription;
+mod tag;
+mod time_zone;
+mod token;
+mod undo;
+mod user;
+mod webhook;
 
-use async_graphql::http::graphiql_source;
+use async_graphql::http::{graphiql_source, WebSocketProtocols};
 use async_graphql::Schema;
+use async_graphql_warp::GraphQLWebSocket;
+use channels::Channels;
 use color_eyre::eyre::{Result, WrapErr};
 use mutation::Mutation;
 use query::Query;
@@ -15,48 +37,224 @@ use session::Session;
 use sqlx::{Pool, Sqlite};
 use std::convert::Infallible;
 use subscription::Subscription;
-use warp::Filter;
+use warp::{Filter, Reply};
+use webhook::WebhookDelivery;
+
+pub use listener::Listener;
+pub use token::ApiToken;
+pub use user::User;
 
 type MontageSchema = Schema<Query, Mutation, Subscription>;
 
 pub async fn schema(pool: Pool<Sqlite>) -> Result<MontageSchema> {
-    let initial = Session::current_session(&pool).await?;
+    let channels = Channels::new(pool.clone(), false);
 
-    let (sender, receiver) = tokio::sync::watch::channel(initial);
+    Ok(build_schema(pool, channels))
+}
 
-    Ok(
-        Schema::build(Query, Mutation::new(sender), Subscription::new(receiver))
-            .extension(async_graphql::extensions::Tracing)
-            .data(pool)
-            .finish(),
+fn build_schema(pool: Pool<Sqlite>, channels: Channels) -> MontageSchema {
+    Schema::build(
+        Query,
+        Mutation::new(channels.clone()),
+        Subscription::new(channels),
     )
+    .extension(async_graphql::extensions::Tracing)
+    .data(pool)
+    .finish()
 }
 
-pub async fn serve(pool: Pool<Sqlite>, addr: std::net::IpAddr, port: u16) -> Result<()> {
+/// Bring the database's tables up to date. `serve` does this when it starts; anything else that
+/// opens the database directly should too.
+pub async fn migrate(pool: &Pool<Sqlite>) -> Result<()> {
     sqlx::migrate!("db/migrations")
-        .run(&pool)
+        .run(pool)
+        .await
+        .wrap_err("could not run migrations")
+}
+
+pub async fn serve(
+    pool: Pool<Sqlite>,
+    listener: Listener,
+    keep_deleted_for: chrono::Duration,
+    keep_webhook_deliveries_for: chrono::Duration,
+) -> Result<()> {
+    migrate(&pool).await?;
+
+    Session::record_missing_time_zones(&pool, time_zone::local())
         .await
-        .wrap_err("could not run migrations")?;
+        .wrap_err("could not record time zones for old sessions")?;
+
+    tokio::spawn(purge_deleted_sessions(pool.clone(), keep_deleted_for));
+    tokio::spawn(prune_webhook_deliveries(
+        pool.clone(),
+        keep_webhook_deliveries_for,
+    ));
+
+    // everyone's channel starts their chained session scheduler and webhook dispatcher, so set
+    // them all up now instead of waiting for each user to make a request.
+    let channels = Channels::new(pool.clone(), true);
+    for user in User::all(&pool).await? {
+        channels.sender(user.id).await?;
+    }
 
-    let schema = schema(pool).await?;
+    if let Some(addr) = listener.exposed_addr() {
+        if !ApiToken::is_required(&pool).await? {
+            tracing::warn!(
+                %addr,
+                "listening beyond localhost without API tokens, so anyone who can reach this port can use it. Create one with `montage token create`."
+            );
+        }
+    }
 
-    let graphql = async_graphql_warp::graphql(schema.clone()).and_then(
-        |(schema, request): (MontageSchema, async_graphql::Request)| async move {
-            let resp = schema.execute(request).await;
-            Ok::<_, Infallible>(async_graphql_warp::GraphQLResponse::from(resp))
+    let schema = build_schema(pool.clone(), channels);
+
+    let graphql = warp::header::optional::<String>("authorization")
+        .and(async_graphql_warp::graphql(schema.clone()))
+        .and_then({
+            let pool = pool.clone();
+            move |authorization: Option<String>,
+                  (schema, request): (MontageSchema, async_graphql::Request)| {
+                let pool = pool.clone();
+                async move {
+                    let response = match ApiToken::authorize(
+                        &pool,
+                        authorization.as_deref(),
+                        chrono::Utc::now(),
+                    )
+                    .await
+                    {
+                        Ok(Some(user)) => async_graphql_warp::GraphQLResponse::from(
+                            schema.execute(request.data(user)).await,
+                        )
+                        .into_response(),
+                        Ok(None) => warp::reply::with_header(
+                            error_response(
+                                warp::http::StatusCode::UNAUTHORIZED,
+                                "missing or invalid API token",
+                            ),
+                            "WWW-Authenticate",
+                            "Bearer",
+                        )
+                        .into_response(),
+                        Err(err) => {
+                            tracing::error!(err = ?err, "could not check API token");
+                            error_response(
+                                warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+                                "could not check API token",
+                            )
+                            .into_response()
+                        }
+                    };
+
+                    Ok::<_, Infallible>(response)
+                }
+            }
+        });
+
+    // websocket clients can't always set headers, so they send their token in the payload of
+    // the connection init message instead (as `{"authorization": "Bearer TOKEN"}`.)
+    let subscriptions = warp::ws().and(async_graphql_warp::graphql_protocol()).map(
+        move |ws: warp::ws::Ws, protocol: WebSocketProtocols| {
+            let schema = schema.clone();
+            let pool = pool.clone();
+
+            let reply = ws.on_upgrade(move |socket| {
+                GraphQLWebSocket::new(socket, schema, protocol)
+                    .on_connection_init(move |payload| authorize_connection(pool, payload))
+                    .serve()
+            });
+
+            warp::reply::with_header(
+                reply,
+                "Sec-WebSocket-Protocol",
+                protocol.sec_websocket_protocol(),
+            )
         },
     );
 
-    let subscriptions = async_graphql_warp::graphql_subscription(schema);
-
-    let subscriptions_url = format!("ws://{}:{}/", &addr, &port);
+    let subscriptions_url = listener.subscriptions_url();
 
     let graphiql = warp::path("graphiql")
         .map(move || warp::reply::html(graphiql_source("graphql", Some(&subscriptions_url))));
 
-    warp::serve(graphql.or(graphiql).or(subscriptions))
-        .run((addr, port))
-        .await;
+    let server = warp::serve(graphql.or(graphiql).or(subscriptions));
+
+    match listener {
+        Listener::Tcp { addr, port } => server.run((addr, port)).await,
+        Listener::Tls {
+            addr,
+            port,
+            cert,
+            key,
+        } => {
+            server
+                .run_incoming(listener::tls_incoming(addr, port, &cert, &key).await?)
+                .await
+        }
+        Listener::Unix { path } => server.run_incoming(listener::unix_incoming(&path)?).await,
+    }
 
     Ok(())
 }
+
+fn error_response(status: warp::http::StatusCode, message: &str) -> impl Reply {
+    warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({ "errors": [{ "message": message }] })),
+        status,
+    )
+}
+
+async fn authorize_connection(
+    pool: Pool<Sqlite>,
+    payload: serde_json::Value,
+) -> async_graphql::Result<async_graphql::Data> {
+    let authorization = payload
+        .as_object()
+        .and_then(|fields| {
+            fields
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case("authorization"))
+        })
+        .and_then(|(_, value)| value.as_str());
+
+    match ApiToken::authorize(&pool, authorization, chrono::Utc::now()).await {
+        Ok(Some(user)) => {
+            let mut data = async_graphql::Data::default();
+            data.insert(user);
+            Ok(data)
+        }
+        Ok(None) => Err(async_graphql::Error::new("missing or invalid API token")),
+        Err(err) => {
+            tracing::error!(err = ?err, "could not check API token");
+            Err(async_graphql::Error::new("could not check API token"))
+        }
+    }
+}
+
+/// Every so often, permanently remove sessions that were deleted longer ago than we keep them
+/// around for.
+async fn purge_deleted_sessions(pool: Pool<Sqlite>, keep_deleted_for: chrono::Duration) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(60 * 60));
+
+    loop {
+        interval.tick().await;
+
+        if let Err(err) = Session::purge_deleted(&pool, chrono::Utc::now() - keep_deleted_for).await
+        {
+            tracing::error!(err = ?err, "could not purge deleted sessions");
+        }
+    }
+}
+
+/// Every so often, remove webhook delivery attempts older than we keep the log for.
+async fn prune_webhook_deliveries(pool: Pool<Sqlite>, keep_for: chrono::Duration) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(60 * 60));
+
+    loop {
+        interval.tick().await;
+
+        if let Err(err) = WebhookDelivery::prune(&pool, chrono::Utc::now() - keep_for).await {
+            tracing::error!(err = ?err, "could not prune webhook deliveries");
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use color_eyre::eyre::{bail, Result, WrapErr};
+use futures::Stream;
+use std::net::IpAddr;
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use tokio::net::{TcpListener, TcpStream, UnixListener};
+use tokio_native_tls::TlsStream;
+use tokio_stream::wrappers::{ReceiverStream, UnixListenerStream};
+
+/// How long a client gets to finish the TLS handshake before we hang up on it
+static TLS_HANDSHAKE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Where the server should listen for connections.
+#[derive(Debug, Clone)]
+pub enum Listener {
+    /// Plain HTTP on a TCP port.
+    Tcp { addr: IpAddr, port: u16 },
+
+    /// HTTPS on a TCP port. The certificate and key are PEM files, and the key has to be in
+    /// PKCS#8 format (`BEGIN PRIVATE KEY`.)
+    Tls {
+        addr: IpAddr,
+        port: u16,
+        cert: PathBuf,
+        key: PathBuf,
+    },
+
+    /// Plain HTTP on a Unix socket, which only people who can get at the path can connect to.
+    Unix { path: PathBuf },
+}
+
+impl Listener {
+    /// The address we're listening on, if anyone beyond this machine could reach it.
+    pub(crate) fn exposed_addr(&self) -> Option<IpAddr> {
+        match self {
+            Listener::Tcp { addr, .. } | Listener::Tls { addr, .. } if !addr.is_loopback() => {
+                Some(*addr)
+            }
+            _ => None,
+        }
+    }
+
+    /// Where GraphiQL should connect to for subscriptions.
+    pub(crate) fn subscriptions_url(&self) -> String {
+        match self {
+            Listener::Tcp { addr, port } => format!("ws://{}:{}/", addr, port),
+            Listener::Tls { addr, port, .. } => format!("wss://{}:{}/", addr, port),
+            // browsers can't connect to Unix sockets, so this is the best we can do
+            Listener::Unix { .. } => "ws://localhost/".to_string(),
+        }
+    }
+}
+
+/// Accept TLS connections on the given address. Handshakes happen in the background, so one slow
+/// or broken client can't hold up everyone else; failed or stalled ones are logged and dropped.
+pub(crate) async fn tls_incoming(
+    addr: IpAddr,
+    port: u16,
+    cert: &Path,
+    key: &Path,
+) -> Result<impl Stream<Item = std::io::Result<TlsStream<TcpStream>>>> {
+    let cert = tokio::fs::read(cert)
+        .await
+        .wrap_err_with(|| format!("could not read TLS certificate from {}", cert.display()))?;
+    let key = tokio::fs::read(key)
+        .await
+        .wrap_err_with(|| format!("could not read TLS key from {}", key.display()))?;
+
+    let identity = native_tls::Identity::from_pkcs8(&cert, &key)
+        .wrap_err("could not load TLS certificate and key (is the key in PKCS#8 format?)")?;
+    let acceptor = tokio_native_tls::TlsAcceptor::from(
+        native_tls::TlsAcceptor::new(identity).wrap_err("could not set up TLS")?,
+    );
+
+    let listener = TcpListener::bind((addr, port))
+        .await
+        .wrap_err_with(|| format!("could not listen on {}:{}", addr, port))?;
+
+    let (sender, receiver) = tokio::sync::mpsc::channel(16);
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(err) => {
+                    tracing::error!(err = ?err, "could not accept connection");
+                    continue;
+                }
+            };
+
+            let acceptor = acceptor.clone();
+            let sender = sender.clone();
+
+            tokio::spawn(async move {
+                match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(stream)) => {
+                        // if this fails, the server has gone away and there's nobody to hand
+                        // the connection to anyway.
+                        let _ = sender.send(Ok(stream)).await;
+                    }
+                    Ok(Err(err)) => tracing::warn!(%peer, err = ?err, "TLS handshake failed"),
+                    Err(_) => tracing::warn!(%peer, "TLS handshake timed out"),
+                }
+            });
+        }
+    });
+
+    Ok(ReceiverStream::new(receiver))
+}
+
+/// Accept connections on a Unix socket at the given path. If a socket is already there (say, from
+/// a server that didn't shut down cleanly) we replace it, but we won't clobber anything else.
+pub(crate) fn unix_incoming(path: &Path) -> Result<UnixListenerStream> {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path)
+            .wrap_err_with(|| format!("could not remove old socket at {}", path.display()))?,
+        Ok(_) => bail!(
+            "{} already exists and is not a socket, so not replacing it",
+            path.display()
+        ),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => (),
+        Err(err) => {
+            return Err(err).wrap_err_with(|| format!("could not check {}", path.display()))
+        }
+    }
+
+    // the socket is the only thing standing between the rest of the machine and the server, so
+    // only we get to use it. Sockets are created with whatever permissions the umask allows, so
+    // we make it in a directory nobody else can get into, lock it down, and only then move it
+    // into place.
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let staging = parent.join(format!(".montage-{}", std::process::id()));
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)
+            .wrap_err_with(|| format!("could not remove old {}", staging.display()))?;
+    }
+    std::fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&staging)
+        .wrap_err_with(|| format!("could not create {}", staging.display()))?;
+
+    let listener = bind_unix_privately(&staging.join("sock"), path);
+    let _ = std::fs::remove_dir_all(&staging);
+
+    Ok(UnixListenerStream::new(listener?))
+}
+
+/// Bind a socket at `staged`, which nobody else can reach, and move it to `path` once only we can
+/// use it.
+fn bind_unix_privately(staged: &Path, path: &Path) -> Result<UnixListener> {
+    let listener = UnixListener::bind(staged)
+        .wrap_err_with(|| format!("could not listen on {}", path.display()))?;
+
+    std::fs::set_permissions(staged, std::fs::Permissions::from_mode(0o600))
+        .wrap_err_with(|| format!("could not set permissions on {}", path.display()))?;
+
+    std::fs::rename(staged, path)
+        .wrap_err_with(|| format!("could not move socket to {}", path.display()))?;
+
+    Ok(listener)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn unix_incoming_replaces_stale_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("montage.sock");
+
+        drop(unix_incoming(&path).unwrap());
+        assert!(path.exists());
+
+        unix_incoming(&path).unwrap();
+
+        let metadata = std::fs::metadata(&path).unwrap();
+        assert!(metadata.file_type().is_socket());
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+
+        // nothing is left behind from setting it up
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn unix_incoming_accepts_connections_at_the_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("montage.sock");
+
+        let _incoming = unix_incoming(&path).unwrap();
+
+        tokio::net::UnixStream::connect(&path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn unix_incoming_does_not_replace_other_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("montage.sock");
+        std::fs::write(&path, "important").unwrap();
+
+        assert!(unix_incoming(&path).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "important");
+    }
+
+    #[test]
+    fn only_non_loopback_tcp_is_exposed() {
+        let loopback = Listener::Tcp {
+            addr: "127.0.0.1".parse().unwrap(),
+            port: 4774,
+        };
+        assert_eq!(loopback.exposed_addr(), None);
+
+        let everywhere: IpAddr = "0.0.0.0".parse().unwrap();
+        let tls = Listener::Tls {
+            addr: everywhere,
+            port: 4774,
+            cert: PathBuf::from("cert.pem"),
+            key: PathBuf::from("key.pem"),
+        };
+        assert_eq!(tls.exposed_addr(), Some(everywhere));
+
+        let unix = Listener::Unix {
+            path: PathBuf::from("/tmp/montage.sock"),
+        };
+        assert_eq!(unix.exposed_addr(), None);
+    }
+}
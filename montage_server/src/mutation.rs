@@ -1,57 +1,126 @@
+use super::chain::{Chain, FollowUp};
+use super::channels::Channels;
+use super::cycle::Cycle;
 use super::error::{Error, Result};
-use super::kind::Kind;
-use super::session::Session;
+use super::goal::{Goal, GoalBound, GoalPeriod};
+use super::import::{ImportResult, ImportedSession, OnOverlap};
+use super::kind::{Kind, KindChanges};
+use super::outcome::Outcome;
+use super::planned_session::PlannedSession;
+use super::session::{Session, SessionChanges};
+use super::settings::{Settings, SettingsChanges};
+use super::time_zone;
+use super::undo::Undone;
+use super::user::User;
+use super::webhook::Webhook;
 use async_graphql::context::Context;
 use async_graphql::Object;
-use tokio::sync::watch::Sender;
+use chrono_tz::Tz;
+use sqlx::{Pool, Sqlite};
 
-#[derive(Debug)]
 pub struct Mutation {
-    sender: Sender<Option<Session>>,
+    channels: Channels,
 }
 
 impl Mutation {
-    pub fn new(sender: Sender<Option<Session>>) -> Self {
-        Self { sender }
+    pub fn new(channels: Channels) -> Self {
+        Self { channels }
     }
 
-    fn notify_subscribers(&self, session: &Session) -> Result<()> {
-        self.sender
-            .send(Some(session.clone()))
-            .map_err(Error::SendError)
+    async fn notify_subscribers(&self, user_id: i64, session: Option<&Session>) -> Result<()> {
+        self.channels
+            .sender(user_id)
+            .await?
+            .send_replace(session.cloned());
+
+        Ok(())
+    }
+
+    /// Send the user's current session to their subscribers, but only if it's different from
+    /// what they already have (for example, if a change to a historical session didn't touch it.)
+    async fn republish_current_session(&self, pool: &Pool<Sqlite>, user_id: i64) -> Result<()> {
+        let current = Session::current_session(pool, user_id).await?;
+
+        self.channels
+            .sender(user_id)
+            .await?
+            .send_if_modified(|existing| {
+                if *existing != current {
+                    *existing = current;
+                    true
+                } else {
+                    false
+                }
+            });
+
+        Ok(())
     }
 }
 
 #[Object]
 impl Mutation {
     /// Start a new session
+    #[allow(clippy::too_many_arguments)]
     async fn start(
         &self,
         context: &Context<'_>,
-        #[graphql(desc = "What kind of session will this be?")] kind: Kind,
+        #[graphql(desc = "What kind of session will this be? (The name of a kind)")] kind: String,
         #[graphql(desc = "What will you be doing during this session?")] description: String,
         #[graphql(
-            desc = "How long will this session last? (If omitted, we'll decide based on the session type)"
+            desc = "How long will this session last? (If omitted, we'll use the kind's default duration, or a long break once a Pomodoro set is done)"
         )]
         duration: Option<chrono::Duration>,
         #[graphql(desc = "When did this session start? (Omit to start now)")] start_time: Option<
-            chrono::DateTime<chrono::Local>,
+            chrono::DateTime<chrono::Utc>,
         >,
+        #[graphql(desc = "Tags to group this session with related sessions in reports")]
+        tags: Option<Vec<String>>,
+        #[graphql(
+            desc = "Which IANA time zone is this session happening in? (Omit to use the server's time zone)"
+        )]
+        time_zone: Option<Tz>,
+        #[graphql(
+            desc = "A session to start automatically when this one reaches its projected end time"
+        )]
+        then: Option<FollowUp>,
     ) -> Result<Session> {
-        let final_start = start_time.unwrap_or_else(chrono::Local::now);
+        let pool = context.data().map_err(Error::Context)?;
+        let user_id = User::from_context(context)?.id;
 
-        let final_duration = duration.unwrap_or_else(|| kind.default_session_length());
+        if let Some(follow_up) = &then {
+            Chain::validate(pool, follow_up).await?;
+        }
 
-        let session = Session::start(
-            context.data().map_err(Error::Context)?,
-            kind,
+        let final_start = start_time.unwrap_or_else(chrono::Utc::now);
+
+        let final_duration = match duration {
+            Some(duration) => duration,
+            None => Cycle::default_duration(pool, user_id, &kind).await?,
+        };
+
+        let final_time_zone = time_zone.unwrap_or_else(time_zone::local);
+
+        Session::validate_start(pool, &kind, &description).await?;
+
+        let mut transaction = pool.begin().await.map_err(Error::Query)?;
+        let session = Session::start_in(
+            &mut transaction,
+            user_id,
+            &kind,
             &description,
             final_start,
             final_duration,
+            final_time_zone,
+            &tags.unwrap_or_default(),
+            None,
         )
         .await?;
+        if let Some(follow_up) = &then {
+            Chain::create_in(&mut transaction, session.id, follow_up).await?;
+        }
+        transaction.commit().await.map_err(Error::Query)?;
 
-        self.notify_subscribers(&session)?;
+        self.notify_subscribers(user_id, Some(&session)).await?;
 
         Ok(session)
     }
@@ -62,9 +131,11 @@ impl Mutation {
         context: &Context<'_>,
         #[graphql(desc = "How much time to add?")] duration: chrono::Duration,
     ) -> Result<Session> {
-        let session = Session::extend_by(context.data().map_err(Error::Context)?, duration).await?;
+        let user_id = User::from_context(context)?.id;
+        let session =
+            Session::extend_by(context.data().map_err(Error::Context)?, user_id, duration).await?;
 
-        self.notify_subscribers(&session)?;
+        self.notify_subscribers(user_id, Some(&session)).await?;
         Ok(session)
     }
 
@@ -72,11 +143,503 @@ impl Mutation {
     async fn extend_to(
         &self,
         context: &Context<'_>,
-        #[graphql(desc = "When to extend to?")] target: chrono::DateTime<chrono::Local>,
+        #[graphql(desc = "When to extend to?")] target: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Session> {
+        let user_id = User::from_context(context)?.id;
+        let session =
+            Session::extend_to(context.data().map_err(Error::Context)?, user_id, target).await?;
+
+        self.notify_subscribers(user_id, Some(&session)).await?;
+        Ok(session)
+    }
+
+    /// Stop the current session without starting a new one
+    async fn stop(
+        &self,
+        context: &Context<'_>,
+        #[graphql(desc = "When did this session end? (Omit to end now)")] end_time: Option<
+            chrono::DateTime<chrono::Utc>,
+        >,
+    ) -> Result<Session> {
+        let user_id = User::from_context(context)?.id;
+        let final_end = end_time.unwrap_or_else(chrono::Utc::now);
+
+        let session =
+            Session::stop(context.data().map_err(Error::Context)?, user_id, final_end).await?;
+
+        self.notify_subscribers(user_id, None).await?;
+        Ok(session)
+    }
+
+    /// Pause the current session. Time spent paused doesn't count towards the session, so the
+    /// projected end time moves later for as long as the session stays paused.
+    async fn pause(
+        &self,
+        context: &Context<'_>,
+        #[graphql(desc = "When was the session paused? (Omit to pause now)")] at: Option<
+            chrono::DateTime<chrono::Utc>,
+        >,
+    ) -> Result<Session> {
+        let user_id = User::from_context(context)?.id;
+        let final_at = at.unwrap_or_else(chrono::Utc::now);
+
+        let session =
+            Session::pause(context.data().map_err(Error::Context)?, user_id, final_at).await?;
+
+        self.notify_subscribers(user_id, Some(&session)).await?;
+        Ok(session)
+    }
+
+    /// Resume the current session after pausing it
+    async fn resume(
+        &self,
+        context: &Context<'_>,
+        #[graphql(desc = "When was the session resumed? (Omit to resume now)")] at: Option<
+            chrono::DateTime<chrono::Utc>,
+        >,
+    ) -> Result<Session> {
+        let user_id = User::from_context(context)?.id;
+        let final_at = at.unwrap_or_else(chrono::Utc::now);
+
+        let session =
+            Session::resume(context.data().map_err(Error::Context)?, user_id, final_at).await?;
+
+        self.notify_subscribers(user_id, Some(&session)).await?;
+        Ok(session)
+    }
+
+    /// Change a session after the fact. Sessions can't be changed so that they overlap other
+    /// sessions or end before they start.
+    #[allow(clippy::too_many_arguments)]
+    async fn update_session(
+        &self,
+        context: &Context<'_>,
+        #[graphql(desc = "The ID of the session to change")] id: i64,
+        #[graphql(desc = "What was going on in this session?")] description: Option<String>,
+        #[graphql(desc = "What kind of session was this? (The name of a kind)")] kind: Option<
+            String,
+        >,
+        #[graphql(desc = "When did this session start?")] start_time: Option<
+            chrono::DateTime<chrono::Utc>,
+        >,
+        #[graphql(desc = "When did this session end?")] end_time: Option<
+            chrono::DateTime<chrono::Utc>,
+        >,
+        #[graphql(desc = "How much time was committed to this session?")] duration: Option<
+            chrono::Duration,
+        >,
+    ) -> Result<Session> {
+        let pool = context.data().map_err(Error::Context)?;
+        let user_id = User::from_context(context)?.id;
+
+        let session = Session::update(
+            pool,
+            user_id,
+            id,
+            SessionChanges {
+                description,
+                kind,
+                start_time,
+                end_time,
+                duration,
+            },
+        )
+        .await?;
+
+        self.republish_current_session(pool, user_id).await?;
+        Ok(session)
+    }
+
+    /// Delete a session. It can be brought back with `restoreSession` until it's purged.
+    async fn delete_session(
+        &self,
+        context: &Context<'_>,
+        #[graphql(desc = "The ID of the session to delete")] id: i64,
     ) -> Result<Session> {
-        let session = Session::extend_to(context.data().map_err(Error::Context)?, target).await?;
+        let pool = context.data().map_err(Error::Context)?;
+        let user_id = User::from_context(context)?.id;
+
+        let session = Session::delete(pool, user_id, id, chrono::Utc::now()).await?;
+
+        self.republish_current_session(pool, user_id).await?;
+        Ok(session)
+    }
+
+    /// Bring back a deleted session
+    async fn restore_session(
+        &self,
+        context: &Context<'_>,
+        #[graphql(
+            desc = "The ID of the session to restore (Omit to restore the most recently deleted session)"
+        )]
+        id: Option<i64>,
+    ) -> Result<Session> {
+        let pool = context.data().map_err(Error::Context)?;
+        let user_id = User::from_context(context)?.id;
+
+        let session = Session::restore(pool, user_id, id).await?;
+
+        self.republish_current_session(pool, user_id).await?;
+        Ok(session)
+    }
+
+    /// Undo the most recent change to your sessions: remove a session that was started by
+    /// mistake (reopening the one it ended), take back an extension, and so on. Undoing again
+    /// keeps going further back.
+    async fn undo(&self, context: &Context<'_>) -> Result<Undone> {
+        let pool = context.data().map_err(Error::Context)?;
+        let user_id = User::from_context(context)?.id;
+
+        let undone = Undone::last_change(pool, user_id, chrono::Utc::now()).await?;
+
+        self.republish_current_session(pool, user_id).await?;
+        Ok(undone)
+    }
+
+    /// Add finished sessions from somewhere else (like another time tracker) all at once. If any
+    /// of them can't be imported, none of them are.
+    async fn import_sessions(
+        &self,
+        context: &Context<'_>,
+        sessions: Vec<ImportedSession>,
+        #[graphql(
+            desc = "What to do with sessions that overlap ones we already have",
+            default_with = "OnOverlap::Reject"
+        )]
+        on_overlap: OnOverlap,
+        #[graphql(
+            desc = "Check the sessions and say what would happen, without saving anything",
+            default
+        )]
+        dry_run: bool,
+    ) -> Result<ImportResult> {
+        let pool = context.data().map_err(Error::Context)?;
+        let user_id = User::from_context(context)?.id;
+
+        let result = ImportResult::import(pool, user_id, &sessions, on_overlap, dry_run).await?;
+
+        if !dry_run {
+            self.republish_current_session(pool, user_id).await?;
+        }
+        Ok(result)
+    }
+
+    /// Write down a note about what got done during a session
+    async fn add_note(
+        &self,
+        context: &Context<'_>,
+        #[graphql(desc = "What do you want to remember?")] text: String,
+        #[graphql(
+            desc = "The ID of the session to add the note to (Omit to use the current session)"
+        )]
+        session_id: Option<i64>,
+    ) -> Result<Session> {
+        let pool = context.data().map_err(Error::Context)?;
+        let user_id = User::from_context(context)?.id;
+
+        let session =
+            Session::add_note(pool, user_id, session_id, &text, chrono::Utc::now()).await?;
+
+        self.republish_current_session(pool, user_id).await?;
+        Ok(session)
+    }
+
+    /// Log something that interrupted the current session
+    async fn log_interruption(
+        &self,
+        context: &Context<'_>,
+        #[graphql(desc = "What interrupted you? (For example, \"slack ping\")")] source: String,
+        #[graphql(desc = "Anything else worth remembering about the interruption")] note: Option<
+            String,
+        >,
+    ) -> Result<Session> {
+        let pool = context.data().map_err(Error::Context)?;
+        let user_id = User::from_context(context)?.id;
+
+        let session =
+            Session::log_interruption(pool, user_id, &source, note.as_deref(), chrono::Utc::now())
+                .await?;
 
-        self.notify_subscribers(&session)?;
+        self.republish_current_session(pool, user_id).await?;
         Ok(session)
     }
+
+    /// Record how a session turned out
+    async fn set_outcome(
+        &self,
+        context: &Context<'_>,
+        #[graphql(desc = "How did the session go?")] outcome: Outcome,
+        #[graphql(desc = "The ID of the session (Omit to use the most recently started session)")]
+        session_id: Option<i64>,
+    ) -> Result<Session> {
+        let pool = context.data().map_err(Error::Context)?;
+        let user_id = User::from_context(context)?.id;
+
+        let session = Session::set_outcome(pool, user_id, session_id, outcome).await?;
+
+        self.republish_current_session(pool, user_id).await?;
+        Ok(session)
+    }
+
+    /// Plan a session for later by adding it to the end of the queue
+    async fn enqueue(
+        &self,
+        context: &Context<'_>,
+        #[graphql(desc = "What kind of session will this be? (The name of a kind)")] kind: String,
+        #[graphql(desc = "What will you be doing during this session?")] description: String,
+        #[graphql(
+            desc = "How long will this session last? (If omitted, we'll use the kind's default duration when it starts)"
+        )]
+        duration: Option<chrono::Duration>,
+    ) -> Result<PlannedSession> {
+        PlannedSession::enqueue(
+            context.data().map_err(Error::Context)?,
+            User::from_context(context)?.id,
+            &kind,
+            &description,
+            duration,
+        )
+        .await
+    }
+
+    /// Move a planned session to a new position in the queue
+    async fn reorder_queue(
+        &self,
+        context: &Context<'_>,
+        #[graphql(desc = "The ID of the planned session to move")] id: i64,
+        #[graphql(desc = "Where to move it (0 is the head of the queue)")] position: i64,
+    ) -> Result<Vec<PlannedSession>> {
+        PlannedSession::reorder(
+            context.data().map_err(Error::Context)?,
+            User::from_context(context)?.id,
+            id,
+            position,
+        )
+        .await
+    }
+
+    /// Take a planned session out of the queue without starting it
+    async fn dequeue(
+        &self,
+        context: &Context<'_>,
+        #[graphql(desc = "The ID of the planned session to remove")] id: i64,
+    ) -> Result<PlannedSession> {
+        PlannedSession::dequeue(
+            context.data().map_err(Error::Context)?,
+            User::from_context(context)?.id,
+            id,
+        )
+        .await
+    }
+
+    /// Start the planned session at the head of the queue
+    async fn start_next(
+        &self,
+        context: &Context<'_>,
+        #[graphql(
+            desc = "Which IANA time zone is this session happening in? (Omit to use the server's time zone)"
+        )]
+        time_zone: Option<Tz>,
+    ) -> Result<Session> {
+        let user_id = User::from_context(context)?.id;
+        let session = PlannedSession::start_next(
+            context.data().map_err(Error::Context)?,
+            user_id,
+            chrono::Utc::now(),
+            time_zone.unwrap_or_else(time_zone::local),
+        )
+        .await?;
+
+        self.notify_subscribers(user_id, Some(&session)).await?;
+        Ok(session)
+    }
+
+    /// Set a goal for how much time to spend on a kind of session or a tag every day or week
+    async fn create_goal(
+        &self,
+        context: &Context<'_>,
+        #[graphql(
+            desc = "Which kind of session counts towards the goal? (The name of a kind. Set this or `tag`)"
+        )]
+        kind: Option<String>,
+        #[graphql(desc = "Which tag counts towards the goal? (Set this or `kind`)")] tag: Option<
+            String,
+        >,
+        #[graphql(desc = "How often does the goal start over?")] period: GoalPeriod,
+        #[graphql(desc = "How much time are you aiming for?")] target: chrono::Duration,
+        #[graphql(desc = "Is the target a minimum or a maximum?")] bound: GoalBound,
+    ) -> Result<Goal> {
+        Goal::create(
+            context.data().map_err(Error::Context)?,
+            User::from_context(context)?.id,
+            kind.as_deref(),
+            tag.as_deref(),
+            period,
+            target,
+            bound,
+        )
+        .await
+    }
+
+    /// Stop tracking a goal
+    async fn delete_goal(
+        &self,
+        context: &Context<'_>,
+        #[graphql(desc = "The ID of the goal to delete")] id: i64,
+    ) -> Result<Goal> {
+        Goal::delete(
+            context.data().map_err(Error::Context)?,
+            User::from_context(context)?.id,
+            id,
+        )
+        .await
+    }
+
+    /// Add a new kind of session. Kinds are shared by every user, so only the default user can add
+    /// them.
+    #[allow(clippy::too_many_arguments)]
+    async fn create_kind(
+        &self,
+        context: &Context<'_>,
+        #[graphql(desc = "What should sessions call this kind?")] name: String,
+        #[graphql(desc = "How long should sessions of this kind last if not told otherwise?")]
+        default_duration: chrono::Duration,
+        #[graphql(desc = "Does time in sessions of this kind count as working time?")]
+        is_working: bool,
+        #[graphql(desc = "Should the vexer nag when sessions of this kind run over?")] nags: bool,
+        #[graphql(
+            default,
+            desc = "Are sessions of this kind breaks? Long ones start a new Pomodoro set."
+        )]
+        is_break: bool,
+        #[graphql(
+            default,
+            desc = "Does any session of this kind start a new Pomodoro set, no matter how long?"
+        )]
+        ends_set: bool,
+        #[graphql(desc = "An emoji to show for sessions of this kind")] emoji: String,
+    ) -> Result<Kind> {
+        User::from_context(context)?.check_can_change_shared()?;
+
+        Kind::create(
+            context.data().map_err(Error::Context)?,
+            &name,
+            default_duration,
+            is_working,
+            nags,
+            is_break,
+            ends_set,
+            &emoji,
+        )
+        .await
+    }
+
+    /// Change an existing kind of session. Only the default user can do this, since kinds are
+    /// shared by every user.
+    #[allow(clippy::too_many_arguments)]
+    async fn update_kind(
+        &self,
+        context: &Context<'_>,
+        #[graphql(desc = "The name of the kind to change")] name: String,
+        #[graphql(desc = "How long should sessions of this kind last if not told otherwise?")]
+        default_duration: Option<chrono::Duration>,
+        #[graphql(desc = "Does time in sessions of this kind count as working time?")]
+        is_working: Option<bool>,
+        #[graphql(desc = "Should the vexer nag when sessions of this kind run over?")] nags: Option<
+            bool,
+        >,
+        #[graphql(desc = "Are sessions of this kind breaks? Long ones start a new Pomodoro set.")]
+        is_break: Option<bool>,
+        #[graphql(
+            desc = "Does any session of this kind start a new Pomodoro set, no matter how long?"
+        )]
+        ends_set: Option<bool>,
+        #[graphql(desc = "An emoji to show for sessions of this kind")] emoji: Option<String>,
+    ) -> Result<Kind> {
+        let pool = context.data().map_err(Error::Context)?;
+        let user = User::from_context(context)?;
+        user.check_can_change_shared()?;
+
+        let kind = Kind::update(
+            pool,
+            &name,
+            KindChanges {
+                default_duration,
+                is_working,
+                nags,
+                is_break,
+                ends_set,
+                emoji,
+            },
+        )
+        .await?;
+
+        // the current session's kind details may have changed
+        self.republish_current_session(pool, user.id).await?;
+        Ok(kind)
+    }
+
+    /// Send a JSON payload to a URL whenever a session starts, gets extended, ends, or runs over
+    /// time
+    async fn create_webhook(
+        &self,
+        context: &Context<'_>,
+        #[graphql(desc = "Where should payloads go? (An http:// or https:// URL)")] url: String,
+        #[graphql(
+            desc = "A key to sign payloads with, so the receiver can check they came from us. Signatures are in the `X-Montage-Signature` header."
+        )]
+        secret: String,
+    ) -> Result<Webhook> {
+        Webhook::create(
+            context.data().map_err(Error::Context)?,
+            User::from_context(context)?.id,
+            &url,
+            &secret,
+            chrono::Utc::now(),
+        )
+        .await
+    }
+
+    /// Stop sending payloads to a webhook, and forget its delivery log
+    async fn delete_webhook(
+        &self,
+        context: &Context<'_>,
+        #[graphql(desc = "The ID of the webhook to delete")] id: i64,
+    ) -> Result<Webhook> {
+        Webhook::delete(
+            context.data().map_err(Error::Context)?,
+            User::from_context(context)?.id,
+            id,
+        )
+        .await
+    }
+
+    /// Change server-wide settings, which are shared by every user (so only the default user can
+    /// change them.) Settings left out are not changed.
+    async fn update_settings(
+        &self,
+        context: &Context<'_>,
+        #[graphql(
+            desc = "Breaks longer than this are long breaks, which don't count as working time"
+        )]
+        short_break_cutoff: Option<chrono::Duration>,
+        #[graphql(desc = "How many task sessions make a set before we suggest a long break?")]
+        set_size: Option<i64>,
+        #[graphql(
+            desc = "How long should breaks started without a duration last once a set is done?"
+        )]
+        long_break_duration: Option<chrono::Duration>,
+    ) -> Result<Settings> {
+        User::from_context(context)?.check_can_change_shared()?;
+
+        Settings::update(
+            context.data().map_err(Error::Context)?,
+            SettingsChanges {
+                short_break_cutoff,
+                set_size,
+                long_break_duration,
+            },
+        )
+        .await
+    }
 }
@@ -0,0 +1,63 @@
+use super::error::{Error, Result};
+use async_graphql::SimpleObject;
+use chrono::{DateTime, Utc};
+use indoc::indoc;
+use sqlx::{FromRow, Pool, Sqlite, SqliteConnection};
+
+/// A note about what happened during a session
+#[derive(SimpleObject, FromRow, Debug, PartialEq, Eq, Clone)]
+pub struct Note {
+    /// The session this note belongs to
+    #[graphql(skip)]
+    pub session_id: i64,
+
+    /// When was this note written?
+    pub created_at: DateTime<Utc>,
+
+    /// What does the note say?
+    pub text: String,
+}
+
+impl Note {
+    pub async fn add(
+        conn: &mut SqliteConnection,
+        session_id: i64,
+        text: &str,
+        created_at: DateTime<Utc>,
+    ) -> Result<Self> {
+        sqlx::query_as::<_, Self>(indoc! {"
+            INSERT INTO session_notes (session_id, created_at, text)
+            VALUES (?, ?, ?)
+            RETURNING session_id, created_at, text;
+        "})
+        .bind(session_id)
+        .bind(created_at)
+        .bind(text)
+        .fetch_one(conn)
+        .await
+        .map_err(Error::Query)
+    }
+
+    pub async fn for_sessions(pool: &Pool<Sqlite>, session_ids: &[i64]) -> Result<Vec<Self>> {
+        if session_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let ids = session_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+
+        sqlx::query_as::<_, Self>(indoc! {"
+            SELECT session_id, created_at, text
+            FROM session_notes
+            WHERE session_id IN (SELECT value FROM json_each(?))
+            ORDER BY created_at ASC
+        "})
+        .bind(format!("[{ids}]"))
+        .fetch_all(pool)
+        .await
+        .map_err(Error::Query)
+    }
+}
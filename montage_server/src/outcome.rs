@@ -0,0 +1,17 @@
+/// How did a session turn out?
+#[derive(async_graphql::Enum, Debug, Default, PartialEq, Eq, Copy, Clone, sqlx::Type)]
+#[sqlx(rename_all = "lowercase")]
+pub enum Outcome {
+    /// Everything planned for the session got done
+    Completed,
+
+    /// Some of what was planned got done
+    Partial,
+
+    /// The session was given up on
+    Abandoned,
+
+    /// Nobody said how the session went
+    #[default]
+    Unknown,
+}
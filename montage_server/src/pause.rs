@@ -0,0 +1,91 @@
+use super::error::{Error, Result};
+use async_graphql::SimpleObject;
+use chrono::{DateTime, Duration, Utc};
+use indoc::indoc;
+use sqlx::{FromRow, Pool, Sqlite, SqliteConnection};
+
+/// A span of time where a session was paused. Paused time doesn't count towards the session's
+/// duration.
+#[derive(SimpleObject, FromRow, Debug, PartialEq, Eq, Clone)]
+pub struct Pause {
+    /// The session this pause belongs to
+    #[graphql(skip)]
+    pub session_id: i64,
+
+    /// When was the session paused?
+    pub start_time: DateTime<Utc>,
+
+    /// When was the session resumed? If this is empty, the session is still paused.
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+impl Pause {
+    /// How much of this pause falls between `start` and `end`?
+    pub fn time_within_dates(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Duration {
+        let start_final = std::cmp::max(start, self.start_time);
+        let end_final = std::cmp::min(end, self.end_time.unwrap_or_else(Utc::now));
+
+        std::cmp::max(end_final - start_final, Duration::zero())
+    }
+
+    pub async fn start(
+        conn: &mut SqliteConnection,
+        session_id: i64,
+        start_time: DateTime<Utc>,
+    ) -> Result<Self> {
+        sqlx::query_as::<_, Self>(indoc! {"
+            INSERT INTO session_pauses (session_id, start_time)
+            VALUES (?, ?)
+            RETURNING session_id, start_time, end_time;
+        "})
+        .bind(session_id)
+        .bind(start_time)
+        .fetch_one(conn)
+        .await
+        .map_err(Error::Query)
+    }
+
+    /// End every pause still going on in the user's sessions.
+    pub async fn stop_all(
+        conn: &mut SqliteConnection,
+        user_id: i64,
+        as_of: DateTime<Utc>,
+    ) -> Result<u64> {
+        let receipt = sqlx::query(indoc! {"
+            UPDATE session_pauses
+            SET end_time = ?
+            WHERE end_time IS NULL
+              AND session_id IN (SELECT id FROM sessions WHERE user_id = ?)
+        "})
+        .bind(as_of)
+        .bind(user_id)
+        .execute(conn)
+        .await
+        .map_err(Error::Query)?;
+
+        Ok(receipt.rows_affected())
+    }
+
+    pub async fn for_sessions(pool: &Pool<Sqlite>, session_ids: &[i64]) -> Result<Vec<Self>> {
+        if session_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let ids = session_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+
+        sqlx::query_as::<_, Self>(indoc! {"
+            SELECT session_id, start_time, end_time
+            FROM session_pauses
+            WHERE session_id IN (SELECT value FROM json_each(?))
+            ORDER BY start_time ASC
+        "})
+        .bind(format!("[{ids}]"))
+        .fetch_all(pool)
+        .await
+        .map_err(Error::Query)
+    }
+}
@@ -0,0 +1,427 @@
+use super::cycle::Cycle;
+use super::error::{Error, Result, StartSessionError};
+use super::kind::Kind;
+use super::session::Session;
+use async_graphql::SimpleObject;
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use indoc::indoc;
+use sqlx::{sqlite::SqliteRow, FromRow, Pool, Row, Sqlite, SqliteConnection};
+
+/// A session that's planned but not started yet. Planned sessions wait in a queue until they're
+/// started with `startNext`.
+#[derive(SimpleObject, Debug, PartialEq, Eq, Clone)]
+pub struct PlannedSession {
+    /// The ID of this planned session. It won't change if the queue is reordered.
+    pub id: i64,
+
+    /// Where is this session in the queue? The session at position 0 is next.
+    pub position: i64,
+
+    /// What kind of session will this be?
+    pub kind: String,
+
+    /// What will you be doing during this session?
+    pub description: String,
+
+    /// How long will this session last? If empty, we'll decide based on the session type when
+    /// it's started.
+    pub duration: Option<Duration>,
+}
+
+impl FromRow<'_, SqliteRow> for PlannedSession {
+    fn from_row(row: &SqliteRow) -> std::result::Result<Self, sqlx::Error> {
+        let duration_seconds: Option<i64> = row.try_get("duration")?;
+
+        Ok(Self {
+            id: row.try_get("id")?,
+            position: row.try_get("position")?,
+            kind: row.try_get("kind")?,
+            description: row.try_get("description")?,
+            duration: duration_seconds.map(Duration::seconds),
+        })
+    }
+}
+
+impl PlannedSession {
+    /// Get all the user's planned sessions, in the order they'll be started.
+    pub async fn queue(pool: &Pool<Sqlite>, user_id: i64) -> Result<Vec<Self>> {
+        sqlx::query_as::<_, Self>(
+            "SELECT * FROM planned_sessions WHERE user_id = ? ORDER BY position ASC",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map_err(Error::Query)
+    }
+
+    /// Get the planned session that will be started next, if there is one.
+    pub async fn next(pool: &Pool<Sqlite>, user_id: i64) -> Result<Option<Self>> {
+        sqlx::query_as::<_, Self>(
+            "SELECT * FROM planned_sessions WHERE user_id = ? ORDER BY position ASC LIMIT 1",
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(Error::Query)
+    }
+
+    /// Add a planned session to the end of the queue.
+    pub async fn enqueue(
+        pool: &Pool<Sqlite>,
+        user_id: i64,
+        kind: &str,
+        description: &str,
+        duration: Option<Duration>,
+    ) -> Result<Self> {
+        if description.trim().is_empty() {
+            return Err(Error::StartSessionError(
+                StartSessionError::DescriptionWasBlank,
+            ));
+        }
+
+        Kind::by_name(pool, kind).await?;
+
+        let res = sqlx::query_as::<_, Self>(indoc! {"
+            INSERT INTO planned_sessions (user_id, position, kind, description, duration)
+            VALUES (
+                ?1,
+                (SELECT COALESCE(MAX(position) + 1, 0) FROM planned_sessions WHERE user_id = ?1),
+                ?2,
+                ?3,
+                ?4
+            )
+            RETURNING id, position, kind, description, duration;
+        "})
+        .bind(user_id)
+        .bind(kind)
+        .bind(description)
+        .bind(duration.map(|duration| duration.num_seconds()))
+        .fetch_one(pool)
+        .await
+        .map_err(Error::Query)?;
+
+        tracing::info!(
+            description = res.description,
+            position = res.position,
+            "planned session"
+        );
+
+        Ok(res)
+    }
+
+    /// Take a planned session out of the queue.
+    pub async fn dequeue(pool: &Pool<Sqlite>, user_id: i64, id: i64) -> Result<Self> {
+        let mut transaction = pool.begin().await.map_err(Error::Query)?;
+        let removed = Self::remove(&mut transaction, user_id, id).await?;
+        transaction.commit().await.map_err(Error::Query)?;
+
+        tracing::info!(description = removed.description, "removed planned session");
+
+        Ok(removed)
+    }
+
+    /// Take a planned session out of the queue as part of a bigger transaction, moving the ones
+    /// after it up.
+    async fn remove(conn: &mut SqliteConnection, user_id: i64, id: i64) -> Result<Self> {
+        let removed = sqlx::query_as::<_, Self>(indoc! {"
+            DELETE FROM planned_sessions
+            WHERE id = ? AND user_id = ?
+            RETURNING id, position, kind, description, duration;
+        "})
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(Error::Query)?
+        .ok_or(Error::NoSuchPlannedSession(id))?;
+
+        sqlx::query(
+            "UPDATE planned_sessions SET position = position - 1 WHERE user_id = ? AND position > ?",
+        )
+        .bind(user_id)
+        .bind(removed.position)
+        .execute(&mut *conn)
+        .await
+        .map_err(Error::Query)?;
+
+        Ok(removed)
+    }
+
+    /// Put a planned session back at the front of the queue, keeping its ID, as part of a bigger
+    /// transaction (like undoing `startNext`.)
+    pub(crate) async fn requeue(
+        conn: &mut SqliteConnection,
+        user_id: i64,
+        planned_session: &Self,
+    ) -> Result<()> {
+        sqlx::query("UPDATE planned_sessions SET position = position + 1 WHERE user_id = ?")
+            .bind(user_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(Error::Query)?;
+
+        sqlx::query(indoc! {"
+            INSERT INTO planned_sessions (id, user_id, position, kind, description, duration)
+            VALUES (?, ?, 0, ?, ?, ?)
+        "})
+        .bind(planned_session.id)
+        .bind(user_id)
+        .bind(&planned_session.kind)
+        .bind(&planned_session.description)
+        .bind(
+            planned_session
+                .duration
+                .map(|duration| duration.num_seconds()),
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(Error::Query)?;
+
+        Ok(())
+    }
+
+    /// Move a planned session to a new position in the queue. Positions past the end of the
+    /// queue move the session to the end.
+    pub async fn reorder(
+        pool: &Pool<Sqlite>,
+        user_id: i64,
+        id: i64,
+        position: i64,
+    ) -> Result<Vec<Self>> {
+        let mut transaction = pool.begin().await.map_err(Error::Query)?;
+
+        let (current, last): (Option<i64>, i64) = sqlx::query_as(indoc! {"
+            SELECT
+                (SELECT position FROM planned_sessions WHERE id = ?1 AND user_id = ?2),
+                (SELECT COALESCE(MAX(position), 0) FROM planned_sessions WHERE user_id = ?2)
+        "})
+        .bind(id)
+        .bind(user_id)
+        .fetch_one(&mut *transaction)
+        .await
+        .map_err(Error::Query)?;
+
+        let current = current.ok_or(Error::NoSuchPlannedSession(id))?;
+        let target = position.clamp(0, last);
+
+        if target < current {
+            sqlx::query(indoc! {"
+                UPDATE planned_sessions
+                SET position = position + 1
+                WHERE user_id = ? AND position >= ? AND position < ?
+            "})
+            .bind(user_id)
+            .bind(target)
+            .bind(current)
+            .execute(&mut *transaction)
+            .await
+            .map_err(Error::Query)?;
+        } else if target > current {
+            sqlx::query(indoc! {"
+                UPDATE planned_sessions
+                SET position = position - 1
+                WHERE user_id = ? AND position > ? AND position <= ?
+            "})
+            .bind(user_id)
+            .bind(current)
+            .bind(target)
+            .execute(&mut *transaction)
+            .await
+            .map_err(Error::Query)?;
+        }
+
+        sqlx::query("UPDATE planned_sessions SET position = ? WHERE id = ?")
+            .bind(target)
+            .bind(id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(Error::Query)?;
+
+        transaction.commit().await.map_err(Error::Query)?;
+
+        Self::queue(pool, user_id).await
+    }
+
+    /// Start the planned session at the head of the queue, removing it from the queue.
+    pub async fn start_next(
+        pool: &Pool<Sqlite>,
+        user_id: i64,
+        start_time: DateTime<Utc>,
+        time_zone: Tz,
+    ) -> Result<Session> {
+        let next = Self::next(pool, user_id)
+            .await?
+            .ok_or(Error::QueueIsEmpty)?;
+
+        let duration = match next.duration {
+            Some(duration) => duration,
+            None => Cycle::default_duration(pool, user_id, &next.kind).await?,
+        };
+
+        Session::validate_start(pool, &next.kind, &next.description).await?;
+
+        // if either half fails, the planned session should stay in the queue with nothing started
+        let mut transaction = pool.begin().await.map_err(Error::Query)?;
+        let session = Session::start_in(
+            &mut transaction,
+            user_id,
+            &next.kind,
+            &next.description,
+            start_time,
+            duration,
+            time_zone,
+            &[],
+            Some(&next),
+        )
+        .await?;
+        Self::remove(&mut transaction, user_id, next.id).await?;
+        transaction.commit().await.map_err(Error::Query)?;
+
+        tracing::info!(description = next.description, "started planned session");
+
+        Ok(session)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::user::DEFAULT_USER_ID;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn get_pool() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new().connect(":memory:").await.unwrap();
+
+        sqlx::migrate!("db/migrations").run(&pool).await.unwrap();
+
+        pool
+    }
+
+    async fn descriptions(pool: &Pool<Sqlite>) -> Vec<String> {
+        PlannedSession::queue(pool, DEFAULT_USER_ID)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|planned| planned.description)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn enqueue_adds_to_the_end() {
+        let pool = get_pool().await;
+
+        PlannedSession::enqueue(&pool, DEFAULT_USER_ID, "task", "a", None)
+            .await
+            .unwrap();
+        let b = PlannedSession::enqueue(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "b",
+            Some(Duration::minutes(50)),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(b.position, 1);
+        assert_eq!(b.duration, Some(Duration::minutes(50)));
+        assert_eq!(descriptions(&pool).await, vec!["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn dequeue_closes_the_gap() {
+        let pool = get_pool().await;
+
+        PlannedSession::enqueue(&pool, DEFAULT_USER_ID, "task", "a", None)
+            .await
+            .unwrap();
+        let b = PlannedSession::enqueue(&pool, DEFAULT_USER_ID, "task", "b", None)
+            .await
+            .unwrap();
+        PlannedSession::enqueue(&pool, DEFAULT_USER_ID, "task", "c", None)
+            .await
+            .unwrap();
+
+        PlannedSession::dequeue(&pool, DEFAULT_USER_ID, b.id)
+            .await
+            .unwrap();
+
+        let queue = PlannedSession::queue(&pool, DEFAULT_USER_ID).await.unwrap();
+        assert_eq!(
+            queue.iter().map(|p| p.position).collect::<Vec<i64>>(),
+            vec![0, 1]
+        );
+        assert_eq!(descriptions(&pool).await, vec!["a", "c"]);
+    }
+
+    #[tokio::test]
+    async fn reorder_moves_sessions_up_and_down() {
+        let pool = get_pool().await;
+
+        PlannedSession::enqueue(&pool, DEFAULT_USER_ID, "task", "a", None)
+            .await
+            .unwrap();
+        PlannedSession::enqueue(&pool, DEFAULT_USER_ID, "task", "b", None)
+            .await
+            .unwrap();
+        let c = PlannedSession::enqueue(&pool, DEFAULT_USER_ID, "task", "c", None)
+            .await
+            .unwrap();
+
+        PlannedSession::reorder(&pool, DEFAULT_USER_ID, c.id, 0)
+            .await
+            .unwrap();
+        assert_eq!(descriptions(&pool).await, vec!["c", "a", "b"]);
+
+        PlannedSession::reorder(&pool, DEFAULT_USER_ID, c.id, 100)
+            .await
+            .unwrap();
+        assert_eq!(descriptions(&pool).await, vec!["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn start_next_starts_the_head_of_the_queue() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        PlannedSession::enqueue(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "a",
+            Some(Duration::minutes(50)),
+        )
+        .await
+        .unwrap();
+        PlannedSession::enqueue(&pool, DEFAULT_USER_ID, "break", "b", None)
+            .await
+            .unwrap();
+
+        let session = PlannedSession::start_next(&pool, DEFAULT_USER_ID, now, Tz::UTC)
+            .await
+            .unwrap();
+
+        assert_eq!(session.description, "a");
+        assert_eq!(session.duration, Duration::minutes(50));
+        assert_eq!(descriptions(&pool).await, vec!["b"]);
+        assert_eq!(
+            PlannedSession::next(&pool, DEFAULT_USER_ID)
+                .await
+                .unwrap()
+                .unwrap()
+                .position,
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn start_next_fails_with_an_empty_queue() {
+        let pool = get_pool().await;
+
+        match PlannedSession::start_next(&pool, DEFAULT_USER_ID, Utc::now(), Tz::UTC).await {
+            Err(Error::QueueIsEmpty) => (),
+            other => panic!("expected QueueIsEmpty, got {other:?}"),
+        }
+    }
+}
@@ -1,8 +1,18 @@
+use super::audit::AuditEvent;
+use super::cycle::Cycle;
 use super::error::{Error, Result};
+use super::goal::{Goal, GoalProgress};
+use super::kind::Kind;
+use super::planned_session::PlannedSession;
 use super::report::Report;
 use super::session::Session;
+use super::settings::Settings;
+use super::time_zone;
+use super::user::User;
+use super::webhook::{Webhook, WebhookDelivery};
 use async_graphql::{Context, Object};
-use chrono::{DateTime, Local};
+use chrono::{DateTime, FixedOffset};
+use chrono_tz::Tz;
 
 pub struct Query;
 
@@ -12,8 +22,91 @@ impl Query {
         env!("CARGO_PKG_VERSION")
     }
 
+    /// The user making this request
+    async fn me(&self, context: &Context<'_>) -> Result<User> {
+        User::from_context(context).cloned()
+    }
+
     async fn current_session(&self, context: &Context<'_>) -> Result<Option<Session>> {
-        Session::current_session(context.data().map_err(Error::Context)?).await
+        Session::current_session(
+            context.data().map_err(Error::Context)?,
+            User::from_context(context)?.id,
+        )
+        .await
+    }
+
+    /// Sessions planned for later, in the order they'll be started
+    async fn queue(&self, context: &Context<'_>) -> Result<Vec<PlannedSession>> {
+        PlannedSession::queue(
+            context.data().map_err(Error::Context)?,
+            User::from_context(context)?.id,
+        )
+        .await
+    }
+
+    /// Where we are in the current Pomodoro set
+    async fn cycle(&self, context: &Context<'_>) -> Result<Cycle> {
+        Cycle::current(
+            context.data().map_err(Error::Context)?,
+            User::from_context(context)?.id,
+        )
+        .await
+    }
+
+    /// Server-wide settings, shared by every user
+    async fn settings(&self, context: &Context<'_>) -> Result<Settings> {
+        Settings::load(context.data().map_err(Error::Context)?).await
+    }
+
+    /// The kinds of sessions we know about, shared by every user
+    async fn kinds(&self, context: &Context<'_>) -> Result<Vec<Kind>> {
+        Kind::all(context.data().map_err(Error::Context)?).await
+    }
+
+    /// Endpoints that get told about session lifecycle events
+    async fn webhooks(&self, context: &Context<'_>) -> Result<Vec<Webhook>> {
+        Webhook::all(
+            context.data().map_err(Error::Context)?,
+            User::from_context(context)?.id,
+        )
+        .await
+    }
+
+    /// Recent attempts to deliver events to webhooks, newest first
+    async fn webhook_deliveries(
+        &self,
+        context: &Context<'_>,
+        #[graphql(desc = "Only show deliveries to this webhook (Omit to show all of them)")]
+        webhook_id: Option<i64>,
+        #[graphql(desc = "How many deliveries to show", default = 50)] limit: i64,
+    ) -> Result<Vec<WebhookDelivery>> {
+        WebhookDelivery::recent(
+            context.data().map_err(Error::Context)?,
+            User::from_context(context)?.id,
+            webhook_id,
+            limit,
+        )
+        .await
+    }
+
+    /// How the goals are going in the current day or week
+    async fn goals(
+        &self,
+        context: &Context<'_>,
+        #[graphql(
+            desc = "Which time zone should days and weeks start in? (Omit to use the server's time zone)"
+        )]
+        time_zone: Option<Tz>,
+    ) -> Result<Vec<GoalProgress>> {
+        let time_zone = time_zone.unwrap_or_else(time_zone::local);
+
+        Goal::progress(
+            context.data().map_err(Error::Context)?,
+            User::from_context(context)?.id,
+            chrono::Utc::now().with_timezone(&time_zone).date_naive(),
+            time_zone,
+        )
+        .await
     }
 
     /// Get a report on the sessions in a given range (start and end will be treated as a date
@@ -22,9 +115,32 @@ impl Query {
     async fn report(
         &self,
         context: &Context<'_>,
-        start: DateTime<Local>,
-        end: DateTime<Local>,
+        start: DateTime<FixedOffset>,
+        end: DateTime<FixedOffset>,
+        #[graphql(
+            desc = "Which time zone should days start and end in? (Omit to use the server's time zone)"
+        )]
+        time_zone: Option<Tz>,
     ) -> Result<Report> {
-        Report::for_range_inclusive(context.data().map_err(Error::Context)?, start, end).await
+        let time_zone = time_zone.unwrap_or_else(time_zone::local);
+
+        Report::for_range_inclusive(
+            context.data().map_err(Error::Context)?,
+            User::from_context(context)?.id,
+            start.with_timezone(&time_zone).date_naive(),
+            end.with_timezone(&time_zone).date_naive(),
+            time_zone,
+        )
+        .await
+    }
+
+    /// Every change made to a session, oldest first
+    async fn history(&self, context: &Context<'_>, session_id: i64) -> Result<Vec<AuditEvent>> {
+        AuditEvent::history(
+            context.data().map_err(Error::Context)?,
+            User::from_context(context)?.id,
+            session_id,
+        )
+        .await
     }
 }
@@ -1,86 +1,100 @@
-use super::kind::Kind;
+use super::error::{Error, Result};
+use super::goal::{Goal, GoalProgress};
+use super::outcome::Outcome;
 use super::session::Session;
-use super::{error::Result, kind::BreakKind};
-use async_graphql::{ComplexObject, SimpleObject};
-use chrono::{DateTime, Datelike, Duration, Local, Timelike};
-use sqlx::{Pool, Sqlite};
-use std::collections::HashMap;
+use super::time_zone;
+use async_graphql::{ComplexObject, Context, SimpleObject};
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, Utc};
+use chrono_tz::Tz;
+use indoc::indoc;
+use sqlx::{sqlite::SqliteRow, FromRow, Pool, Sqlite};
 
 /// A report on sessons started and ended during a given day plus some statistics.
 #[derive(SimpleObject, Debug)]
 #[graphql(complex)]
 pub struct Report {
     /// The first date with sessions
-    pub start: DateTime<Local>,
+    pub start: DateTime<FixedOffset>,
 
     /// The last date with sessions
-    pub end: DateTime<Local>,
+    pub end: DateTime<FixedOffset>,
+
+    /// The time zone used to decide where days start and end
+    pub time_zone: Tz,
 
     /// The sessions included in this report
     pub sessions: Vec<Session>,
+
+    /// Whose sessions these are
+    #[graphql(skip)]
+    pub user_id: i64,
+
+    /// When the last day in the report is over
+    #[graphql(skip)]
+    pub until: DateTime<Utc>,
 }
 
 #[ComplexObject]
 impl Report {
     /// Aggregate totals of the time spent in sessions
-    async fn totals(&self) -> Totals {
-        self.get_totals()
+    async fn totals(&self, context: &Context<'_>) -> Result<Totals> {
+        Totals::for_range(
+            context.data().map_err(Error::Context)?,
+            self.user_id,
+            self.start.with_timezone(&Utc),
+            self.until,
+        )
+        .await
+    }
+
+    /// How the goals went in the day and week containing the last day of the report
+    async fn goals(&self, context: &Context<'_>) -> Result<Vec<GoalProgress>> {
+        Goal::progress(
+            context.data().map_err(Error::Context)?,
+            self.user_id,
+            self.end.date_naive(),
+            self.time_zone,
+        )
+        .await
     }
 }
 
 impl Report {
     pub async fn for_range_inclusive(
         pool: &Pool<Sqlite>,
-        start: DateTime<Local>,
-        end: DateTime<Local>,
+        user_id: i64,
+        start: NaiveDate,
+        end: NaiveDate,
+        time_zone: Tz,
     ) -> Result<Self> {
-        let start_date = at_midnight(start);
-        let end_date = at_midnight(end);
+        let start_time = time_zone::start_of_day(start, time_zone);
+        let end_time = time_zone::start_of_day(end, time_zone);
+        let until = time_zone::start_of_day(end + Duration::days(1), time_zone);
 
-        let sessions = Session::for_range_inclusive(pool, start_date, end_date).await?;
+        let sessions = Session::for_range(pool, user_id, start_time, until).await?;
 
         Ok(Self {
             sessions,
-            start: start_date,
-            end: end_date,
+            user_id,
+            start: start_time.with_timezone(&time_zone).fixed_offset(),
+            end: end_time.with_timezone(&time_zone).fixed_offset(),
+            time_zone,
+            until,
         })
     }
-
-    pub fn get_totals(&self) -> Totals {
-        Totals::from_sessions(&self.sessions, self.start, self.end)
-    }
-}
-
-fn at_midnight<TZ: chrono::TimeZone>(date: DateTime<TZ>) -> DateTime<TZ> {
-    if date.num_seconds_from_midnight() == 0 {
-        date
-    } else {
-        date.timezone()
-            .with_ymd_and_hms(date.year(), date.month(), date.day(), 0, 0, 0)
-            .unwrap()
-    }
-}
-
-fn at_one_second_to_midnight<TZ: chrono::TimeZone>(date: DateTime<TZ>) -> DateTime<TZ> {
-    if date.num_seconds_from_midnight() == 86399 {
-        date
-    } else {
-        date.timezone()
-            .with_ymd_and_hms(date.year(), date.month(), date.day(), 23, 59, 59)
-            .unwrap()
-    }
 }
 
 /// Totals for each kind of session. If sessions started on one day and ended another, and the
 /// start or end date would cut part of that time off, we only count to or from midnight in the
-/// local time zone. Incomplete sessions are included in these totals!
+/// report's time zone. Incomplete sessions are included in these totals!
 #[derive(SimpleObject, Debug, PartialEq, Eq)]
-#[graphql(complex)]
 pub struct Totals {
-    /// The total time spent in short breaks (that is, those 15 minutes or less)
+    /// The total time spent in short breaks (that is, those no longer than the short break cutoff
+    /// in the settings)
     pub short_break: Duration,
 
-    /// The total time spent in long breaks (that is, those more than 15 minutes)
+    /// The total time spent in long breaks (that is, those longer than the short break cutoff in
+    /// the settings)
     pub long_break: Duration,
 
     /// The total time spent on tasks
@@ -91,6 +105,26 @@ pub struct Totals {
 
     /// Total time spent in meetings
     meeting: Duration,
+
+    /// The total spent "working"—that is, in short breaks and in sessions whose kind counts as
+    /// working time (tasks and meetings, unless you've added your own kinds.)
+    pub working: Duration,
+
+    /// Total time spent in each kind of session, including kinds you've added yourself
+    pub totals_by_kind: Vec<TotalByKind>,
+
+    /// Total time spent working, broken down by tag. Sessions with more than one tag count towards
+    /// each of them.
+    pub totals_by_tag: Vec<TotalByTag>,
+
+    /// How the task sessions during the report's time period turned out
+    pub task_outcomes: OutcomeCounts,
+
+    /// How many interruptions were logged during each kind of session
+    pub interruptions_by_kind: Vec<InterruptionsByKind>,
+
+    /// How many times sessions of each kind were extended, and how much time that added
+    pub extensions_by_kind: Vec<ExtensionsByKind>,
 }
 
 /// A description (of a task or break) and the total time spent on it during the report's time
@@ -98,15 +132,71 @@ pub struct Totals {
 #[derive(SimpleObject, Debug, PartialEq, Eq)]
 pub struct TotalByDescription {
     description: String,
-    kind: Kind,
+    kind: String,
+    total: Duration,
+
+    /// How many interruptions were logged during these sessions
+    interruptions: i64,
+}
+
+/// A kind of session and how many interruptions were logged during sessions of that kind.
+#[derive(SimpleObject, Debug, PartialEq, Eq)]
+pub struct InterruptionsByKind {
+    kind: String,
+    count: i64,
+}
+
+/// A kind of session, how many times sessions of that kind were extended (with `extendBy` or
+/// `extendTo`), and how much time the extensions added in total.
+#[derive(SimpleObject, Debug, PartialEq, Eq)]
+pub struct ExtensionsByKind {
+    kind: String,
+    count: i64,
+    added: Duration,
+}
+
+/// A kind of session and the total time spent in sessions of that kind during the report's time
+/// period.
+#[derive(SimpleObject, Debug, PartialEq, Eq)]
+pub struct TotalByKind {
+    kind: String,
     total: Duration,
 }
 
+/// A tag and the total time spent in sessions with that tag during the report's time period.
+#[derive(SimpleObject, Debug, PartialEq, Eq)]
+pub struct TotalByTag {
+    tag: String,
+    total: Duration,
+}
+
+/// How many sessions had each outcome
+#[derive(SimpleObject, Debug, Default, PartialEq, Eq)]
+#[graphql(complex)]
+pub struct OutcomeCounts {
+    completed: i64,
+    partial: i64,
+    abandoned: i64,
+    unknown: i64,
+}
+
 #[ComplexObject]
-impl Totals {
-    /// The total spent "working"—that is, on tasks, meetings, and short breaks
-    async fn working(&self) -> Duration {
-        self.short_break + self.task + self.meeting
+impl OutcomeCounts {
+    /// How many sessions there were in total, whatever their outcome
+    async fn total(&self) -> i64 {
+        self.completed + self.partial + self.abandoned + self.unknown
+    }
+
+    /// What fraction (between 0 and 1) of sessions were completed? Empty if there weren't any
+    /// sessions.
+    async fn completion_rate(&self) -> Option<f64> {
+        let total = self.completed + self.partial + self.abandoned + self.unknown;
+
+        if total == 0 {
+            None
+        } else {
+            Some(self.completed as f64 / total as f64)
+        }
     }
 }
 
@@ -118,214 +208,921 @@ impl Default for Totals {
             task: Duration::zero(),
             sessions_by_description: Vec::new(),
             meeting: Duration::zero(),
+            working: Duration::zero(),
+            totals_by_kind: Vec::new(),
+            totals_by_tag: Vec::new(),
+            task_outcomes: OutcomeCounts::default(),
+            interruptions_by_kind: Vec::new(),
+            extensions_by_kind: Vec::new(),
         }
     }
 }
 
+/// Every one of a user's sessions that started or ended in a range, with the (unpaused) time it
+/// spent inside the range, the interruptions logged inside the range, which total it counts
+/// towards, and whether it counts as working time. Breaks are sorted into short and long by how
+/// long the whole break took, not just the part inside the range.
+///
+/// Parameters are the start and end of the range (?1 and ?2 as timestamps, so we can use the index
+/// on `start_time`, and ?3 and ?4 as Unix seconds), the current time in Unix seconds for sessions
+/// and pauses that haven't ended yet (?5), and the user (?6). The longest a short break can be
+/// comes from `settings`.
+const COUNTED_SESSIONS: &str = indoc! {"
+    WITH in_range AS (
+        SELECT
+            id,
+            kind,
+            description,
+            outcome,
+            unixepoch(start_time) AS started,
+            COALESCE(unixepoch(end_time), ?5) AS ended,
+            (
+                SELECT COUNT(*)
+                FROM interruptions
+                WHERE interruptions.session_id = sessions.id
+                  AND interruptions.created_at >= ?1
+                  AND interruptions.created_at < ?2
+            ) AS interruptions
+        FROM sessions
+        WHERE user_id = ?6
+          AND deleted_at IS NULL
+          AND ((start_time >= ?1 AND start_time < ?2)
+            OR (end_time   >= ?1 AND end_time   < ?2))
+    ),
+    timed AS (
+        SELECT
+            in_range.id,
+            in_range.kind,
+            in_range.description,
+            in_range.outcome,
+            in_range.interruptions,
+            MAX(MIN(ended, ?4) - MAX(started, ?3), 0) - COALESCE(SUM(MAX(
+                MIN(ended, ?4, COALESCE(unixepoch(session_pauses.end_time), ?5))
+                    - MAX(started, ?3, unixepoch(session_pauses.start_time)),
+                0
+            )), 0) AS within_range,
+            ended - started - COALESCE(SUM(MAX(
+                MIN(ended, COALESCE(unixepoch(session_pauses.end_time), ?5))
+                    - MAX(started, unixepoch(session_pauses.start_time)),
+                0
+            )), 0) AS actual
+        FROM in_range
+        LEFT JOIN session_pauses ON session_pauses.session_id = in_range.id
+        GROUP BY in_range.id
+    ),
+    categorized AS (
+        SELECT
+            timed.*,
+            CASE
+                WHEN kinds.is_break AND actual > (SELECT short_break_cutoff FROM settings) THEN 'long_break'
+                WHEN kinds.is_break THEN 'short_break'
+                ELSE kind
+            END AS category,
+            COALESCE(kinds.is_working, FALSE) AS kind_is_working
+        FROM timed
+        LEFT JOIN kinds ON kinds.name = timed.kind
+    ),
+    counted AS (
+        SELECT
+            id,
+            kind,
+            description,
+            outcome,
+            interruptions,
+            within_range,
+            category,
+            category = 'short_break' OR kind_is_working AS is_working
+        FROM categorized
+    )
+"};
+
 impl Totals {
-    fn from_sessions(
-        sessions: &Vec<Session>,
-        start: DateTime<Local>,
-        end: DateTime<Local>,
-    ) -> Self {
+    pub async fn for_range(
+        pool: &Pool<Sqlite>,
+        user_id: i64,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Self> {
         let mut totals = Self::default();
-        let mut sessions_by_description = HashMap::with_capacity(sessions.len());
-
-        let start_date = at_midnight(start);
-        let end_date = at_one_second_to_midnight(end);
-
-        for session in sessions.iter() {
-            let session_total_within_dates = session.total_time_within_dates(start_date, end_date);
-            debug_assert!(session_total_within_dates >= Duration::zero());
-
-            match session.kind {
-                Kind::Task => {
-                    totals.task = totals.task + session_total_within_dates;
-
-                    // TODO: deduplicate?
-                    sessions_by_description
-                        .entry((&session.description, &session.kind))
-                        .and_modify(|current| *current = *current + session_total_within_dates)
-                        .or_insert(session_total_within_dates);
-                }
-                Kind::Break => match BreakKind::from(session.get_actual_duration()) {
-                    BreakKind::Short => {
-                        totals.short_break = totals.short_break + session_total_within_dates;
-
-                        sessions_by_description
-                            .entry((&session.description, &session.kind))
-                            .and_modify(|current| *current = *current + session_total_within_dates)
-                            .or_insert(session_total_within_dates);
-                    }
-                    BreakKind::Long => {
-                        totals.long_break = totals.long_break + session_total_within_dates
-                    }
+
+        let by_category: Vec<(String, bool, i64)> = Self::query_counted(
+            pool,
+            user_id,
+            start,
+            end,
+            indoc! {"
+                SELECT category, is_working, SUM(within_range)
+                FROM counted
+                GROUP BY category, is_working
+            "},
+        )
+        .await?;
+
+        for (category, is_working, seconds) in by_category {
+            let total = Duration::seconds(seconds);
+
+            if is_working {
+                totals.working = totals.working + total;
+            }
+
+            match category.as_str() {
+                "task" => totals.task = total,
+                "meeting" => totals.meeting = total,
+                "short_break" => totals.short_break = total,
+                "long_break" => totals.long_break = total,
+                _ => (),
+            }
+        }
+
+        let by_kind: Vec<(String, i64)> = Self::query_counted(
+            pool,
+            user_id,
+            start,
+            end,
+            indoc! {"
+                SELECT kind, SUM(within_range) AS total
+                FROM counted
+                GROUP BY kind
+                ORDER BY total DESC, kind ASC
+            "},
+        )
+        .await?;
+
+        totals.totals_by_kind = by_kind
+            .into_iter()
+            .map(|(kind, seconds)| TotalByKind {
+                kind,
+                total: Duration::seconds(seconds),
+            })
+            .collect();
+
+        let by_description: Vec<(String, String, i64, i64)> = Self::query_counted(
+            pool,
+            user_id,
+            start,
+            end,
+            indoc! {"
+                SELECT description, kind, SUM(within_range) AS total, SUM(interruptions)
+                FROM counted
+                WHERE is_working
+                GROUP BY description, kind
+                ORDER BY total DESC, description ASC
+            "},
+        )
+        .await?;
+
+        totals.sessions_by_description = by_description
+            .into_iter()
+            .map(
+                |(description, kind, seconds, interruptions)| TotalByDescription {
+                    description,
+                    kind,
+                    total: Duration::seconds(seconds),
+                    interruptions,
                 },
-                Kind::Meeting => {
-                    totals.meeting = totals.meeting + session_total_within_dates;
-
-                    sessions_by_description
-                        .entry((&session.description, &session.kind))
-                        .and_modify(|current| *current = *current + session_total_within_dates)
-                        .or_insert(session_total_within_dates);
-                }
-                Kind::Offline => continue,
-            };
+            )
+            .collect();
+
+        let by_tag: Vec<(String, i64)> = Self::query_counted(
+            pool,
+            user_id,
+            start,
+            end,
+            indoc! {"
+                SELECT tags.name, SUM(counted.within_range) AS total
+                FROM counted
+                JOIN session_tags ON session_tags.session_id = counted.id
+                JOIN tags ON tags.id = session_tags.tag_id
+                WHERE counted.is_working
+                GROUP BY tags.name
+                ORDER BY total DESC, tags.name ASC
+            "},
+        )
+        .await?;
+
+        totals.totals_by_tag = by_tag
+            .into_iter()
+            .map(|(tag, seconds)| TotalByTag {
+                tag,
+                total: Duration::seconds(seconds),
+            })
+            .collect();
+
+        let by_outcome: Vec<(Outcome, i64)> = Self::query_counted(
+            pool,
+            user_id,
+            start,
+            end,
+            indoc! {"
+                SELECT outcome, COUNT(*)
+                FROM counted
+                WHERE kind = 'task'
+                GROUP BY outcome
+            "},
+        )
+        .await?;
+
+        for (outcome, count) in by_outcome {
+            match outcome {
+                Outcome::Completed => totals.task_outcomes.completed = count,
+                Outcome::Partial => totals.task_outcomes.partial = count,
+                Outcome::Abandoned => totals.task_outcomes.abandoned = count,
+                Outcome::Unknown => totals.task_outcomes.unknown = count,
+            }
         }
 
-        debug_assert!(totals.short_break >= Duration::zero());
-        debug_assert!(totals.long_break >= Duration::zero());
-        debug_assert!(totals.task >= Duration::zero());
+        let interruptions_by_kind: Vec<(String, i64)> = Self::query_counted(
+            pool,
+            user_id,
+            start,
+            end,
+            indoc! {"
+                SELECT kind, SUM(interruptions) AS count
+                FROM counted
+                GROUP BY kind
+                HAVING count > 0
+                ORDER BY count DESC, kind ASC
+            "},
+        )
+        .await?;
+
+        totals.interruptions_by_kind = interruptions_by_kind
+            .into_iter()
+            .map(|(kind, count)| InterruptionsByKind { kind, count })
+            .collect();
+
+        let extensions_by_kind: Vec<(String, i64, i64)> = Self::query_counted(
+            pool,
+            user_id,
+            start,
+            end,
+            indoc! {"
+                SELECT
+                    counted.kind,
+                    COUNT(*) AS count,
+                    SUM(
+                        json_extract(events.after, '$.duration')
+                            - json_extract(events.before, '$.duration')
+                    )
+                FROM counted
+                JOIN events ON events.session_id = counted.id
+                WHERE events.mutation IN ('extendBy', 'extendTo')
+                  AND COALESCE(events.change_id, events.id) NOT IN (
+                      SELECT json_extract(undos.arguments, '$.changeId')
+                      FROM events AS undos
+                      WHERE undos.mutation = 'undo'
+                  )
+                GROUP BY counted.kind
+                ORDER BY count DESC, counted.kind ASC
+            "},
+        )
+        .await?;
 
-        totals.sessions_by_description = sessions_by_description
-            .drain()
-            .map(|((description, kind), total)| TotalByDescription {
-                description: description.clone(),
-                kind: *kind,
-                total,
+        totals.extensions_by_kind = extensions_by_kind
+            .into_iter()
+            .map(|(kind, count, seconds)| ExtensionsByKind {
+                kind,
+                count,
+                added: Duration::seconds(seconds),
             })
             .collect();
-        totals.sessions_by_description.sort_by_key(|t| -t.total);
 
-        totals
+        Ok(totals)
+    }
+
+    /// How much time was spent in sessions of the given kind?
+    pub fn time_for_kind(&self, kind: &str) -> Duration {
+        self.totals_by_kind
+            .iter()
+            .find(|total| total.kind == kind)
+            .map(|total| total.total)
+            .unwrap_or_else(Duration::zero)
+    }
+
+    /// How much time was spent in sessions with the given tag?
+    pub fn time_for_tag(&self, tag: &str) -> Duration {
+        self.totals_by_tag
+            .iter()
+            .find(|total| total.tag == tag)
+            .map(|total| total.total)
+            .unwrap_or_else(Duration::zero)
+    }
+
+    /// Run a query against the `counted` table from `COUNTED_SESSIONS`.
+    async fn query_counted<T>(
+        pool: &Pool<Sqlite>,
+        user_id: i64,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        select: &str,
+    ) -> Result<Vec<T>>
+    where
+        T: for<'r> FromRow<'r, SqliteRow> + Send + Unpin,
+    {
+        sqlx::query_as::<_, T>(&format!("{COUNTED_SESSIONS}{select}"))
+            .bind(start)
+            .bind(end)
+            .bind(start.timestamp())
+            .bind(end.timestamp())
+            .bind(Utc::now().timestamp())
+            .bind(user_id)
+            .fetch_all(pool)
+            .await
+            .map_err(Error::Query)
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::interruption::Interruption;
+    use crate::kind::Kind;
+    use crate::settings::{Settings, SettingsChanges};
+    use crate::tag::Tag;
+    use crate::undo::Undone;
+    use crate::user::DEFAULT_USER_ID;
+    use chrono::TimeZone;
+    use sqlx::sqlite::SqlitePoolOptions;
 
-    fn session(kind: Kind, start: DateTime<Local>, duration: Duration, ended: bool) -> Session {
-        Session {
-            id: 0,
-            description: String::from("description"),
-            kind,
-            start_time: start,
-            duration,
-            end_time: if ended { Some(start + duration) } else { None },
-        }
+    async fn get_pool() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new().connect(":memory:").await.unwrap();
+
+        sqlx::migrate!("db/migrations").run(&pool).await.unwrap();
+
+        pool
     }
 
-    #[test]
-    fn adds_tasks() {
-        let now = Local::now();
+    async fn session(
+        pool: &Pool<Sqlite>,
+        kind: &str,
+        start: DateTime<Utc>,
+        duration: Duration,
+        ended: bool,
+    ) -> i64 {
+        sqlx::query_scalar(indoc! {"
+            INSERT INTO sessions (kind, description, start_time, duration, end_time, time_zone)
+            VALUES (?, 'description', ?, ?, ?, 'UTC')
+            RETURNING id
+        "})
+        .bind(kind)
+        .bind(start)
+        .bind(duration.num_seconds())
+        .bind(if ended { Some(start + duration) } else { None })
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
 
-        let totals = Totals::from_sessions(
-            &vec![
-                session(Kind::Task, now, Duration::minutes(5), true),
-                session(Kind::Task, now, Duration::minutes(5), true),
-            ],
+    async fn totals_around(pool: &Pool<Sqlite>, now: DateTime<Utc>) -> Totals {
+        Totals::for_range(
+            pool,
+            DEFAULT_USER_ID,
             now - Duration::days(1),
             now + Duration::days(1),
-        );
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn adds_tasks() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        session(&pool, "task", now, Duration::minutes(5), true).await;
+        session(&pool, "task", now, Duration::minutes(5), true).await;
 
         assert_eq!(
-            totals,
+            totals_around(&pool, now).await,
             Totals {
                 short_break: Duration::zero(),
                 long_break: Duration::zero(),
                 task: Duration::minutes(10),
                 sessions_by_description: vec![TotalByDescription {
                     description: String::from("description"),
-                    kind: Kind::Task,
+                    kind: String::from("task"),
                     total: Duration::minutes(10),
+                    interruptions: 0,
                 }],
                 meeting: Duration::zero(),
+                working: Duration::minutes(10),
+                totals_by_kind: vec![TotalByKind {
+                    kind: String::from("task"),
+                    total: Duration::minutes(10),
+                }],
+                totals_by_tag: Vec::new(),
+                task_outcomes: OutcomeCounts {
+                    unknown: 2,
+                    ..OutcomeCounts::default()
+                },
+                interruptions_by_kind: Vec::new(),
+                extensions_by_kind: Vec::new(),
             }
         )
     }
 
-    #[test]
-    fn adds_short_breaks() {
-        let now = Local::now();
+    #[tokio::test]
+    async fn adds_short_breaks() {
+        let pool = get_pool().await;
+        let now = Utc::now();
 
-        let totals = Totals::from_sessions(
-            &vec![
-                session(Kind::Break, now, Duration::minutes(5), true),
-                session(Kind::Break, now, Duration::minutes(5), true),
-            ],
-            now - Duration::days(1),
-            now + Duration::days(1),
-        );
+        session(&pool, "break", now, Duration::minutes(5), true).await;
+        session(&pool, "break", now, Duration::minutes(5), true).await;
+
+        let totals = totals_around(&pool, now).await;
 
         assert_eq!(totals.short_break, Duration::minutes(10));
         assert_eq!(totals.long_break, Duration::zero());
         assert_eq!(totals.meeting, Duration::zero());
     }
 
-    #[test]
-    fn adds_long_breaks() {
-        let now = Local::now();
+    #[tokio::test]
+    async fn counts_breaks_at_the_cutoff_as_short() {
+        let pool = get_pool().await;
+        let now = Utc::now();
 
-        let totals = Totals::from_sessions(
-            &vec![
-                session(Kind::Break, now, Duration::hours(1), true),
-                session(Kind::Break, now, Duration::hours(1), true),
-            ],
-            now - Duration::days(1),
-            now + Duration::days(1),
-        );
+        let cutoff = Settings::load(&pool).await.unwrap().short_break_cutoff;
+
+        session(&pool, "break", now, cutoff, true).await;
+
+        let totals = totals_around(&pool, now).await;
+
+        assert_eq!(totals.short_break, cutoff);
+        assert_eq!(totals.long_break, Duration::zero());
+    }
+
+    #[tokio::test]
+    async fn uses_the_break_cutoff_from_settings() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        Settings::update(
+            &pool,
+            SettingsChanges {
+                short_break_cutoff: Some(Duration::minutes(30)),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        session(&pool, "break", now, Duration::minutes(20), true).await;
+
+        let totals = totals_around(&pool, now).await;
+
+        assert_eq!(totals.short_break, Duration::minutes(20));
+        assert_eq!(totals.long_break, Duration::zero());
+    }
+
+    #[tokio::test]
+    async fn adds_long_breaks() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        session(&pool, "break", now, Duration::hours(1), true).await;
+        session(&pool, "break", now, Duration::hours(1), true).await;
+
+        let totals = totals_around(&pool, now).await;
 
         assert_eq!(totals.short_break, Duration::zero());
         assert_eq!(totals.long_break, Duration::hours(2));
         assert_eq!(totals.meeting, Duration::zero());
     }
 
-    #[test]
-    fn adds_meetings() {
-        let now = Local::now();
+    #[tokio::test]
+    async fn adds_meetings() {
+        let pool = get_pool().await;
+        let now = Utc::now();
 
-        let totals = Totals::from_sessions(
-            &vec![
-                session(Kind::Meeting, now, Duration::hours(1), true),
-                session(Kind::Meeting, now, Duration::hours(1), true),
-            ],
-            now - Duration::days(1),
-            now + Duration::days(1),
-        );
+        session(&pool, "meeting", now, Duration::hours(1), true).await;
+        session(&pool, "meeting", now, Duration::hours(1), true).await;
+
+        let totals = totals_around(&pool, now).await;
 
         assert_eq!(totals.short_break, Duration::zero());
         assert_eq!(totals.long_break, Duration::zero());
         assert_eq!(totals.meeting, Duration::hours(2));
     }
 
-    #[test]
-    fn cuts_off_overnight_breaks() {
-        let today = at_midnight(Local::now());
+    #[tokio::test]
+    async fn cuts_off_overnight_breaks() {
+        let pool = get_pool().await;
+        let today = time_zone::start_of_day(Utc::now().date_naive(), Tz::UTC);
 
-        let totals = Totals::from_sessions(
-            &vec![session(
-                Kind::Break,
-                today - Duration::hours(8),
-                Duration::hours(16),
-                true,
-            )],
-            today,
-            today + Duration::days(1),
-        );
+        session(
+            &pool,
+            "break",
+            today - Duration::hours(8),
+            Duration::hours(16),
+            true,
+        )
+        .await;
+
+        let totals = Totals::for_range(&pool, DEFAULT_USER_ID, today, today + Duration::days(1))
+            .await
+            .unwrap();
 
         assert_eq!(totals.short_break, Duration::zero());
         assert_eq!(totals.long_break, Duration::hours(8));
         assert_eq!(totals.meeting, Duration::zero());
     }
 
-    #[test]
-    fn excludes_offline_time() {
-        let today = at_midnight(Local::now());
+    #[tokio::test]
+    async fn cuts_off_sessions_that_run_past_the_end() {
+        let pool = get_pool().await;
+        let today = time_zone::start_of_day(Utc::now().date_naive(), Tz::UTC);
 
-        let totals = Totals::from_sessions(
-            &vec![session(
-                Kind::Offline,
-                today - Duration::hours(8),
-                Duration::hours(16),
-                true,
-            )],
-            today,
-            today + Duration::days(1),
-        );
+        session(
+            &pool,
+            "task",
+            today + Duration::hours(23),
+            Duration::hours(2),
+            true,
+        )
+        .await;
+
+        let totals = Totals::for_range(&pool, DEFAULT_USER_ID, today, today + Duration::days(1))
+            .await
+            .unwrap();
+
+        assert_eq!(totals.task, Duration::hours(1));
+    }
+
+    #[tokio::test]
+    async fn excludes_offline_time() {
+        let pool = get_pool().await;
+        let today = time_zone::start_of_day(Utc::now().date_naive(), Tz::UTC);
+
+        session(
+            &pool,
+            "offline",
+            today - Duration::hours(8),
+            Duration::hours(16),
+            true,
+        )
+        .await;
+
+        let totals = Totals::for_range(&pool, DEFAULT_USER_ID, today, today + Duration::days(1))
+            .await
+            .unwrap();
 
         assert_eq!(totals.short_break, Duration::zero());
         assert_eq!(totals.long_break, Duration::zero());
         assert_eq!(totals.meeting, Duration::zero());
+        assert_eq!(totals.sessions_by_description, Vec::new());
+    }
+
+    #[tokio::test]
+    async fn counts_added_kinds_as_working_time_if_they_say_so() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        Kind::create(
+            &pool,
+            "pairing",
+            Duration::minutes(50),
+            true,
+            false,
+            false,
+            false,
+            "👯",
+        )
+        .await
+        .unwrap();
+        Kind::create(
+            &pool,
+            "lunch",
+            Duration::minutes(45),
+            false,
+            true,
+            false,
+            false,
+            "🥪",
+        )
+        .await
+        .unwrap();
+
+        session(&pool, "pairing", now, Duration::minutes(50), true).await;
+        session(&pool, "lunch", now, Duration::minutes(45), true).await;
+        session(&pool, "task", now, Duration::minutes(25), true).await;
+
+        let totals = totals_around(&pool, now).await;
+
+        assert_eq!(totals.working, Duration::minutes(75));
+        assert_eq!(
+            totals.totals_by_kind,
+            vec![
+                TotalByKind {
+                    kind: String::from("pairing"),
+                    total: Duration::minutes(50),
+                },
+                TotalByKind {
+                    kind: String::from("lunch"),
+                    total: Duration::minutes(45),
+                },
+                TotalByKind {
+                    kind: String::from("task"),
+                    total: Duration::minutes(25),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn sorts_added_break_kinds_into_short_and_long_breaks() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        Kind::create(
+            &pool,
+            "walk",
+            Duration::minutes(10),
+            false,
+            true,
+            true,
+            false,
+            "🚶",
+        )
+        .await
+        .unwrap();
+
+        session(&pool, "walk", now, Duration::minutes(10), true).await;
+        session(&pool, "walk", now, Duration::minutes(40), true).await;
+
+        let totals = totals_around(&pool, now).await;
+
+        assert_eq!(totals.short_break, Duration::minutes(10));
+        assert_eq!(totals.long_break, Duration::minutes(40));
+        assert_eq!(totals.working, Duration::minutes(10));
+    }
+
+    #[tokio::test]
+    async fn excludes_paused_time() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        let id = session(&pool, "task", now, Duration::minutes(30), true).await;
+        sqlx::query(
+            "INSERT INTO session_pauses (session_id, start_time, end_time) VALUES (?, ?, ?)",
+        )
+        .bind(id)
+        .bind(now + Duration::minutes(5))
+        .bind(now + Duration::minutes(15))
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        assert_eq!(totals_around(&pool, now).await.task, Duration::minutes(20));
+    }
+
+    #[tokio::test]
+    async fn excludes_deleted_sessions() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        let id = session(&pool, "task", now, Duration::minutes(30), true).await;
+        Session::delete(&pool, DEFAULT_USER_ID, id, now)
+            .await
+            .unwrap();
+
+        assert_eq!(totals_around(&pool, now).await, Totals::default());
+    }
+
+    #[tokio::test]
+    async fn adds_time_by_tag() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        let review = session(&pool, "task", now, Duration::minutes(10), true).await;
+        Tag::attach(
+            &mut pool.acquire().await.unwrap(),
+            review,
+            &[String::from("code review")],
+        )
+        .await
+        .unwrap();
+
+        let ops_review = session(&pool, "task", now, Duration::minutes(5), true).await;
+        Tag::attach(
+            &mut pool.acquire().await.unwrap(),
+            ops_review,
+            &[String::from("code review"), String::from("ops")],
+        )
+        .await
+        .unwrap();
+
+        let lunch = session(&pool, "break", now, Duration::hours(1), true).await;
+        Tag::attach(
+            &mut pool.acquire().await.unwrap(),
+            lunch,
+            &[String::from("ops")],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            totals_around(&pool, now).await.totals_by_tag,
+            vec![
+                TotalByTag {
+                    tag: String::from("code review"),
+                    total: Duration::minutes(15),
+                },
+                TotalByTag {
+                    tag: String::from("ops"),
+                    total: Duration::minutes(5),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn for_range_inclusive_uses_days_in_the_given_time_zone() {
+        let pool = get_pool().await;
+
+        // 10pm on June 30th in Chicago, but already July 1st in UTC
+        Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "late night",
+            Utc.with_ymd_and_hms(2023, 7, 1, 3, 0, 0).unwrap(),
+            Duration::minutes(25),
+            Tz::America__Chicago,
+        )
+        .await
+        .unwrap();
+
+        let june_30 = NaiveDate::from_ymd_opt(2023, 6, 30).unwrap();
+
+        let chicago = Report::for_range_inclusive(
+            &pool,
+            DEFAULT_USER_ID,
+            june_30,
+            june_30,
+            Tz::America__Chicago,
+        )
+        .await
+        .unwrap();
+        assert_eq!(chicago.sessions.len(), 1);
+        assert_eq!(chicago.start.to_rfc3339(), "2023-06-30T00:00:00-05:00");
+
+        let utc = Report::for_range_inclusive(&pool, DEFAULT_USER_ID, june_30, june_30, Tz::UTC)
+            .await
+            .unwrap();
+        assert_eq!(utc.sessions.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn counts_task_outcomes() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        for outcome in [Outcome::Completed, Outcome::Completed, Outcome::Abandoned] {
+            let id = session(&pool, "task", now, Duration::minutes(25), true).await;
+            Session::set_outcome(&pool, DEFAULT_USER_ID, Some(id), outcome)
+                .await
+                .unwrap();
+        }
+        session(&pool, "task", now, Duration::minutes(25), true).await;
+
+        // breaks don't have outcomes that matter for completion
+        let id = session(&pool, "break", now, Duration::minutes(5), true).await;
+        Session::set_outcome(&pool, DEFAULT_USER_ID, Some(id), Outcome::Completed)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            totals_around(&pool, now).await.task_outcomes,
+            OutcomeCounts {
+                completed: 2,
+                partial: 0,
+                abandoned: 1,
+                unknown: 1,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn counts_interruptions() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        let task = session(&pool, "task", now, Duration::minutes(25), true).await;
+        let meeting = session(&pool, "meeting", now, Duration::minutes(25), true).await;
+
+        for (session_id, at) in [
+            (task, now),
+            (task, now + Duration::minutes(1)),
+            (meeting, now),
+            // this one is outside the report's range, so it doesn't count
+            (task, now + Duration::days(2)),
+        ] {
+            Interruption::log(
+                &mut pool.acquire().await.unwrap(),
+                session_id,
+                "slack ping",
+                None,
+                at,
+            )
+            .await
+            .unwrap();
+        }
+
+        let totals = totals_around(&pool, now).await;
+
+        assert_eq!(
+            totals.interruptions_by_kind,
+            vec![
+                InterruptionsByKind {
+                    kind: String::from("task"),
+                    count: 2,
+                },
+                InterruptionsByKind {
+                    kind: String::from("meeting"),
+                    count: 1,
+                },
+            ]
+        );
+        assert_eq!(
+            totals
+                .sessions_by_description
+                .iter()
+                .map(|total| total.interruptions)
+                .sum::<i64>(),
+            3
+        );
+    }
+
+    #[tokio::test]
+    async fn counts_extensions() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "description",
+            now,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        Session::extend_by(&pool, DEFAULT_USER_ID, Duration::minutes(5))
+            .await
+            .unwrap();
+        Session::extend_to(&pool, DEFAULT_USER_ID, now + Duration::minutes(40))
+            .await
+            .unwrap();
+
+        let totals = totals_around(&pool, now).await;
+
+        assert_eq!(
+            totals.extensions_by_kind,
+            vec![ExtensionsByKind {
+                kind: String::from("task"),
+                count: 2,
+                added: Duration::minutes(15),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn undone_extensions_do_not_count() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "description",
+            now,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        Session::extend_by(&pool, DEFAULT_USER_ID, Duration::minutes(5))
+            .await
+            .unwrap();
+        Session::extend_by(&pool, DEFAULT_USER_ID, Duration::minutes(10))
+            .await
+            .unwrap();
+        Undone::last_change(&pool, DEFAULT_USER_ID, now)
+            .await
+            .unwrap();
+
+        let totals = totals_around(&pool, now).await;
+
+        assert_eq!(
+            totals.extensions_by_kind,
+            vec![ExtensionsByKind {
+                kind: String::from("task"),
+                count: 1,
+                added: Duration::minutes(5),
+            }]
+        );
     }
 }
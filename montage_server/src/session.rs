@@ -1,9 +1,21 @@
-use super::error::{Error, Result, StartSessionError};
+use super::audit::Change;
+use super::chain::Chain;
+use super::cycle::Cycle;
+use super::error::{Error, Result, StartSessionError, UpdateSessionError};
+use super::interruption::Interruption;
 use super::kind::Kind;
-use async_graphql::{ComplexObject, SimpleObject};
-use chrono::{DateTime, Duration, Local};
+use super::note::Note;
+use super::outcome::Outcome;
+use super::pause::Pause;
+use super::planned_session::PlannedSession;
+use super::tag::Tag;
+use super::time_zone;
+use async_graphql::{ComplexObject, Context, SimpleObject};
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
 use indoc::indoc;
-use sqlx::{sqlite::SqliteRow, FromRow, Pool, Row, Sqlite};
+use serde_json::json;
+use sqlx::{sqlite::SqliteRow, FromRow, Pool, Row, Sqlite, SqliteConnection};
 
 /// A session, either currently-running or historical
 #[derive(SimpleObject, Debug, PartialEq, Eq, Clone)]
@@ -13,26 +25,48 @@ pub struct Session {
     /// of which session you're looking at in a subscription with this.
     pub id: i64,
 
-    /// What kind of session is this?
-    pub kind: Kind,
+    /// Who this session belongs to
+    #[graphql(skip)]
+    pub user_id: i64,
+
+    /// What kind of session is this? (The name of a kind; see `kindDetails` for the rest.)
+    pub kind: String,
 
     /// What's going on in this session?
     pub description: String,
 
     /// When did this session start?
-    pub start_time: DateTime<Local>,
+    pub start_time: DateTime<Utc>,
 
     /// How much time have we committed to this session?
     pub duration: Duration,
 
     /// If the session is over, when did it end?
-    pub end_time: Option<DateTime<Local>>,
+    pub end_time: Option<DateTime<Utc>>,
+
+    /// When was this session paused? Paused time doesn't count towards the session.
+    pub pauses: Vec<Pause>,
+
+    /// Tags for grouping this session with related sessions in reports
+    pub tags: Vec<String>,
+
+    /// Notes about what got done during this session
+    pub notes: Vec<Note>,
+
+    /// Things that broke focus during this session
+    pub interruptions: Vec<Interruption>,
+
+    /// The IANA time zone this session was started in
+    pub time_zone: Tz,
+
+    /// How did this session turn out?
+    pub outcome: Outcome,
 }
 
 #[ComplexObject]
 impl Session {
     /// When is/was the session projected to end?
-    async fn projected_end_time(&self) -> DateTime<Local> {
+    async fn projected_end_time(&self) -> DateTime<Utc> {
         self.get_projected_end_time()
     }
 
@@ -46,45 +80,89 @@ impl Session {
     async fn actual_duration(&self) -> Duration {
         self.get_actual_duration()
     }
-}
 
-#[derive(Debug, thiserror::Error)]
-enum DurationError {
-    #[error("failed to parse ISO8601 duration string: {0}")]
-    ParsingError(String),
+    /// Is the session paused right now?
+    async fn is_paused(&self) -> bool {
+        self.get_is_paused()
+    }
+
+    /// How much time has this session spent paused?
+    async fn paused_duration(&self) -> Duration {
+        self.get_paused_duration()
+    }
+
+    /// Where we were in the Pomodoro set when this session started (so it doesn't count itself)
+    async fn cycle(&self, context: &Context<'_>) -> Result<Cycle> {
+        Cycle::as_of(
+            context.data().map_err(Error::Context)?,
+            self.user_id,
+            self.start_time,
+        )
+        .await
+    }
+
+    /// The kind of this session, with its emoji, default duration, and so on
+    async fn kind_details(&self, context: &Context<'_>) -> Result<Kind> {
+        Kind::by_name(context.data().map_err(Error::Context)?, &self.kind).await
+    }
+
+    /// The session that will start automatically when this one ends, if any
+    async fn follow_up(&self, context: &Context<'_>) -> Result<Option<Chain>> {
+        Chain::for_session(context.data().map_err(Error::Context)?, self.id).await
+    }
+}
 
-    #[error("std duration was out of range: {0}")]
-    OutOfRangeError(chrono::OutOfRangeError),
+/// Changes to make to a session. Fields left as `None` are not changed.
+#[derive(Debug, Default)]
+pub struct SessionChanges {
+    pub description: Option<String>,
+    pub kind: Option<String>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub duration: Option<Duration>,
 }
 
 impl FromRow<'_, SqliteRow> for Session {
     fn from_row(row: &SqliteRow) -> std::result::Result<Self, sqlx::Error> {
-        let duration_str = row.try_get("duration")?;
-        let duration = iso8601::duration(duration_str)
-            .map_err(DurationError::ParsingError)
-            .map_err(|err| sqlx::Error::Decode(Box::new(err)))?;
-
         Ok(Self {
             id: row.try_get("id")?,
+            user_id: row.try_get("user_id")?,
             kind: row.try_get("kind")?,
             description: row.try_get("description")?,
             start_time: row.try_get("start_time")?,
-            duration: Duration::from_std(std::time::Duration::from(duration))
-                .map_err(DurationError::OutOfRangeError)
-                .map_err(|err| sqlx::Error::Decode(Box::new(err)))?,
+            duration: Duration::seconds(row.try_get("duration")?),
             end_time: row.try_get("end_time")?,
+            pauses: Vec::new(),
+            tags: Vec::new(),
+            notes: Vec::new(),
+            interruptions: Vec::new(),
+            time_zone: row
+                .try_get::<Option<&str>, _>("time_zone")?
+                .and_then(|name| name.parse().ok())
+                .unwrap_or_else(time_zone::local),
+            outcome: row.try_get("outcome")?,
         })
     }
 }
 
 impl Session {
-    fn get_projected_end_time(&self) -> DateTime<Local> {
-        self.start_time + self.duration
+    pub fn get_projected_end_time(&self) -> DateTime<Utc> {
+        self.start_time + self.duration + self.get_paused_duration()
+    }
+
+    pub fn get_is_paused(&self) -> bool {
+        self.end_time.is_none() && self.pauses.iter().any(|pause| pause.end_time.is_none())
+    }
+
+    fn get_paused_duration(&self) -> Duration {
+        self.pauses.iter().fold(Duration::zero(), |total, pause| {
+            total + pause.time_within_dates(self.start_time, self.end_time.unwrap_or_else(Utc::now))
+        })
     }
 
     // TODO: tests for this!
     fn get_remaining_time(&self) -> Option<Duration> {
-        let now = Local::now();
+        let now = Utc::now();
         let projected_end_time = self.get_projected_end_time();
 
         if self.end_time.is_some() {
@@ -96,50 +174,170 @@ impl Session {
         }
     }
 
-    async fn stop_all(pool: &Pool<Sqlite>, as_of: DateTime<Local>) -> Result<()> {
-        let closed_existing_sessions_receipt =
-            sqlx::query("UPDATE sessions SET end_time = ? WHERE end_time IS NULL")
-                .bind(as_of)
-                .execute(pool)
-                .await
-                .map_err(Error::Query)?;
+    async fn stop_all(
+        conn: &mut SqliteConnection,
+        user_id: i64,
+        as_of: DateTime<Utc>,
+        change: &mut Change,
+    ) -> Result<()> {
+        let running: Vec<i64> = sqlx::query_scalar(indoc! {"
+            SELECT id
+            FROM sessions
+            WHERE user_id = ?
+              AND end_time IS NULL
+              AND deleted_at IS NULL
+        "})
+        .bind(user_id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(Error::Query)?;
+
+        let mut befores = Vec::with_capacity(running.len());
+        for id in &running {
+            befores.push(Change::snapshot(conn, *id).await?);
+        }
+
+        let closed_existing_sessions_receipt = sqlx::query(indoc! {"
+            UPDATE sessions
+            SET end_time = ?
+            WHERE user_id = ?
+              AND end_time IS NULL
+              AND deleted_at IS NULL
+        "})
+        .bind(as_of)
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(Error::Query)?;
 
         tracing::info!(
             count = closed_existing_sessions_receipt.rows_affected(),
             "closed existing sessions"
         );
 
+        let closed_pauses = Pause::stop_all(conn, user_id, as_of).await?;
+        tracing::info!(count = closed_pauses, "closed existing pauses");
+
+        for (id, before) in running.into_iter().zip(befores) {
+            change.record(conn, id, before).await?;
+        }
+
         Ok(())
     }
 
     pub async fn start(
         pool: &Pool<Sqlite>,
-        kind: Kind,
+        user_id: i64,
+        kind: &str,
         description: &str,
-        start_time: DateTime<Local>,
+        start_time: DateTime<Utc>,
         duration: Duration,
+        time_zone: Tz,
     ) -> Result<Self> {
+        Self::validate_start(pool, kind, description).await?;
+
+        let mut transaction = pool.begin().await.map_err(Error::Query)?;
+        let session = Self::start_in(
+            &mut transaction,
+            user_id,
+            kind,
+            description,
+            start_time,
+            duration,
+            time_zone,
+            &[],
+            None,
+        )
+        .await?;
+        transaction.commit().await.map_err(Error::Query)?;
+
+        Ok(session)
+    }
+
+    /// Check that a session could be started, before opening a transaction to start it in.
+    pub(crate) async fn validate_start(
+        pool: &Pool<Sqlite>,
+        kind: &str,
+        description: &str,
+    ) -> Result<()> {
         if description.trim().is_empty() {
             return Err(Error::StartSessionError(
                 StartSessionError::DescriptionWasBlank,
             ));
         }
 
-        Self::stop_all(pool, start_time).await?;
+        Kind::by_name(pool, kind).await?;
 
-        let res = sqlx::query_as::<_, Session>(indoc! {"
-            INSERT INTO sessions (kind, description, start_time, duration)
-            VALUES (?, ?, ?, ?)
-            RETURNING id, kind, description, start_time, duration, end_time;
+        Ok(())
+    }
+
+    /// Start a session (with any tags) as part of a bigger transaction, stopping whatever was
+    /// running. Call `validate_start` first. If the session comes from the queue, pass the planned
+    /// session so the change says so (and `undo` can put it back.)
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn start_in(
+        conn: &mut SqliteConnection,
+        user_id: i64,
+        kind: &str,
+        description: &str,
+        start_time: DateTime<Utc>,
+        duration: Duration,
+        time_zone: Tz,
+        tags: &[String],
+        planned_session: Option<&PlannedSession>,
+    ) -> Result<Self> {
+        let mut tags = Tag::normalize(tags);
+
+        let mut arguments = json!({
+            "kind": kind,
+            "description": description,
+            "startTime": start_time,
+            "duration": duration.num_seconds(),
+            "timeZone": time_zone.name(),
+            "tags": tags,
+        });
+
+        let mutation = match planned_session {
+            Some(planned_session) => {
+                arguments["plannedSession"] = json!({
+                    "id": planned_session.id,
+                    "kind": planned_session.kind,
+                    "description": planned_session.description,
+                    "duration": planned_session
+                        .duration
+                        .map(|duration| duration.num_seconds()),
+                });
+
+                "startNext"
+            }
+            None => "start",
+        };
+
+        let mut change = Change::new(user_id, mutation, arguments);
+
+        Self::stop_all(conn, user_id, start_time, &mut change).await?;
+
+        let mut res = sqlx::query_as::<_, Session>(indoc! {"
+            INSERT INTO sessions (user_id, kind, description, start_time, duration, time_zone)
+            VALUES (?, ?, ?, ?, ?, ?)
+            RETURNING id, user_id, kind, description, start_time, duration, end_time, time_zone, outcome;
         "})
+        .bind(user_id)
         .bind(kind)
         .bind(description)
         .bind(start_time)
-        .bind(duration.to_string())
-        .fetch_one(pool)
+        .bind(duration.num_seconds())
+        .bind(time_zone.name())
+        .fetch_one(&mut *conn)
         .await
         .map_err(Error::Query)?;
 
+        Tag::attach(conn, res.id, &tags).await?;
+        tags.sort();
+        res.tags = tags;
+
+        change.record(conn, res.id, None).await?;
+
         tracing::info!(
             description = res.description,
             kind = ?res.kind,
@@ -149,8 +347,15 @@ impl Session {
         Ok(res)
     }
 
-    pub async fn extend_by(pool: &Pool<Sqlite>, duration: Duration) -> Result<Self> {
-        let res = Self::update_duration(pool, |current| current.duration + duration).await?;
+    pub async fn extend_by(pool: &Pool<Sqlite>, user_id: i64, duration: Duration) -> Result<Self> {
+        let change = Change::new(
+            user_id,
+            "extendBy",
+            json!({ "duration": duration.num_seconds() }),
+        );
+        let res =
+            Self::update_duration(pool, user_id, change, |current| current.duration + duration)
+                .await?;
 
         tracing::info!(
             description = res.description,
@@ -162,8 +367,16 @@ impl Session {
         Ok(res)
     }
 
-    pub async fn extend_to(pool: &Pool<Sqlite>, target: DateTime<Local>) -> Result<Self> {
-        let res = Self::update_duration(pool, |current| target - current.start_time).await?;
+    pub async fn extend_to(
+        pool: &Pool<Sqlite>,
+        user_id: i64,
+        target: DateTime<Utc>,
+    ) -> Result<Self> {
+        let change = Change::new(user_id, "extendTo", json!({ "target": target }));
+        let res = Self::update_duration(pool, user_id, change, |current| {
+            target - current.start_time - current.get_paused_duration()
+        })
+        .await?;
 
         tracing::info!(
             description = res.description,
@@ -175,386 +388,1996 @@ impl Session {
         Ok(res)
     }
 
-    async fn update_duration<F>(pool: &Pool<Sqlite>, get_new_duration: F) -> Result<Self>
-    where
-        F: FnOnce(&Session) -> Duration,
-    {
-        let mut current = match Self::current_session(pool).await? {
+    pub async fn stop(pool: &Pool<Sqlite>, user_id: i64, end_time: DateTime<Utc>) -> Result<Self> {
+        let mut current = match Self::current_session(pool, user_id).await? {
             Some(session) => session,
             None => return Err(Error::NoCurrentSession),
         };
 
-        let new_duration = get_new_duration(&current);
+        if end_time <= current.start_time {
+            return Err(Error::StoppedBeforeStart);
+        }
 
-        let receipt = sqlx::query("UPDATE sessions SET duration = ? WHERE id = ?")
-            .bind(new_duration.to_string())
+        let mut transaction = pool.begin().await.map_err(Error::Query)?;
+        let mut change = Change::new(user_id, "stop", json!({ "endTime": end_time }));
+        let before = Change::snapshot(&mut transaction, current.id).await?;
+
+        let receipt = sqlx::query("UPDATE sessions SET end_time = ? WHERE id = ?")
+            .bind(end_time)
             .bind(current.id)
-            .execute(pool)
+            .execute(&mut *transaction)
             .await
             .map_err(Error::Query)?;
 
         debug_assert!(receipt.rows_affected() == 1);
 
-        current.duration = new_duration;
+        Pause::stop_all(&mut transaction, user_id, end_time).await?;
+        for pause in current.pauses.iter_mut() {
+            pause.end_time.get_or_insert(end_time);
+        }
+
+        change.record(&mut transaction, current.id, before).await?;
+        transaction.commit().await.map_err(Error::Query)?;
+
+        current.end_time = Some(end_time);
+
+        tracing::info!(
+            description = current.description,
+            kind = ?current.kind,
+            "stopped session"
+        );
 
         Ok(current)
     }
 
-    pub async fn current_session(pool: &Pool<Sqlite>) -> Result<Option<Self>> {
-        sqlx::query_as::<_, Self>(indoc! {"
-            SELECT *
-            FROM sessions
-            ORDER BY start_time DESC
-            LIMIT 1
-        "})
-        .fetch_optional(pool)
-        .await
-        .map_err(Error::Query)
-    }
+    pub async fn pause(pool: &Pool<Sqlite>, user_id: i64, at: DateTime<Utc>) -> Result<Self> {
+        let mut current = match Self::current_session(pool, user_id).await? {
+            Some(session) => session,
+            None => return Err(Error::NoCurrentSession),
+        };
 
-    pub async fn for_range_inclusive(
-        pool: &Pool<Sqlite>,
-        start: DateTime<Local>,
-        end: DateTime<Local>,
-    ) -> Result<Vec<Self>> {
-        let start_date = start.format("%Y-%m-%d").to_string();
-        let end_date = (end + Duration::days(1)).format("%Y-%m-%d").to_string();
+        if current.get_is_paused() {
+            return Err(Error::AlreadyPaused);
+        }
 
-        sqlx::query_as::<_, Self>(indoc! {"
-            SELECT *
-            FROM sessions
-            WHERE (start_time >= ? AND start_time < ?)
-               OR (end_time   >= ? AND end_time   < ?)
-        "})
-        .bind(&start_date)
-        .bind(&end_date)
-        .bind(&start_date)
-        .bind(&end_date)
-        .fetch_all(pool)
-        .await
-        .map_err(Error::Query)
-    }
+        if at < current.start_time {
+            return Err(Error::PausedBeforeStart);
+        }
 
-    pub fn total_time_within_dates(
-        &self,
-        start: DateTime<Local>,
-        end: DateTime<Local>,
-    ) -> Duration {
-        debug_assert!(
-            end > start,
-            "start should always come before end in arguments. Start was {start}, end was {end}"
-        );
+        let mut transaction = pool.begin().await.map_err(Error::Query)?;
+        let mut change = Change::new(user_id, "pause", json!({ "at": at }));
+        let before = Change::snapshot(&mut transaction, current.id).await?;
+
+        let pause = Pause::start(&mut transaction, current.id, at).await?;
+        current.pauses.push(pause);
 
-        let start_final = std::cmp::max(start, self.start_time);
-        let end_final = std::cmp::min(end, self.end_time.unwrap_or_else(Local::now));
+        change.record(&mut transaction, current.id, before).await?;
+        transaction.commit().await.map_err(Error::Query)?;
 
-        debug_assert!(
-            end_final >= start_final,
-            "start should always come before end after comparison. Start was {start_final}, end was {end_final}"
+        tracing::info!(
+            description = current.description,
+            kind = ?current.kind,
+            "paused session"
         );
 
-        end_final - start_final
+        Ok(current)
     }
 
-    pub fn get_actual_duration(&self) -> Duration {
-        self.end_time.unwrap_or_else(Local::now) - self.start_time
-    }
-}
+    pub async fn resume(pool: &Pool<Sqlite>, user_id: i64, at: DateTime<Utc>) -> Result<Self> {
+        let mut current = match Self::current_session(pool, user_id).await? {
+            Some(session) => session,
+            None => return Err(Error::NoCurrentSession),
+        };
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use sqlx::sqlite::SqlitePoolOptions;
+        if !current.get_is_paused() {
+            return Err(Error::NotPaused);
+        }
 
-    async fn get_pool() -> Pool<Sqlite> {
-        let pool = SqlitePoolOptions::new().connect(":memory:").await.unwrap();
+        if current
+            .pauses
+            .iter()
+            .any(|pause| pause.end_time.is_none() && at < pause.start_time)
+        {
+            return Err(Error::ResumedBeforePause);
+        }
 
-        sqlx::migrate!("db/migrations").run(&pool).await.unwrap();
+        let mut transaction = pool.begin().await.map_err(Error::Query)?;
+        let mut change = Change::new(user_id, "resume", json!({ "at": at }));
+        let before = Change::snapshot(&mut transaction, current.id).await?;
 
-        pool
-    }
+        Pause::stop_all(&mut transaction, user_id, at).await?;
+        for pause in current.pauses.iter_mut() {
+            pause.end_time.get_or_insert(at);
+        }
 
-    #[tokio::test]
-    async fn current_session_gets_nothing_in_empty_database() {
-        let pool = get_pool().await;
+        change.record(&mut transaction, current.id, before).await?;
+        transaction.commit().await.map_err(Error::Query)?;
 
-        let current = Session::current_session(&pool).await.unwrap();
+        tracing::info!(
+            description = current.description,
+            kind = ?current.kind,
+            "resumed session"
+        );
 
-        assert_eq!(current, None);
+        Ok(current)
     }
 
-    #[tokio::test]
-    async fn current_session_gets_a_started_session() {
-        let pool = get_pool().await;
-        let now = Local::now();
+    pub async fn update(
+        pool: &Pool<Sqlite>,
+        user_id: i64,
+        id: i64,
+        changes: SessionChanges,
+    ) -> Result<Self> {
+        let mut session = match Self::by_id(pool, user_id, id).await? {
+            Some(session) => session,
+            None => return Err(Error::NoSuchSession(id)),
+        };
 
-        let new_session = Session::start(&pool, Kind::Task, "foo", now, Duration::minutes(25))
-            .await
-            .unwrap();
-        let current_session = Session::current_session(&pool).await.unwrap();
+        let mut change = Change::new(
+            user_id,
+            "updateSession",
+            json!({
+                "id": id,
+                "description": changes.description,
+                "kind": changes.kind,
+                "startTime": changes.start_time,
+                "endTime": changes.end_time,
+                "duration": changes.duration.map(|duration| duration.num_seconds()),
+            }),
+        );
 
-        assert_eq!(current_session, Some(new_session));
-    }
+        if let Some(description) = changes.description {
+            session.description = description;
+        }
+        if let Some(kind) = changes.kind {
+            Kind::by_name(pool, &kind).await?;
+            session.kind = kind;
+        }
+        if let Some(start_time) = changes.start_time {
+            session.start_time = start_time;
+        }
+        if let Some(end_time) = changes.end_time {
+            session.end_time = Some(end_time);
+        }
+        if let Some(duration) = changes.duration {
+            session.duration = duration;
+        }
 
-    #[tokio::test]
-    async fn current_session_gets_the_most_recent_session() {
-        let pool = get_pool().await;
-        let now = Local::now();
-        let next = now + Duration::minutes(5);
+        session.validate_update(pool).await?;
 
-        Session::start(&pool, Kind::Task, "foo", now, Duration::minutes(25))
-            .await
-            .unwrap();
+        let mut transaction = pool.begin().await.map_err(Error::Query)?;
+        let before = Change::snapshot(&mut transaction, session.id).await?;
 
-        let session_2 = Session::start(&pool, Kind::Task, "foo", next, Duration::minutes(25))
-            .await
-            .unwrap();
+        let receipt = sqlx::query(indoc! {"
+            UPDATE sessions
+            SET description = ?, kind = ?, start_time = ?, end_time = ?, duration = ?
+            WHERE id = ?
+        "})
+        .bind(&session.description)
+        .bind(&session.kind)
+        .bind(session.start_time)
+        .bind(session.end_time)
+        .bind(session.duration.num_seconds())
+        .bind(session.id)
+        .execute(&mut *transaction)
+        .await
+        .map_err(Error::Query)?;
+
+        debug_assert!(receipt.rows_affected() == 1);
 
-        let current_session = Session::current_session(&pool).await.unwrap();
+        change.record(&mut transaction, session.id, before).await?;
+        transaction.commit().await.map_err(Error::Query)?;
 
-        assert_eq!(current_session.map(|s| s.id), Some(session_2.id))
+        tracing::info!(
+            id = session.id,
+            description = session.description,
+            kind = ?session.kind,
+            "updated session"
+        );
+
+        Ok(session)
     }
 
-    #[tokio::test]
-    async fn starting_a_new_session_closes_existing_sessions() {
-        let pool = get_pool().await;
-        let now = Local::now();
-        let duration = Duration::minutes(25);
-        let next = now + duration;
+    async fn validate_update(&self, pool: &Pool<Sqlite>) -> Result<()> {
+        if self.description.trim().is_empty() {
+            return Err(Error::UpdateSessionError(
+                UpdateSessionError::DescriptionWasBlank,
+            ));
+        }
 
-        let first_session = Session::start(&pool, Kind::Task, "foo", now, duration)
-            .await
-            .unwrap();
+        if self.duration <= Duration::zero() {
+            return Err(Error::UpdateSessionError(
+                UpdateSessionError::DurationWasNotPositive,
+            ));
+        }
 
-        Session::start(&pool, Kind::Task, "foo", next, duration)
-            .await
-            .unwrap();
+        if let Some(end_time) = self.end_time {
+            if end_time <= self.start_time {
+                return Err(Error::UpdateSessionError(
+                    UpdateSessionError::EndedBeforeStart,
+                ));
+            }
+        }
 
-        let first_session_refetched =
-            sqlx::query_as::<_, Session>("SELECT * FROM sessions WHERE id = ?")
-                .bind(first_session.id)
-                .fetch_one(&pool)
-                .await
-                .unwrap();
+        let overlapping: Option<(i64,)> = sqlx::query_as(indoc! {"
+            SELECT id
+            FROM sessions
+            WHERE id != ?
+              AND user_id = ?
+              AND deleted_at IS NULL
+              AND (? IS NULL OR start_time < ?)
+              AND (end_time IS NULL OR end_time > ?)
+            ORDER BY start_time ASC
+            LIMIT 1
+        "})
+        .bind(self.id)
+        .bind(self.user_id)
+        .bind(self.end_time)
+        .bind(self.end_time)
+        .bind(self.start_time)
+        .fetch_optional(pool)
+        .await
+        .map_err(Error::Query)?;
 
-        assert_ne!(first_session_refetched.end_time, first_session.end_time);
-        assert_eq!(first_session_refetched.end_time, Some(next));
+        match overlapping {
+            Some((other,)) => Err(Error::UpdateSessionError(
+                UpdateSessionError::OverlapsSession(other),
+            )),
+            None => Ok(()),
+        }
     }
 
-    #[tokio::test]
-    async fn cannot_start_a_session_with_a_blank_description() {
-        let pool = get_pool().await;
-        let now = Local::now();
-        let duration = Duration::minutes(25);
+    /// Delete a session. The session stays in the database (so it can be restored) until it's
+    /// purged.
+    pub async fn delete(
+        pool: &Pool<Sqlite>,
+        user_id: i64,
+        id: i64,
+        deleted_at: DateTime<Utc>,
+    ) -> Result<Self> {
+        let session = match Self::by_id(pool, user_id, id).await? {
+            Some(session) => session,
+            None => return Err(Error::NoSuchSession(id)),
+        };
 
-        let resp = Session::start(&pool, Kind::Task, "", now, duration)
+        let mut transaction = pool.begin().await.map_err(Error::Query)?;
+        let mut change = Change::new(user_id, "deleteSession", json!({ "id": id }));
+        let before = Change::snapshot(&mut transaction, id).await?;
+
+        let receipt = sqlx::query("UPDATE sessions SET deleted_at = ? WHERE id = ?")
+            .bind(deleted_at)
+            .bind(id)
+            .execute(&mut *transaction)
             .await
-            .unwrap_err();
+            .map_err(Error::Query)?;
 
-        assert_eq!(
-            resp.to_string(),
-            "validation error starting a session: description cannot be blank"
-        );
-    }
+        debug_assert!(receipt.rows_affected() == 1);
 
-    #[tokio::test]
-    async fn you_cant_extend_a_session_that_doesnt_exist() {
-        let pool = get_pool().await;
+        change.record(&mut transaction, id, before).await?;
+        transaction.commit().await.map_err(Error::Query)?;
 
-        match Session::extend_by(&pool, Duration::minutes(5)).await {
-            Err(Error::NoCurrentSession) => (),
-            other => panic!("expected NoCurrentSession, got {other:?}"),
-        }
+        tracing::info!(
+            id = session.id,
+            description = session.description,
+            "deleted session"
+        );
+
+        Ok(session)
     }
 
-    #[tokio::test]
-    async fn extending_a_session_changes_the_duration() {
-        let pool = get_pool().await;
-        let now = Local::now();
-        let duration = Duration::minutes(5);
-        let extension = Duration::minutes(5);
+    /// Bring back a deleted session. If no ID is given, restore the most recently deleted one.
+    pub async fn restore(pool: &Pool<Sqlite>, user_id: i64, id: Option<i64>) -> Result<Self> {
+        let mut sessions: Vec<Self> = sqlx::query_as::<_, Self>(indoc! {"
+            SELECT *
+            FROM sessions
+            WHERE user_id = ?
+              AND deleted_at IS NOT NULL
+              AND (? IS NULL OR id = ?)
+            ORDER BY deleted_at DESC
+            LIMIT 1
+        "})
+        .bind(user_id)
+        .bind(id)
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(Error::Query)?
+        .into_iter()
+        .collect();
 
-        let original_session = Session::start(&pool, Kind::Task, "foo", now, duration)
-            .await
-            .unwrap();
+        Self::load_related(pool, &mut sessions).await?;
 
-        let extended_session = Session::extend_by(&pool, extension).await.unwrap();
+        let session = match (sessions.pop(), id) {
+            (Some(session), _) => session,
+            (None, Some(id)) => return Err(Error::NoSuchSession(id)),
+            (None, None) => return Err(Error::NothingToRestore),
+        };
 
-        assert_eq!(
-            extended_session.duration,
-            original_session.duration + extension
-        )
-    }
+        session.validate_update(pool).await?;
 
-    #[tokio::test]
-    async fn extending_a_session_to_a_date_sets_the_duration() {
-        let pool = get_pool().await;
-        let now = Local::now();
-        let duration = Duration::minutes(5);
-        let extension = Duration::minutes(5);
+        let mut transaction = pool.begin().await.map_err(Error::Query)?;
+        let mut change = Change::new(user_id, "restoreSession", json!({ "id": id }));
+        let before = Change::snapshot(&mut transaction, session.id).await?;
 
-        let original_session = Session::start(&pool, Kind::Task, "foo", now, duration)
+        let receipt = sqlx::query("UPDATE sessions SET deleted_at = NULL WHERE id = ?")
+            .bind(session.id)
+            .execute(&mut *transaction)
             .await
-            .unwrap();
+            .map_err(Error::Query)?;
 
-        let extended_session = Session::extend_to(&pool, now + duration + extension)
-            .await
-            .unwrap();
+        debug_assert!(receipt.rows_affected() == 1);
 
-        assert_eq!(
-            extended_session.duration,
-            original_session.duration + extension
-        )
+        change.record(&mut transaction, session.id, before).await?;
+        transaction.commit().await.map_err(Error::Query)?;
+
+        tracing::info!(
+            id = session.id,
+            description = session.description,
+            "restored session"
+        );
+
+        Ok(session)
     }
 
-    #[tokio::test]
-    async fn for_date_gets_finished_session() {
+    /// Permanently remove sessions that were deleted before the cutoff.
+    pub async fn purge_deleted(pool: &Pool<Sqlite>, cutoff: DateTime<Utc>) -> Result<u64> {
+        let mut transaction = pool.begin().await.map_err(Error::Query)?;
+
+        for table in [
+            "session_pauses",
+            "session_tags",
+            "session_notes",
+            "interruptions",
+            "session_chains",
+        ] {
+            sqlx::query(&format!(
+                indoc! {"
+                    DELETE FROM {table}
+                    WHERE session_id IN (
+                        SELECT id FROM sessions WHERE deleted_at IS NOT NULL AND deleted_at < ?
+                    )
+                "},
+                table = table
+            ))
+            .bind(cutoff)
+            .execute(&mut *transaction)
+            .await
+            .map_err(Error::Query)?;
+        }
+
+        let receipt =
+            sqlx::query("DELETE FROM sessions WHERE deleted_at IS NOT NULL AND deleted_at < ?")
+                .bind(cutoff)
+                .execute(&mut *transaction)
+                .await
+                .map_err(Error::Query)?;
+
+        transaction.commit().await.map_err(Error::Query)?;
+
+        tracing::info!(count = receipt.rows_affected(), "purged deleted sessions");
+
+        Ok(receipt.rows_affected())
+    }
+
+    /// Attach tags to this session.
+    pub async fn tag(&mut self, pool: &Pool<Sqlite>, tags: &[String]) -> Result<()> {
+        let tags = Tag::normalize(tags);
+
+        let mut transaction = pool.begin().await.map_err(Error::Query)?;
+        let mut change = Change::new(
+            self.user_id,
+            "tag",
+            json!({ "sessionId": self.id, "tags": tags }),
+        );
+        let before = Change::snapshot(&mut transaction, self.id).await?;
+
+        Tag::attach(&mut transaction, self.id, &tags).await?;
+
+        change.record(&mut transaction, self.id, before).await?;
+        transaction.commit().await.map_err(Error::Query)?;
+
+        for tag in tags {
+            if !self.tags.contains(&tag) {
+                self.tags.push(tag);
+            }
+        }
+        self.tags.sort();
+
+        Ok(())
+    }
+
+    /// Write down a note about a session. If no ID is given, the note goes on the current session.
+    pub async fn add_note(
+        pool: &Pool<Sqlite>,
+        user_id: i64,
+        id: Option<i64>,
+        text: &str,
+        created_at: DateTime<Utc>,
+    ) -> Result<Self> {
+        if text.trim().is_empty() {
+            return Err(Error::NoteWasBlank);
+        }
+
+        let mut session = match id {
+            Some(id) => Self::by_id(pool, user_id, id)
+                .await?
+                .ok_or(Error::NoSuchSession(id))?,
+            None => Self::current_session(pool, user_id)
+                .await?
+                .ok_or(Error::NoCurrentSession)?,
+        };
+
+        let mut transaction = pool.begin().await.map_err(Error::Query)?;
+        let mut change = Change::new(
+            user_id,
+            "addNote",
+            json!({ "sessionId": id, "text": text.trim() }),
+        );
+        let before = Change::snapshot(&mut transaction, session.id).await?;
+
+        let note = Note::add(&mut transaction, session.id, text.trim(), created_at).await?;
+
+        change.record(&mut transaction, session.id, before).await?;
+        transaction.commit().await.map_err(Error::Query)?;
+
+        session.notes.push(note);
+
+        tracing::info!(id = session.id, "added note to session");
+
+        Ok(session)
+    }
+
+    /// Fill in the time zone for sessions that were started before we kept track of it.
+    pub async fn record_missing_time_zones(pool: &Pool<Sqlite>, time_zone: Tz) -> Result<u64> {
+        let receipt = sqlx::query("UPDATE sessions SET time_zone = ? WHERE time_zone IS NULL")
+            .bind(time_zone.name())
+            .execute(pool)
+            .await
+            .map_err(Error::Query)?;
+
+        Ok(receipt.rows_affected())
+    }
+
+    /// Record how a session turned out. If no ID is given, the outcome goes on the most recently
+    /// started session (the current one, if there is one.)
+    pub async fn set_outcome(
+        pool: &Pool<Sqlite>,
+        user_id: i64,
+        id: Option<i64>,
+        outcome: Outcome,
+    ) -> Result<Self> {
+        let mut session = match id {
+            Some(id) => Self::by_id(pool, user_id, id)
+                .await?
+                .ok_or(Error::NoSuchSession(id))?,
+            None => Self::most_recent(pool, user_id)
+                .await?
+                .ok_or(Error::NoSessions)?,
+        };
+
+        let mut transaction = pool.begin().await.map_err(Error::Query)?;
+        let mut change = Change::new(
+            user_id,
+            "setOutcome",
+            json!({ "sessionId": id, "outcome": format!("{outcome:?}").to_lowercase() }),
+        );
+        let before = Change::snapshot(&mut transaction, session.id).await?;
+
+        let receipt = sqlx::query("UPDATE sessions SET outcome = ? WHERE id = ?")
+            .bind(outcome)
+            .bind(session.id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(Error::Query)?;
+
+        debug_assert!(receipt.rows_affected() == 1);
+
+        change.record(&mut transaction, session.id, before).await?;
+        transaction.commit().await.map_err(Error::Query)?;
+
+        session.outcome = outcome;
+
+        tracing::info!(id = session.id, outcome = ?outcome, "set session outcome");
+
+        Ok(session)
+    }
+
+    /// Log something that interrupted the current session.
+    pub async fn log_interruption(
+        pool: &Pool<Sqlite>,
+        user_id: i64,
+        source: &str,
+        note: Option<&str>,
+        created_at: DateTime<Utc>,
+    ) -> Result<Self> {
+        if source.trim().is_empty() {
+            return Err(Error::InterruptionSourceWasBlank);
+        }
+
+        let mut session = Self::current_session(pool, user_id)
+            .await?
+            .ok_or(Error::NoCurrentSession)?;
+
+        let note = note.map(str::trim).filter(|note| !note.is_empty());
+
+        let mut transaction = pool.begin().await.map_err(Error::Query)?;
+        let mut change = Change::new(
+            user_id,
+            "logInterruption",
+            json!({ "source": source.trim(), "note": note }),
+        );
+        let before = Change::snapshot(&mut transaction, session.id).await?;
+
+        let interruption = Interruption::log(
+            &mut transaction,
+            session.id,
+            source.trim(),
+            note,
+            created_at,
+        )
+        .await?;
+
+        change.record(&mut transaction, session.id, before).await?;
+        transaction.commit().await.map_err(Error::Query)?;
+
+        session.interruptions.push(interruption);
+
+        tracing::info!(id = session.id, source = source, "logged interruption");
+
+        Ok(session)
+    }
+
+    /// Load the pauses, tags, notes, and interruptions that live in other tables.
+    async fn load_related(pool: &Pool<Sqlite>, sessions: &mut [Self]) -> Result<()> {
+        let ids: Vec<i64> = sessions.iter().map(|session| session.id).collect();
+
+        for interruption in Interruption::for_sessions(pool, &ids).await? {
+            if let Some(session) = sessions
+                .iter_mut()
+                .find(|session| session.id == interruption.session_id)
+            {
+                session.interruptions.push(interruption);
+            }
+        }
+
+        for note in Note::for_sessions(pool, &ids).await? {
+            if let Some(session) = sessions
+                .iter_mut()
+                .find(|session| session.id == note.session_id)
+            {
+                session.notes.push(note);
+            }
+        }
+
+        for (session_id, tag) in Tag::for_sessions(pool, &ids).await? {
+            if let Some(session) = sessions.iter_mut().find(|session| session.id == session_id) {
+                session.tags.push(tag);
+            }
+        }
+
+        for pause in Pause::for_sessions(pool, &ids).await? {
+            if let Some(session) = sessions
+                .iter_mut()
+                .find(|session| session.id == pause.session_id)
+            {
+                session.pauses.push(pause);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn update_duration<F>(
+        pool: &Pool<Sqlite>,
+        user_id: i64,
+        mut change: Change,
+        get_new_duration: F,
+    ) -> Result<Self>
+    where
+        F: FnOnce(&Session) -> Duration,
+    {
+        let mut current = match Self::current_session(pool, user_id).await? {
+            Some(session) => session,
+            None => return Err(Error::NoCurrentSession),
+        };
+
+        let new_duration = get_new_duration(&current);
+
+        let mut transaction = pool.begin().await.map_err(Error::Query)?;
+        let before = Change::snapshot(&mut transaction, current.id).await?;
+
+        let receipt = sqlx::query("UPDATE sessions SET duration = ? WHERE id = ?")
+            .bind(new_duration.num_seconds())
+            .bind(current.id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(Error::Query)?;
+
+        debug_assert!(receipt.rows_affected() == 1);
+
+        change.record(&mut transaction, current.id, before).await?;
+        transaction.commit().await.map_err(Error::Query)?;
+
+        current.duration = new_duration;
+
+        Ok(current)
+    }
+
+    pub async fn current_session(pool: &Pool<Sqlite>, user_id: i64) -> Result<Option<Self>> {
+        let mut sessions: Vec<Self> = sqlx::query_as::<_, Self>(indoc! {"
+            SELECT *
+            FROM sessions
+            WHERE user_id = ?
+              AND end_time IS NULL
+              AND deleted_at IS NULL
+            ORDER BY start_time DESC
+            LIMIT 1
+        "})
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(Error::Query)?
+        .into_iter()
+        .collect();
+
+        Self::load_related(pool, &mut sessions).await?;
+
+        Ok(sessions.pop())
+    }
+
+    /// Get the session that was started most recently, whether or not it's still running.
+    pub async fn most_recent(pool: &Pool<Sqlite>, user_id: i64) -> Result<Option<Self>> {
+        let mut sessions: Vec<Self> = sqlx::query_as::<_, Self>(indoc! {"
+            SELECT *
+            FROM sessions
+            WHERE user_id = ?
+              AND deleted_at IS NULL
+            ORDER BY start_time DESC
+            LIMIT 1
+        "})
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(Error::Query)?
+        .into_iter()
+        .collect();
+
+        Self::load_related(pool, &mut sessions).await?;
+
+        Ok(sessions.pop())
+    }
+
+    /// Get one of a user's sessions. Other users' sessions are treated as if they don't exist.
+    pub async fn by_id(pool: &Pool<Sqlite>, user_id: i64, id: i64) -> Result<Option<Self>> {
+        let mut sessions: Vec<Self> = sqlx::query_as::<_, Self>(
+            "SELECT * FROM sessions WHERE id = ? AND user_id = ? AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(Error::Query)?
+        .into_iter()
+        .collect();
+
+        Self::load_related(pool, &mut sessions).await?;
+
+        Ok(sessions.pop())
+    }
+
+    /// Get the sessions that started or ended at or after `start` but before `end`.
+    pub async fn for_range(
+        pool: &Pool<Sqlite>,
+        user_id: i64,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<Self>> {
+        let mut sessions = sqlx::query_as::<_, Self>(indoc! {"
+            SELECT *
+            FROM sessions
+            WHERE user_id = ?
+              AND deleted_at IS NULL
+              AND ((start_time >= ? AND start_time < ?)
+                OR (end_time   >= ? AND end_time   < ?))
+        "})
+        .bind(user_id)
+        .bind(start)
+        .bind(end)
+        .bind(start)
+        .bind(end)
+        .fetch_all(pool)
+        .await
+        .map_err(Error::Query)?;
+
+        Self::load_related(pool, &mut sessions).await?;
+
+        Ok(sessions)
+    }
+
+    pub fn get_actual_duration(&self) -> Duration {
+        self.end_time.unwrap_or_else(Utc::now) - self.start_time - self.get_paused_duration()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::audit::AuditEvent;
+    use crate::user::DEFAULT_USER_ID;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn get_pool() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new().connect(":memory:").await.unwrap();
+
+        sqlx::migrate!("db/migrations").run(&pool).await.unwrap();
+
+        pool
+    }
+
+    /// Get the sessions that started or ended on the (UTC) day of the given time
+    async fn on_day(pool: &Pool<Sqlite>, time: DateTime<Utc>) -> Vec<Session> {
+        let start = time_zone::start_of_day(time.date_naive(), Tz::UTC);
+
+        Session::for_range(pool, DEFAULT_USER_ID, start, start + Duration::days(1))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn current_session_gets_nothing_in_empty_database() {
         let pool = get_pool().await;
-        let now = Local::now();
+
+        let current = Session::current_session(&pool, DEFAULT_USER_ID)
+            .await
+            .unwrap();
+
+        assert_eq!(current, None);
+    }
+
+    #[tokio::test]
+    async fn current_session_gets_a_started_session() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        let new_session = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        let current_session = Session::current_session(&pool, DEFAULT_USER_ID)
+            .await
+            .unwrap();
+
+        assert_eq!(current_session, Some(new_session));
+    }
+
+    #[tokio::test]
+    async fn current_session_gets_the_most_recent_session() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+        let next = now + Duration::minutes(5);
+
+        Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        let session_2 = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            next,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        let current_session = Session::current_session(&pool, DEFAULT_USER_ID)
+            .await
+            .unwrap();
+
+        assert_eq!(current_session.map(|s| s.id), Some(session_2.id))
+    }
+
+    #[tokio::test]
+    async fn sessions_belong_to_whoever_started_them() {
+        let pool = get_pool().await;
+        let alice = crate::user::User::create(&pool, "alice", Utc::now())
+            .await
+            .unwrap();
+        let now = Utc::now();
+
+        let default_session = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "mine",
+            now,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        // starting a session only ends the starting user's current session
+        let alice_session = Session::start(
+            &pool,
+            alice.id,
+            "task",
+            "alice's",
+            now + Duration::minutes(5),
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            Session::current_session(&pool, DEFAULT_USER_ID)
+                .await
+                .unwrap(),
+            Some(default_session)
+        );
+        assert_eq!(
+            Session::current_session(&pool, alice.id).await.unwrap(),
+            Some(alice_session.clone())
+        );
+        assert_eq!(
+            Session::by_id(&pool, DEFAULT_USER_ID, alice_session.id)
+                .await
+                .unwrap(),
+            None
+        );
+        assert!(matches!(
+            Session::delete(&pool, DEFAULT_USER_ID, alice_session.id, Utc::now()).await,
+            Err(Error::NoSuchSession(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn current_session_gets_nothing_after_stopping() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        Session::stop(&pool, DEFAULT_USER_ID, now + Duration::minutes(5))
+            .await
+            .unwrap();
+
+        let current = Session::current_session(&pool, DEFAULT_USER_ID)
+            .await
+            .unwrap();
+
+        assert_eq!(current, None);
+    }
+
+    #[tokio::test]
+    async fn stopping_a_session_sets_the_end_time() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+        let end = now + Duration::minutes(5);
+
+        let session = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        let stopped = Session::stop(&pool, DEFAULT_USER_ID, end).await.unwrap();
+
+        assert_eq!(stopped.id, session.id);
+        assert_eq!(stopped.end_time, Some(end));
+    }
+
+    #[tokio::test]
+    async fn you_cant_stop_a_session_that_doesnt_exist() {
+        let pool = get_pool().await;
+
+        match Session::stop(&pool, DEFAULT_USER_ID, Utc::now()).await {
+            Err(Error::NoCurrentSession) => (),
+            other => panic!("expected NoCurrentSession, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn starting_with_tags_tags_the_session_and_records_them() {
+        let pool = get_pool().await;
+
+        let mut transaction = pool.begin().await.unwrap();
+        let session = Session::start_in(
+            &mut transaction,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            Utc::now(),
+            Duration::minutes(25),
+            Tz::UTC,
+            &[String::from("writing"), String::from("docs")],
+            None,
+        )
+        .await
+        .unwrap();
+        transaction.commit().await.unwrap();
+
+        assert_eq!(session.tags, vec!["docs", "writing"]);
+        assert_eq!(
+            Session::current_session(&pool, DEFAULT_USER_ID)
+                .await
+                .unwrap()
+                .unwrap()
+                .tags,
+            vec!["docs", "writing"]
+        );
+
+        let events = AuditEvent::history(&pool, DEFAULT_USER_ID, session.id)
+            .await
+            .unwrap();
+        let arguments: serde_json::Value = serde_json::from_str(&events[0].arguments).unwrap();
+        assert_eq!(arguments["tags"], json!(["writing", "docs"]));
+    }
+
+    #[tokio::test]
+    async fn you_cant_stop_a_session_before_it_starts() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        match Session::stop(&pool, DEFAULT_USER_ID, now - Duration::minutes(1)).await {
+            Err(Error::StoppedBeforeStart) => (),
+            other => panic!("expected StoppedBeforeStart, got {other:?}"),
+        }
+
+        assert_eq!(
+            Session::current_session(&pool, DEFAULT_USER_ID)
+                .await
+                .unwrap()
+                .unwrap()
+                .end_time,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn starting_a_new_session_closes_existing_sessions() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+        let duration = Duration::minutes(25);
+        let next = now + duration;
+
+        let first_session = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            duration,
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            next,
+            duration,
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        let first_session_refetched =
+            sqlx::query_as::<_, Session>("SELECT * FROM sessions WHERE id = ?")
+                .bind(first_session.id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+
+        assert_ne!(first_session_refetched.end_time, first_session.end_time);
+        assert_eq!(first_session_refetched.end_time, Some(next));
+    }
+
+    #[tokio::test]
+    async fn cannot_start_a_session_with_a_blank_description() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+        let duration = Duration::minutes(25);
+
+        let resp = Session::start(&pool, DEFAULT_USER_ID, "task", "", now, duration, Tz::UTC)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            resp.to_string(),
+            "validation error starting a session: description cannot be blank"
+        );
+    }
+
+    #[tokio::test]
+    async fn you_cant_extend_a_session_that_doesnt_exist() {
+        let pool = get_pool().await;
+
+        match Session::extend_by(&pool, DEFAULT_USER_ID, Duration::minutes(5)).await {
+            Err(Error::NoCurrentSession) => (),
+            other => panic!("expected NoCurrentSession, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn extending_a_session_changes_the_duration() {
+        let pool = get_pool().await;
+        let now = Utc::now();
         let duration = Duration::minutes(5);
+        let extension = Duration::minutes(5);
+
+        let original_session = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            duration,
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        let extended_session = Session::extend_by(&pool, DEFAULT_USER_ID, extension)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            extended_session.duration,
+            original_session.duration + extension
+        )
+    }
+
+    #[tokio::test]
+    async fn extending_a_session_to_a_date_sets_the_duration() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+        let duration = Duration::minutes(5);
+        let extension = Duration::minutes(5);
+
+        let original_session = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            duration,
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        let extended_session =
+            Session::extend_to(&pool, DEFAULT_USER_ID, now + duration + extension)
+                .await
+                .unwrap();
+
+        assert_eq!(
+            extended_session.duration,
+            original_session.duration + extension
+        )
+    }
+
+    #[tokio::test]
+    async fn pausing_a_session_marks_it_paused() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        let paused = Session::pause(&pool, DEFAULT_USER_ID, now).await.unwrap();
+        assert!(paused.get_is_paused());
+
+        let current = Session::current_session(&pool, DEFAULT_USER_ID)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(current.get_is_paused());
+    }
+
+    #[tokio::test]
+    async fn you_cant_pause_a_paused_session() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        Session::pause(&pool, DEFAULT_USER_ID, now).await.unwrap();
+
+        match Session::pause(&pool, DEFAULT_USER_ID, now).await {
+            Err(Error::AlreadyPaused) => (),
+            other => panic!("expected AlreadyPaused, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn you_cant_resume_a_session_that_isnt_paused() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        match Session::resume(&pool, DEFAULT_USER_ID, now).await {
+            Err(Error::NotPaused) => (),
+            other => panic!("expected NotPaused, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn you_cant_pause_a_session_before_it_starts() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        match Session::pause(&pool, DEFAULT_USER_ID, now - Duration::minutes(1)).await {
+            Err(Error::PausedBeforeStart) => (),
+            other => panic!("expected PausedBeforeStart, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn you_cant_resume_a_session_before_it_was_paused() {
+        let pool = get_pool().await;
+        let start = Utc::now() - Duration::minutes(20);
+
+        Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            start,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        Session::pause(&pool, DEFAULT_USER_ID, start + Duration::minutes(10))
+            .await
+            .unwrap();
+
+        match Session::resume(&pool, DEFAULT_USER_ID, start + Duration::minutes(5)).await {
+            Err(Error::ResumedBeforePause) => (),
+            other => panic!("expected ResumedBeforePause, got {other:?}"),
+        }
+
+        assert!(Session::current_session(&pool, DEFAULT_USER_ID)
+            .await
+            .unwrap()
+            .unwrap()
+            .get_is_paused());
+    }
+
+    #[tokio::test]
+    async fn paused_time_pushes_back_the_projected_end_time() {
+        let pool = get_pool().await;
+        let start = Utc::now() - Duration::minutes(20);
+        let duration = Duration::minutes(25);
+
+        Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            start,
+            duration,
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        Session::pause(&pool, DEFAULT_USER_ID, start + Duration::minutes(5))
+            .await
+            .unwrap();
+        let resumed = Session::resume(&pool, DEFAULT_USER_ID, start + Duration::minutes(15))
+            .await
+            .unwrap();
+
+        assert!(!resumed.get_is_paused());
+        assert_eq!(resumed.get_paused_duration(), Duration::minutes(10));
+        assert_eq!(
+            resumed.get_projected_end_time(),
+            start + duration + Duration::minutes(10)
+        );
+    }
 
-        let mut session = Session::start(&pool, Kind::Task, "foo", now, duration)
+    #[tokio::test]
+    async fn stopping_a_paused_session_ends_the_pause() {
+        let pool = get_pool().await;
+        let start = Utc::now() - Duration::minutes(20);
+
+        Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            start,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        Session::pause(&pool, DEFAULT_USER_ID, start + Duration::minutes(5))
+            .await
+            .unwrap();
+        let stopped = Session::stop(&pool, DEFAULT_USER_ID, start + Duration::minutes(10))
             .await
             .unwrap();
-        session.end_time = Some(now + duration);
 
-        Session::stop_all(&pool, now + duration).await.unwrap();
+        assert!(!stopped.get_is_paused());
+        assert_eq!(stopped.get_actual_duration(), Duration::minutes(5));
+    }
 
-        assert_eq!(
-            Session::for_range_inclusive(&pool, now, now).await.unwrap(),
-            vec![session]
+    #[tokio::test]
+    async fn updating_a_session_changes_fields() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        let session = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        let updated = Session::update(
+            &pool,
+            DEFAULT_USER_ID,
+            session.id,
+            SessionChanges {
+                description: Some(String::from("bar")),
+                kind: Some(String::from("meeting")),
+                ..SessionChanges::default()
+            },
         )
+        .await
+        .unwrap();
+
+        assert_eq!(updated.description, "bar");
+        assert_eq!(updated.kind, "meeting");
+        assert_eq!(
+            Session::by_id(&pool, DEFAULT_USER_ID, session.id)
+                .await
+                .unwrap(),
+            Some(updated)
+        );
     }
 
     #[tokio::test]
-    async fn for_date_gets_current_session() {
+    async fn you_cant_update_a_session_that_doesnt_exist() {
         let pool = get_pool().await;
-        let now = Local::now();
-        let duration = Duration::minutes(5);
 
-        let session = Session::start(&pool, Kind::Task, "foo", now, duration)
-            .await
-            .unwrap();
+        match Session::update(&pool, DEFAULT_USER_ID, 1, SessionChanges::default()).await {
+            Err(Error::NoSuchSession(1)) => (),
+            other => panic!("expected NoSuchSession, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn you_cant_update_a_session_to_end_before_it_starts() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        let session = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        let resp = Session::update(
+            &pool,
+            DEFAULT_USER_ID,
+            session.id,
+            SessionChanges {
+                end_time: Some(now - Duration::minutes(5)),
+                ..SessionChanges::default()
+            },
+        )
+        .await
+        .unwrap_err();
 
         assert_eq!(
-            Session::for_range_inclusive(&pool, now, now).await.unwrap(),
-            vec![session]
+            resp.to_string(),
+            "validation error updating a session: session cannot end before it starts"
+        );
+    }
+
+    #[tokio::test]
+    async fn you_cant_update_a_session_to_overlap_another() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+        let duration = Duration::minutes(25);
+
+        let first = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            duration,
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        let second = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "bar",
+            now + duration,
+            duration,
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        match Session::update(
+            &pool,
+            DEFAULT_USER_ID,
+            first.id,
+            SessionChanges {
+                end_time: Some(now + duration + Duration::minutes(5)),
+                ..SessionChanges::default()
+            },
         )
+        .await
+        {
+            Err(Error::UpdateSessionError(UpdateSessionError::OverlapsSession(id))) => {
+                assert_eq!(id, second.id)
+            }
+            other => panic!("expected OverlapsSession, got {other:?}"),
+        }
     }
 
     #[tokio::test]
-    async fn for_date_leaves_out_sessions_before_date() {
+    async fn updating_a_session_can_move_it_up_to_its_neighbors() {
         let pool = get_pool().await;
-        let now = Local::now();
-        let duration = Duration::minutes(5);
+        let now = Utc::now();
+        let duration = Duration::minutes(25);
+
+        let first = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            duration,
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "bar",
+            now + duration,
+            duration,
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        let updated = Session::update(
+            &pool,
+            DEFAULT_USER_ID,
+            first.id,
+            SessionChanges {
+                start_time: Some(now - Duration::minutes(5)),
+                ..SessionChanges::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(updated.start_time, now - Duration::minutes(5));
+    }
 
-        Session::start(&pool, Kind::Task, "foo", now, duration)
+    #[tokio::test]
+    async fn deleted_sessions_are_not_current() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        let session = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        Session::delete(&pool, DEFAULT_USER_ID, session.id, now)
             .await
             .unwrap();
 
         assert_eq!(
-            Session::for_range_inclusive(&pool, now + Duration::days(1), now + Duration::days(1))
+            Session::current_session(&pool, DEFAULT_USER_ID)
                 .await
                 .unwrap(),
-            vec![]
-        )
+            None
+        );
+        assert_eq!(on_day(&pool, now).await, vec![]);
     }
 
     #[tokio::test]
-    async fn for_date_leaves_out_sessions_after_date() {
+    async fn restoring_brings_back_the_most_recently_deleted_session() {
         let pool = get_pool().await;
-        let now = Local::now();
-        let duration = Duration::minutes(5);
+        let now = Utc::now();
+
+        let session = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        Session::delete(&pool, DEFAULT_USER_ID, session.id, now)
+            .await
+            .unwrap();
 
-        Session::start(&pool, Kind::Task, "foo", now, duration)
+        let restored = Session::restore(&pool, DEFAULT_USER_ID, None)
             .await
             .unwrap();
 
+        assert_eq!(restored, session);
         assert_eq!(
-            Session::for_range_inclusive(&pool, now - Duration::days(1), now - Duration::days(1))
+            Session::current_session(&pool, DEFAULT_USER_ID)
                 .await
                 .unwrap(),
-            vec![]
-        )
+            Some(session)
+        );
     }
 
     #[tokio::test]
-    async fn for_date_includes_sessions_that_started_before_date_but_ended_on_date() {
+    async fn restoring_with_nothing_deleted_fails() {
         let pool = get_pool().await;
-        let now = Local::now();
-        let duration = Duration::days(1);
-        let end = now + duration;
 
-        let mut session = Session::start(&pool, Kind::Task, "foo", now, duration)
+        match Session::restore(&pool, DEFAULT_USER_ID, None).await {
+            Err(Error::NothingToRestore) => (),
+            other => panic!("expected NothingToRestore, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn purging_removes_old_deleted_sessions() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        let old = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "old",
+            now,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        let recent = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "recent",
+            now + Duration::minutes(25),
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        old.clone()
+            .tag(&pool, &[String::from("tags get purged too")])
             .await
             .unwrap();
-        session.end_time = Some(end);
 
-        Session::stop_all(&pool, end).await.unwrap();
+        Session::add_note(
+            &pool,
+            DEFAULT_USER_ID,
+            Some(old.id),
+            "notes get purged too",
+            now,
+        )
+        .await
+        .unwrap();
+
+        Session::delete(&pool, DEFAULT_USER_ID, old.id, now - Duration::days(60))
+            .await
+            .unwrap();
+        Session::delete(&pool, DEFAULT_USER_ID, recent.id, now)
+            .await
+            .unwrap();
 
+        let purged = Session::purge_deleted(&pool, now - Duration::days(30))
+            .await
+            .unwrap();
+
+        assert_eq!(purged, 1);
         assert_eq!(
-            Session::for_range_inclusive(&pool, end, end).await.unwrap(),
-            vec![session]
+            Session::restore(&pool, DEFAULT_USER_ID, None)
+                .await
+                .unwrap()
+                .id,
+            recent.id
+        );
+        match Session::restore(&pool, DEFAULT_USER_ID, Some(old.id)).await {
+            Err(Error::NoSuchSession(id)) => assert_eq!(id, old.id),
+            other => panic!("expected NoSuchSession, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn tagging_a_session_stores_the_tags() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        let mut session = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            Duration::minutes(25),
+            Tz::UTC,
         )
+        .await
+        .unwrap();
+        session
+            .tag(&pool, &[String::from("review"), String::from("ops")])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            session.tags,
+            vec![String::from("ops"), String::from("review")]
+        );
+        assert_eq!(
+            Session::current_session(&pool, DEFAULT_USER_ID)
+                .await
+                .unwrap(),
+            Some(session)
+        );
     }
 
-    #[test]
-    fn total_time_within_dates_totally_covered() {
-        let now = Local::now();
-        let duration = Duration::minutes(5);
+    #[tokio::test]
+    async fn notes_go_on_the_current_session_by_default() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        let session = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        let noted = Session::add_note(&pool, DEFAULT_USER_ID, None, "fixed the flaky test", now)
+            .await
+            .unwrap();
+
+        assert_eq!(noted.id, session.id);
+        assert_eq!(
+            noted.notes,
+            vec![Note {
+                session_id: session.id,
+                created_at: now,
+                text: String::from("fixed the flaky test"),
+            }]
+        );
+        assert_eq!(on_day(&pool, now).await, vec![noted]);
+    }
+
+    #[tokio::test]
+    async fn notes_can_go_on_past_sessions() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+        let duration = Duration::minutes(25);
 
-        let session = Session {
-            id: 0,
-            description: String::from(""),
-            kind: Kind::Task,
-            start_time: now,
-            end_time: Some(now + duration),
+        let first = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
             duration,
-        };
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "bar",
+            now + duration,
+            duration,
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        let noted = Session::add_note(
+            &pool,
+            DEFAULT_USER_ID,
+            Some(first.id),
+            "forgot this",
+            now + duration,
+        )
+        .await
+        .unwrap();
 
-        assert_eq!(
-            session.total_time_within_dates(now - Duration::days(1), now + Duration::days(1)),
+        assert_eq!(noted.id, first.id);
+        assert_eq!(noted.notes.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn notes_cannot_be_blank() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        match Session::add_note(&pool, DEFAULT_USER_ID, None, "  ", now).await {
+            Err(Error::NoteWasBlank) => (),
+            other => panic!("expected NoteWasBlank, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn for_date_gets_finished_session() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+        let duration = Duration::minutes(5);
+
+        let mut session = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
             duration,
+            Tz::UTC,
         )
+        .await
+        .unwrap();
+        session.end_time = Some(now + duration);
+
+        Session::stop(&pool, DEFAULT_USER_ID, now + duration)
+            .await
+            .unwrap();
+
+        assert_eq!(on_day(&pool, now).await, vec![session])
     }
 
-    #[test]
-    fn total_time_within_dates_session_started_before_start_time() {
-        let now = Local::now();
+    #[tokio::test]
+    async fn for_date_gets_current_session() {
+        let pool = get_pool().await;
+        let now = Utc::now();
         let duration = Duration::minutes(5);
 
-        let session = Session {
-            id: 0,
-            description: String::from(""),
-            kind: Kind::Task,
-            start_time: now - Duration::days(2),
-            end_time: Some(now + duration),
+        let session = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
             duration,
-        };
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
 
-        assert_eq!(
-            session.total_time_within_dates(now - Duration::days(1), now + Duration::days(1)),
-            duration + Duration::days(1),
+        assert_eq!(on_day(&pool, now).await, vec![session])
+    }
+
+    #[tokio::test]
+    async fn for_date_leaves_out_sessions_before_date() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+        let duration = Duration::minutes(5);
+
+        Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            duration,
+            Tz::UTC,
         )
+        .await
+        .unwrap();
+
+        assert_eq!(on_day(&pool, now + Duration::days(1)).await, vec![])
     }
 
-    #[test]
-    fn total_time_within_dates_session_ended_after_end_time() {
-        let now = Local::now();
+    #[tokio::test]
+    async fn for_date_leaves_out_sessions_after_date() {
+        let pool = get_pool().await;
+        let now = Utc::now();
         let duration = Duration::minutes(5);
 
-        let session = Session {
-            id: 0,
-            description: String::from(""),
-            kind: Kind::Task,
-            start_time: now,
-            end_time: Some(now + Duration::days(2)),
+        Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
             duration,
-        };
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(on_day(&pool, now - Duration::days(1)).await, vec![])
+    }
+
+    #[tokio::test]
+    async fn for_date_includes_sessions_that_started_before_date_but_ended_on_date() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+        let duration = Duration::days(1);
+        let end = now + duration;
+
+        let mut session = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            duration,
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        session.end_time = Some(end);
+
+        Session::stop(&pool, DEFAULT_USER_ID, end).await.unwrap();
+
+        assert_eq!(on_day(&pool, end).await, vec![session])
+    }
+
+    #[tokio::test]
+    async fn sessions_start_with_an_unknown_outcome() {
+        let pool = get_pool().await;
+
+        let session = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            Utc::now(),
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(session.outcome, Outcome::Unknown);
+    }
+
+    #[tokio::test]
+    async fn set_outcome_defaults_to_the_most_recent_session() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        let second = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "bar",
+            now + Duration::minutes(5),
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        Session::stop(&pool, DEFAULT_USER_ID, now + Duration::minutes(10))
+            .await
+            .unwrap();
+
+        let updated = Session::set_outcome(&pool, DEFAULT_USER_ID, None, Outcome::Completed)
+            .await
+            .unwrap();
 
+        assert_eq!(updated.id, second.id);
         assert_eq!(
-            session.total_time_within_dates(now - Duration::days(1), now + Duration::days(1)),
-            Duration::days(1),
+            Session::by_id(&pool, DEFAULT_USER_ID, second.id)
+                .await
+                .unwrap()
+                .unwrap()
+                .outcome,
+            Outcome::Completed
+        );
+    }
+
+    #[tokio::test]
+    async fn set_outcome_fails_without_any_sessions() {
+        let pool = get_pool().await;
+
+        match Session::set_outcome(&pool, DEFAULT_USER_ID, None, Outcome::Completed).await {
+            Err(Error::NoSessions) => (),
+            other => panic!("expected NoSessions, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn interruptions_go_on_the_current_session() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        let session = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            now,
+            Duration::minutes(25),
+            Tz::UTC,
         )
+        .await
+        .unwrap();
+
+        let interrupted =
+            Session::log_interruption(&pool, DEFAULT_USER_ID, " slack ping ", Some(""), now)
+                .await
+                .unwrap();
+
+        assert_eq!(interrupted.id, session.id);
+        assert_eq!(
+            interrupted.interruptions,
+            vec![Interruption {
+                session_id: session.id,
+                created_at: now,
+                source: String::from("slack ping"),
+                note: None,
+            }]
+        );
+        assert_eq!(
+            Session::current_session(&pool, DEFAULT_USER_ID)
+                .await
+                .unwrap(),
+            Some(interrupted)
+        );
+    }
+
+    #[tokio::test]
+    async fn interruptions_need_a_current_session() {
+        let pool = get_pool().await;
+
+        match Session::log_interruption(&pool, DEFAULT_USER_ID, "slack ping", None, Utc::now())
+            .await
+        {
+            Err(Error::NoCurrentSession) => (),
+            other => panic!("expected NoCurrentSession, got {other:?}"),
+        }
     }
 }
@@ -0,0 +1,160 @@
+use super::error::{Error, Result, SettingsError};
+use async_graphql::SimpleObject;
+use chrono::Duration;
+use indoc::indoc;
+use sqlx::{sqlite::SqliteRow, FromRow, Pool, Row, Sqlite};
+
+/// Server-wide settings. Defaults for each kind of session live on the kind instead.
+#[derive(SimpleObject, Debug, PartialEq, Eq, Clone)]
+pub struct Settings {
+    /// Breaks longer than this are long breaks, which don't count as working time
+    pub short_break_cutoff: Duration,
+
+    /// How many task sessions make a set. Once a set is done, we suggest a long break.
+    pub set_size: i64,
+
+    /// How long breaks started without a duration last once a set is done
+    pub long_break_duration: Duration,
+}
+
+/// Changes to make to the settings. Fields left as `None` are not changed.
+#[derive(Debug, Default)]
+pub struct SettingsChanges {
+    pub short_break_cutoff: Option<Duration>,
+    pub set_size: Option<i64>,
+    pub long_break_duration: Option<Duration>,
+}
+
+impl FromRow<'_, SqliteRow> for Settings {
+    fn from_row(row: &SqliteRow) -> std::result::Result<Self, sqlx::Error> {
+        Ok(Self {
+            short_break_cutoff: Duration::seconds(row.try_get("short_break_cutoff")?),
+            set_size: row.try_get("set_size")?,
+            long_break_duration: Duration::seconds(row.try_get("long_break_duration")?),
+        })
+    }
+}
+
+impl Settings {
+    pub async fn load(pool: &Pool<Sqlite>) -> Result<Self> {
+        sqlx::query_as::<_, Self>("SELECT * FROM settings WHERE id = 1")
+            .fetch_one(pool)
+            .await
+            .map_err(Error::Query)
+    }
+
+    pub async fn update(pool: &Pool<Sqlite>, changes: SettingsChanges) -> Result<Self> {
+        let mut settings = Self::load(pool).await?;
+
+        if let Some(short_break_cutoff) = changes.short_break_cutoff {
+            if short_break_cutoff < Duration::zero() {
+                return Err(Error::SettingsError(
+                    SettingsError::ShortBreakCutoffWasNegative,
+                ));
+            }
+
+            settings.short_break_cutoff = short_break_cutoff;
+        }
+
+        if let Some(set_size) = changes.set_size {
+            if set_size < 1 {
+                return Err(Error::SettingsError(SettingsError::SetSizeWasNotPositive));
+            }
+
+            settings.set_size = set_size;
+        }
+
+        if let Some(long_break_duration) = changes.long_break_duration {
+            if long_break_duration <= Duration::zero() {
+                return Err(Error::SettingsError(
+                    SettingsError::LongBreakDurationWasNotPositive,
+                ));
+            }
+
+            settings.long_break_duration = long_break_duration;
+        }
+
+        let settings = sqlx::query_as::<_, Self>(indoc! {"
+            UPDATE settings
+            SET short_break_cutoff = ?, set_size = ?, long_break_duration = ?
+            WHERE id = 1
+            RETURNING *
+        "})
+        .bind(settings.short_break_cutoff.num_seconds())
+        .bind(settings.set_size)
+        .bind(settings.long_break_duration.num_seconds())
+        .fetch_one(pool)
+        .await
+        .map_err(Error::Query)?;
+
+        tracing::info!(?settings, "updated settings");
+
+        Ok(settings)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn get_pool() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new().connect(":memory:").await.unwrap();
+
+        sqlx::migrate!("db/migrations").run(&pool).await.unwrap();
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn starts_with_pomodoro_defaults() {
+        let pool = get_pool().await;
+
+        assert_eq!(
+            Settings::load(&pool).await.unwrap(),
+            Settings {
+                short_break_cutoff: Duration::minutes(15),
+                set_size: 4,
+                long_break_duration: Duration::minutes(30),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn update_saves_changes() {
+        let pool = get_pool().await;
+
+        Settings::update(
+            &pool,
+            SettingsChanges {
+                short_break_cutoff: Some(Duration::minutes(20)),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            Settings::load(&pool).await.unwrap().short_break_cutoff,
+            Duration::minutes(20)
+        );
+    }
+
+    #[tokio::test]
+    async fn update_rejects_negative_cutoffs() {
+        let pool = get_pool().await;
+
+        match Settings::update(
+            &pool,
+            SettingsChanges {
+                short_break_cutoff: Some(Duration::minutes(-1)),
+                ..Default::default()
+            },
+        )
+        .await
+        {
+            Err(Error::SettingsError(SettingsError::ShortBreakCutoffWasNegative)) => (),
+            other => panic!("expected ShortBreakCutoffWasNegative, got {other:?}"),
+        }
+    }
+}
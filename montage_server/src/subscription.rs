@@ -1,23 +1,60 @@
+use super::channels::Channels;
+use super::error::{Error, Result};
+use super::event::{self, Event};
 use super::session::Session;
-use async_graphql::Subscription;
+use super::user::User;
+use async_graphql::{Context, Subscription};
 use futures::stream::Stream;
-use tokio::sync::watch::Receiver;
+use sqlx::{Pool, Sqlite};
 use tokio_stream::wrappers::WatchStream;
 
 pub struct Subscription {
-    receiver: Receiver<Option<Session>>,
+    channels: Channels,
 }
 
 impl Subscription {
-    pub fn new(receiver: Receiver<Option<Session>>) -> Self {
-        Self { receiver }
+    pub fn new(channels: Channels) -> Self {
+        Self { channels }
     }
 }
 
 #[Subscription]
 impl Subscription {
     /// Get the current session and any future sessions while the connection is open.
-    async fn current_session(&self) -> impl Stream<Item = Option<Session>> {
-        WatchStream::new(self.receiver.clone())
+    async fn current_session(
+        &self,
+        context: &Context<'_>,
+    ) -> Result<impl Stream<Item = Option<Session>>> {
+        let receiver = self
+            .channels
+            .subscribe(User::from_context(context)?.id)
+            .await?;
+
+        Ok(WatchStream::new(receiver))
+    }
+
+    /// Get told when sessions start, end, get extended, or run over time, and when reminders are
+    /// due. This only covers what happens after subscribing, so query `currentSession` to find
+    /// out where things stand.
+    async fn events(
+        &self,
+        context: &Context<'_>,
+        #[graphql(
+            desc = "Send a `ReminderDue` event this long before the current session's projected end time. Can be given more than once."
+        )]
+        reminder_at: Option<Vec<chrono::Duration>>,
+    ) -> Result<impl Stream<Item = Event>> {
+        let pool: &Pool<Sqlite> = context.data().map_err(Error::Context)?;
+
+        let receiver = self
+            .channels
+            .subscribe(User::from_context(context)?.id)
+            .await?;
+
+        Ok(event::stream(
+            receiver,
+            pool.clone(),
+            reminder_at.unwrap_or_default(),
+        ))
     }
 }
@@ -0,0 +1,97 @@
+use super::error::{Error, Result};
+use indoc::indoc;
+use sqlx::{Pool, Sqlite, SqliteConnection};
+
+/// Tags group sessions with different descriptions together for reporting (for example, "Review
+/// PR 1234" and "Review PR 1240" could both be tagged "code review".)
+pub struct Tag;
+
+impl Tag {
+    /// Clean up user-provided tags: trim whitespace, drop blanks, and remove duplicates.
+    pub fn normalize(tags: &[String]) -> Vec<String> {
+        let mut out: Vec<String> = Vec::with_capacity(tags.len());
+
+        for tag in tags {
+            let trimmed = tag.trim();
+
+            if !trimmed.is_empty() && !out.iter().any(|existing| existing == trimmed) {
+                out.push(trimmed.to_string());
+            }
+        }
+
+        out
+    }
+
+    pub async fn attach(
+        conn: &mut SqliteConnection,
+        session_id: i64,
+        tags: &[String],
+    ) -> Result<()> {
+        for tag in tags {
+            let (tag_id,): (i64,) = sqlx::query_as(indoc! {"
+                INSERT INTO tags (name) VALUES (?)
+                ON CONFLICT (name) DO UPDATE SET name = excluded.name
+                RETURNING id
+            "})
+            .bind(tag)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(Error::Query)?;
+
+            sqlx::query("INSERT OR IGNORE INTO session_tags (session_id, tag_id) VALUES (?, ?)")
+                .bind(session_id)
+                .bind(tag_id)
+                .execute(&mut *conn)
+                .await
+                .map_err(Error::Query)?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the tags for a set of sessions as `(session_id, tag)` pairs.
+    pub async fn for_sessions(
+        pool: &Pool<Sqlite>,
+        session_ids: &[i64],
+    ) -> Result<Vec<(i64, String)>> {
+        if session_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let ids = session_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+
+        sqlx::query_as(indoc! {"
+            SELECT session_tags.session_id, tags.name
+            FROM session_tags
+            JOIN tags ON tags.id = session_tags.tag_id
+            WHERE session_tags.session_id IN (SELECT value FROM json_each(?))
+            ORDER BY tags.name ASC
+        "})
+        .bind(format!("[{ids}]"))
+        .fetch_all(pool)
+        .await
+        .map_err(Error::Query)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn normalize_trims_and_deduplicates() {
+        assert_eq!(
+            Tag::normalize(&[
+                String::from(" code review "),
+                String::from(""),
+                String::from("code review"),
+                String::from("ops"),
+            ]),
+            vec![String::from("code review"), String::from("ops")],
+        )
+    }
+}
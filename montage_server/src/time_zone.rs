@@ -0,0 +1,67 @@
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// The IANA time zone the server is running in. If we can't figure that out, we use UTC.
+pub fn local() -> Tz {
+    iana_time_zone::get_timezone()
+        .ok()
+        .and_then(|name| name.parse().ok())
+        .unwrap_or(Tz::UTC)
+}
+
+/// The first moment of a day in the given time zone. Usually this is midnight, but some zones
+/// skip midnight when switching to daylight saving time. In that case, the day starts at the
+/// first local time that actually exists.
+pub fn start_of_day(date: NaiveDate, time_zone: Tz) -> DateTime<Utc> {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+
+    (0..24 * 60)
+        .find_map(|minutes| {
+            time_zone
+                .from_local_datetime(&(midnight + Duration::minutes(minutes)))
+                .earliest()
+        })
+        .unwrap_or_else(|| time_zone.from_utc_datetime(&midnight))
+        .with_timezone(&Utc)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn start_of_day_is_usually_midnight() {
+        assert_eq!(
+            start_of_day(
+                NaiveDate::from_ymd_opt(2023, 7, 1).unwrap(),
+                Tz::America__Chicago
+            ),
+            Utc.with_ymd_and_hms(2023, 7, 1, 5, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn start_of_day_skips_missing_midnights() {
+        // São Paulo moved its clocks from midnight to 1am on this day
+        assert_eq!(
+            start_of_day(
+                NaiveDate::from_ymd_opt(2018, 11, 4).unwrap(),
+                Tz::America__Sao_Paulo
+            ),
+            Utc.with_ymd_and_hms(2018, 11, 4, 3, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn start_of_day_uses_the_first_of_repeated_midnights() {
+        // Havana moved its clocks from 1am back to midnight on this day, so midnight happened
+        // twice
+        assert_eq!(
+            start_of_day(
+                NaiveDate::from_ymd_opt(2023, 11, 5).unwrap(),
+                Tz::America__Havana
+            ),
+            Utc.with_ymd_and_hms(2023, 11, 5, 4, 0, 0).unwrap()
+        );
+    }
+}
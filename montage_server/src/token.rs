@@ -0,0 +1,336 @@
+use super::error::{Error, Result, TokenError};
+use super::user::{User, DEFAULT_USER_ID};
+use chrono::{DateTime, Duration, Utc};
+use indoc::indoc;
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, Pool, Sqlite};
+
+/// How many random characters go in a token (after the prefix)
+static TOKEN_LENGTH: usize = 40;
+
+/// Makes tokens easy to recognize if they end up somewhere they shouldn't
+static TOKEN_PREFIX: &str = "montage_";
+
+/// How stale a token's `last_used_at` gets before a request updates it, in seconds. Without
+/// this, every request would write to the database.
+static LAST_USED_PRECISION: i64 = 60;
+
+/// A token for authenticating with the GraphQL API
+#[derive(FromRow, Debug, PartialEq, Eq, Clone)]
+pub struct ApiToken {
+    pub id: i64,
+
+    /// Who requests made with this token are from
+    pub user_id: i64,
+
+    /// What is this token for? (For example, "laptop" or "xbar")
+    pub name: String,
+
+    #[sqlx(rename = "token_hash")]
+    hash: String,
+
+    pub created_at: DateTime<Utc>,
+
+    /// When was this token last used to make a request?
+    pub last_used_at: Option<DateTime<Utc>>,
+
+    /// If the token was revoked, when? Revoked tokens can't be used any more.
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiToken {
+    pub async fn all(pool: &Pool<Sqlite>) -> Result<Vec<Self>> {
+        sqlx::query_as::<_, Self>("SELECT * FROM api_tokens ORDER BY id ASC")
+            .fetch_all(pool)
+            .await
+            .map_err(Error::Query)
+    }
+
+    /// Make a new token for a user. This is the only time the token itself is available, since we
+    /// only store a hash of it.
+    pub async fn create(
+        pool: &Pool<Sqlite>,
+        user_id: i64,
+        name: &str,
+        created_at: DateTime<Utc>,
+    ) -> Result<(Self, String)> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(Error::TokenError(TokenError::NameWasBlank));
+        }
+
+        let secret: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(TOKEN_LENGTH)
+            .map(char::from)
+            .collect();
+        let token = format!("{TOKEN_PREFIX}{secret}");
+
+        // `montage token` and `montage user` call this and then exit, so fetch every row to make
+        // sure the insert is committed before they do (see `User::create`.)
+        let api_token = sqlx::query_as::<_, Self>(indoc! {"
+            INSERT INTO api_tokens (user_id, name, token_hash, created_at)
+            VALUES (?, ?, ?, ?)
+            RETURNING *
+        "})
+        .bind(user_id)
+        .bind(name)
+        .bind(hash(&token))
+        .bind(created_at)
+        .fetch_all(pool)
+        .await
+        .map_err(Error::Query)?
+        .pop()
+        .ok_or(Error::Query(sqlx::Error::RowNotFound))?;
+
+        tracing::info!(
+            id = api_token.id,
+            user_id = api_token.user_id,
+            name = api_token.name,
+            "created API token"
+        );
+
+        Ok((api_token, token))
+    }
+
+    pub async fn revoke(pool: &Pool<Sqlite>, id: i64, revoked_at: DateTime<Utc>) -> Result<Self> {
+        let api_token = sqlx::query_as::<_, Self>(indoc! {"
+            UPDATE api_tokens
+            SET revoked_at = COALESCE(revoked_at, ?)
+            WHERE id = ?
+            RETURNING *
+        "})
+        .bind(revoked_at)
+        .bind(id)
+        .fetch_all(pool)
+        .await
+        .map_err(Error::Query)?
+        .pop()
+        .ok_or(Error::NoSuchToken(id))?;
+
+        tracing::info!(
+            id = api_token.id,
+            name = api_token.name,
+            "revoked API token"
+        );
+
+        Ok(api_token)
+    }
+
+    /// Do requests need a token? Only once one has been created, so a server that's only ever
+    /// used locally keeps working without any setup. Revoking tokens doesn't turn this back off.
+    pub async fn is_required(pool: &Pool<Sqlite>) -> Result<bool> {
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM api_tokens)")
+            .fetch_one(pool)
+            .await
+            .map_err(Error::Query)
+    }
+
+    /// Who is a request with this `Authorization` value (`Bearer TOKEN`) from? Until a token
+    /// exists, every request is from the default user. After that, requests without a valid token
+    /// aren't from anyone, and should be turned away.
+    pub async fn authorize(
+        pool: &Pool<Sqlite>,
+        authorization: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<Option<User>> {
+        if !Self::is_required(pool).await? {
+            return User::by_id(pool, DEFAULT_USER_ID).await;
+        }
+
+        let token = match authorization.and_then(|value| value.strip_prefix("Bearer ")) {
+            Some(token) => token.trim(),
+            None => return Ok(None),
+        };
+
+        let api_token = match sqlx::query_as::<_, Self>(
+            "SELECT * FROM api_tokens WHERE token_hash = ? AND revoked_at IS NULL",
+        )
+        .bind(hash(token))
+        .fetch_optional(pool)
+        .await
+        .map_err(Error::Query)?
+        {
+            Some(api_token) => api_token,
+            None => return Ok(None),
+        };
+
+        let stale = match api_token.last_used_at {
+            Some(last_used_at) => now - last_used_at >= Duration::seconds(LAST_USED_PRECISION),
+            None => true,
+        };
+
+        if stale {
+            sqlx::query("UPDATE api_tokens SET last_used_at = ? WHERE id = ?")
+                .bind(now)
+                .bind(api_token.id)
+                .execute(pool)
+                .await
+                .map_err(Error::Query)?;
+        }
+
+        User::by_id(pool, api_token.user_id).await
+    }
+}
+
+fn hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn get_pool() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new().connect(":memory:").await.unwrap();
+
+        sqlx::migrate!("db/migrations").run(&pool).await.unwrap();
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn anything_goes_until_a_token_exists() {
+        let pool = get_pool().await;
+
+        let user = ApiToken::authorize(&pool, None, Utc::now()).await.unwrap();
+
+        assert_eq!(user.map(|user| user.id), Some(DEFAULT_USER_ID));
+    }
+
+    #[tokio::test]
+    async fn requires_a_valid_token_once_one_exists() {
+        let pool = get_pool().await;
+        let (_, token) = ApiToken::create(&pool, DEFAULT_USER_ID, "laptop", Utc::now())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            ApiToken::authorize(&pool, None, Utc::now()).await.unwrap(),
+            None
+        );
+        assert_eq!(
+            ApiToken::authorize(&pool, Some("Bearer montage_nope"), Utc::now())
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            ApiToken::authorize(&pool, Some(&token), Utc::now())
+                .await
+                .unwrap(),
+            None
+        );
+        assert!(
+            ApiToken::authorize(&pool, Some(&format!("Bearer {token}")), Utc::now())
+                .await
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    async fn tokens_say_who_requests_are_from() {
+        let pool = get_pool().await;
+        let alice = User::create(&pool, "alice", Utc::now()).await.unwrap();
+        let (_, token) = ApiToken::create(&pool, alice.id, "laptop", Utc::now())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            ApiToken::authorize(&pool, Some(&format!("Bearer {token}")), Utc::now())
+                .await
+                .unwrap(),
+            Some(alice)
+        );
+    }
+
+    #[tokio::test]
+    async fn records_when_tokens_are_used() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+        let (created, token) = ApiToken::create(&pool, DEFAULT_USER_ID, "laptop", now)
+            .await
+            .unwrap();
+
+        assert_eq!(created.last_used_at, None);
+
+        ApiToken::authorize(&pool, Some(&format!("Bearer {token}")), now)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            ApiToken::all(&pool).await.unwrap()[0].last_used_at,
+            Some(now)
+        );
+
+        // within a minute, we don't bother writing it down again
+        ApiToken::authorize(
+            &pool,
+            Some(&format!("Bearer {token}")),
+            now + Duration::seconds(30),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            ApiToken::all(&pool).await.unwrap()[0].last_used_at,
+            Some(now)
+        );
+
+        ApiToken::authorize(
+            &pool,
+            Some(&format!("Bearer {token}")),
+            now + Duration::minutes(2),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            ApiToken::all(&pool).await.unwrap()[0].last_used_at,
+            Some(now + Duration::minutes(2))
+        );
+    }
+
+    #[tokio::test]
+    async fn revoked_tokens_stop_working() {
+        let pool = get_pool().await;
+        let (created, token) = ApiToken::create(&pool, DEFAULT_USER_ID, "laptop", Utc::now())
+            .await
+            .unwrap();
+
+        ApiToken::revoke(&pool, created.id, Utc::now())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            ApiToken::authorize(&pool, Some(&format!("Bearer {token}")), Utc::now())
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn does_not_store_the_token() {
+        let pool = get_pool().await;
+        let (created, token) = ApiToken::create(&pool, DEFAULT_USER_ID, "laptop", Utc::now())
+            .await
+            .unwrap();
+
+        assert!(token.starts_with(TOKEN_PREFIX));
+        assert_ne!(created.hash, token);
+    }
+
+    #[tokio::test]
+    async fn create_requires_a_name() {
+        let pool = get_pool().await;
+
+        assert!(matches!(
+            ApiToken::create(&pool, DEFAULT_USER_ID, " ", Utc::now()).await,
+            Err(Error::TokenError(TokenError::NameWasBlank))
+        ));
+    }
+}
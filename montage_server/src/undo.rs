@@ -0,0 +1,528 @@
+use super::audit::Change;
+use super::error::{Error, Result};
+use super::planned_session::PlannedSession;
+use super::session::Session;
+use async_graphql::SimpleObject;
+use chrono::{DateTime, Utc};
+use indoc::indoc;
+use serde_json::json;
+use sqlx::{Pool, Sqlite, SqliteConnection};
+
+/// The most recent change that hasn't been undone yet. Undos are changes too, but they're skipped
+/// over (as are the changes they undid) so undoing again keeps going further back.
+const LAST_CHANGE: &str = indoc! {"
+    SELECT COALESCE(change_id, id) AS change, mutation
+    FROM events
+    WHERE user_id = ?1
+      AND mutation != 'undo'
+      AND COALESCE(change_id, id) NOT IN (
+          SELECT json_extract(arguments, '$.changeId')
+          FROM events
+          WHERE user_id = ?1 AND mutation = 'undo'
+      )
+    ORDER BY id DESC
+    LIMIT 1
+"};
+
+/// What `undo` put back the way it was
+#[derive(SimpleObject, Debug, PartialEq, Eq)]
+pub struct Undone {
+    /// The mutation that was undone, like `start` or `extendBy`
+    pub mutation: String,
+
+    /// Sessions that were put back how they were before the change
+    pub restored: Vec<Session>,
+
+    /// Sessions the change created, which are now deleted
+    pub removed: Vec<Session>,
+}
+
+impl Undone {
+    /// Reverse the user's most recent change: remove any sessions it created and put the rest
+    /// back how they were before (including their pauses), and put a session started from the
+    /// queue back in it. Changes to sessions that have since been purged can't be undone.
+    pub async fn last_change(
+        pool: &Pool<Sqlite>,
+        user_id: i64,
+        undone_at: DateTime<Utc>,
+    ) -> Result<Self> {
+        let (change_id, mutation): (i64, String) = sqlx::query_as(LAST_CHANGE)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(Error::Query)?
+            .ok_or(Error::NothingToUndo)?;
+
+        // newest first, in case the change touched a session more than once
+        let events: Vec<(i64, Option<String>)> = sqlx::query_as(indoc! {"
+            SELECT session_id, before
+            FROM events
+            WHERE COALESCE(change_id, id) = ?
+            ORDER BY id DESC
+        "})
+        .bind(change_id)
+        .fetch_all(pool)
+        .await
+        .map_err(Error::Query)?;
+
+        // starting the next planned session took it out of the queue, so it goes back in
+        let planned_session: Option<PlannedSession> = sqlx::query_as(indoc! {"
+            SELECT
+                json_extract(arguments, '$.plannedSession.id') AS id,
+                0 AS position,
+                json_extract(arguments, '$.plannedSession.kind') AS kind,
+                json_extract(arguments, '$.plannedSession.description') AS description,
+                json_extract(arguments, '$.plannedSession.duration') AS duration
+            FROM events
+            WHERE id = ? AND mutation = 'startNext'
+        "})
+        .bind(change_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(Error::Query)?;
+
+        let mut removed = Vec::new();
+        for (session_id, before) in &events {
+            if before.is_none() {
+                if let Some(session) = Session::by_id(pool, user_id, *session_id).await? {
+                    removed.push(session);
+                }
+            }
+        }
+
+        let mut transaction = pool.begin().await.map_err(Error::Query)?;
+
+        // purged sessions are gone for good (pauses and all), so there's nothing to put back.
+        // Checking in the transaction means one can't be purged out from under us.
+        for (session_id, _) in &events {
+            let exists: bool =
+                sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM sessions WHERE id = ?)")
+                    .bind(session_id)
+                    .fetch_one(&mut *transaction)
+                    .await
+                    .map_err(Error::Query)?;
+
+            if !exists {
+                return Err(Error::SessionWasPurged(*session_id));
+            }
+        }
+
+        let mut change = Change::new(
+            user_id,
+            "undo",
+            json!({ "changeId": change_id, "mutation": mutation }),
+        );
+
+        for (session_id, before) in &events {
+            let current = Change::snapshot(&mut transaction, *session_id).await?;
+
+            match before {
+                None => {
+                    sqlx::query("UPDATE sessions SET deleted_at = ? WHERE id = ?")
+                        .bind(undone_at)
+                        .bind(session_id)
+                        .execute(&mut *transaction)
+                        .await
+                        .map_err(Error::Query)?;
+                }
+                Some(before) => {
+                    sqlx::query(indoc! {"
+                        UPDATE sessions
+                        SET kind = json_extract(?1, '$.kind'),
+                            description = json_extract(?1, '$.description'),
+                            start_time = json_extract(?1, '$.start_time'),
+                            duration = json_extract(?1, '$.duration'),
+                            end_time = json_extract(?1, '$.end_time'),
+                            time_zone = json_extract(?1, '$.time_zone'),
+                            outcome = json_extract(?1, '$.outcome'),
+                            deleted_at = json_extract(?1, '$.deleted_at')
+                        WHERE id = ?2
+                    "})
+                    .bind(before)
+                    .bind(session_id)
+                    .execute(&mut *transaction)
+                    .await
+                    .map_err(Error::Query)?;
+
+                    sqlx::query("DELETE FROM session_pauses WHERE session_id = ?")
+                        .bind(session_id)
+                        .execute(&mut *transaction)
+                        .await
+                        .map_err(Error::Query)?;
+
+                    sqlx::query(indoc! {"
+                        INSERT INTO session_pauses (session_id, start_time, end_time)
+                        SELECT
+                            ?1,
+                            json_extract(value, '$.start_time'),
+                            json_extract(value, '$.end_time')
+                        FROM json_each(?2, '$.pauses')
+                    "})
+                    .bind(session_id)
+                    .bind(before)
+                    .execute(&mut *transaction)
+                    .await
+                    .map_err(Error::Query)?;
+
+                    restore_related(&mut transaction, *session_id, before).await?;
+                }
+            }
+
+            change
+                .record(&mut transaction, *session_id, current)
+                .await?;
+        }
+
+        if let Some(planned_session) = &planned_session {
+            PlannedSession::requeue(&mut transaction, user_id, planned_session).await?;
+        }
+
+        transaction.commit().await.map_err(Error::Query)?;
+
+        let mut restored = Vec::new();
+        for (session_id, before) in &events {
+            if before.is_some()
+                && !restored
+                    .iter()
+                    .any(|session: &Session| session.id == *session_id)
+            {
+                if let Some(session) = Session::by_id(pool, user_id, *session_id).await? {
+                    restored.push(session);
+                }
+            }
+        }
+
+        tracing::info!(
+            change_id,
+            mutation,
+            restored = restored.len(),
+            removed = removed.len(),
+            "undid change"
+        );
+
+        Ok(Self {
+            mutation,
+            restored,
+            removed,
+        })
+    }
+}
+
+/// The tables a session's tags, notes, and interruptions live in, where they are in a snapshot,
+/// and how to put them back. Each query takes the session's ID and its snapshot.
+const RELATED: [(&str, &str, &str); 3] = [
+    (
+        "session_tags",
+        "$.tags",
+        indoc! {"
+            INSERT INTO session_tags (session_id, tag_id)
+            SELECT ?1, tags.id
+            FROM json_each(?2, '$.tags')
+            JOIN tags ON tags.name = json_each.value
+        "},
+    ),
+    (
+        "session_notes",
+        "$.notes",
+        indoc! {"
+            INSERT INTO session_notes (session_id, created_at, text)
+            SELECT ?1, json_extract(value, '$.created_at'), json_extract(value, '$.text')
+            FROM json_each(?2, '$.notes')
+        "},
+    ),
+    (
+        "interruptions",
+        "$.interruptions",
+        indoc! {"
+            INSERT INTO interruptions (session_id, created_at, source, note)
+            SELECT
+                ?1,
+                json_extract(value, '$.created_at'),
+                json_extract(value, '$.source'),
+                json_extract(value, '$.note')
+            FROM json_each(?2, '$.interruptions')
+        "},
+    ),
+];
+
+/// Put a session's tags, notes, and interruptions back the way they were in a snapshot. Snapshots
+/// from before we recorded these don't have them, so those leave them alone.
+async fn restore_related(conn: &mut SqliteConnection, session_id: i64, before: &str) -> Result<()> {
+    for (table, key, insert) in RELATED {
+        let recorded: bool = sqlx::query_scalar("SELECT json_type(?, ?) IS NOT NULL")
+            .bind(before)
+            .bind(key)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(Error::Query)?;
+
+        if !recorded {
+            continue;
+        }
+
+        sqlx::query(&format!("DELETE FROM {table} WHERE session_id = ?"))
+            .bind(session_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(Error::Query)?;
+
+        sqlx::query(insert)
+            .bind(session_id)
+            .bind(before)
+            .execute(&mut *conn)
+            .await
+            .map_err(Error::Query)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::user::{User, DEFAULT_USER_ID};
+    use chrono::Duration;
+    use chrono_tz::Tz;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn get_pool() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new().connect(":memory:").await.unwrap();
+
+        sqlx::migrate!("db/migrations").run(&pool).await.unwrap();
+
+        pool
+    }
+
+    async fn start(
+        pool: &Pool<Sqlite>,
+        user_id: i64,
+        description: &str,
+        start_time: DateTime<Utc>,
+    ) -> Session {
+        Session::start(
+            pool,
+            user_id,
+            "task",
+            description,
+            start_time,
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn undoing_a_start_removes_the_session_and_reopens_the_one_it_ended() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+
+        let previous = start(&pool, DEFAULT_USER_ID, "the real thing", now).await;
+        let typo = start(
+            &pool,
+            DEFAULT_USER_ID,
+            "teh real thing",
+            now + Duration::minutes(5),
+        )
+        .await;
+
+        let undone = Undone::last_change(&pool, DEFAULT_USER_ID, Utc::now())
+            .await
+            .unwrap();
+
+        assert_eq!(undone.mutation, "start");
+        assert_eq!(undone.removed, vec![typo.clone()]);
+        assert_eq!(undone.restored, vec![previous.clone()]);
+        assert_eq!(
+            Session::current_session(&pool, DEFAULT_USER_ID)
+                .await
+                .unwrap(),
+            Some(previous)
+        );
+        assert_eq!(
+            Session::by_id(&pool, DEFAULT_USER_ID, typo.id)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn undoing_an_extension_restores_the_duration() {
+        let pool = get_pool().await;
+        let session = start(&pool, DEFAULT_USER_ID, "thing", Utc::now()).await;
+
+        Session::extend_by(&pool, DEFAULT_USER_ID, Duration::minutes(10))
+            .await
+            .unwrap();
+
+        let undone = Undone::last_change(&pool, DEFAULT_USER_ID, Utc::now())
+            .await
+            .unwrap();
+
+        assert_eq!(undone.mutation, "extendBy");
+        assert_eq!(undone.restored, vec![session]);
+        assert_eq!(undone.removed, Vec::new());
+    }
+
+    #[tokio::test]
+    async fn undoing_a_resume_pauses_the_session_again() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+        start(&pool, DEFAULT_USER_ID, "thing", now).await;
+
+        let paused = Session::pause(&pool, DEFAULT_USER_ID, now + Duration::minutes(1))
+            .await
+            .unwrap();
+        Session::resume(&pool, DEFAULT_USER_ID, now + Duration::minutes(2))
+            .await
+            .unwrap();
+
+        let undone = Undone::last_change(&pool, DEFAULT_USER_ID, Utc::now())
+            .await
+            .unwrap();
+
+        assert_eq!(undone.restored, vec![paused]);
+        assert!(undone.restored[0].get_is_paused());
+    }
+
+    #[tokio::test]
+    async fn undoing_tags_notes_and_interruptions_takes_them_back_off() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+        let mut session = start(&pool, DEFAULT_USER_ID, "thing", now).await;
+
+        Session::add_note(&pool, DEFAULT_USER_ID, None, "a note", now)
+            .await
+            .unwrap();
+        Session::log_interruption(&pool, DEFAULT_USER_ID, "slack ping", None, now)
+            .await
+            .unwrap();
+        session.tag(&pool, &[String::from("ops")]).await.unwrap();
+
+        for mutation in ["tag", "logInterruption", "addNote"] {
+            let undone = Undone::last_change(&pool, DEFAULT_USER_ID, Utc::now())
+                .await
+                .unwrap();
+
+            assert_eq!(undone.mutation, mutation);
+        }
+
+        let restored = Session::by_id(&pool, DEFAULT_USER_ID, session.id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(restored.tags, Vec::<String>::new());
+        assert_eq!(restored.notes, Vec::new());
+        assert_eq!(restored.interruptions, Vec::new());
+    }
+
+    #[tokio::test]
+    async fn undoing_start_next_puts_the_planned_session_back() {
+        let pool = get_pool().await;
+
+        let planned = PlannedSession::enqueue(&pool, DEFAULT_USER_ID, "task", "a", None)
+            .await
+            .unwrap();
+        PlannedSession::enqueue(&pool, DEFAULT_USER_ID, "break", "b", None)
+            .await
+            .unwrap();
+
+        let started = PlannedSession::start_next(&pool, DEFAULT_USER_ID, Utc::now(), Tz::UTC)
+            .await
+            .unwrap();
+
+        let undone = Undone::last_change(&pool, DEFAULT_USER_ID, Utc::now())
+            .await
+            .unwrap();
+
+        assert_eq!(undone.mutation, "startNext");
+        assert_eq!(undone.removed, vec![started]);
+        assert_eq!(
+            PlannedSession::queue(&pool, DEFAULT_USER_ID)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|planned| (planned.id, planned.position, planned.description))
+                .collect::<Vec<_>>(),
+            vec![
+                (planned.id, 0, String::from("a")),
+                (planned.id + 1, 1, String::from("b"))
+            ]
+        );
+        assert_eq!(
+            PlannedSession::next(&pool, DEFAULT_USER_ID).await.unwrap(),
+            Some(planned)
+        );
+    }
+
+    #[tokio::test]
+    async fn undoing_again_goes_further_back() {
+        let pool = get_pool().await;
+        let session = start(&pool, DEFAULT_USER_ID, "thing", Utc::now()).await;
+
+        Session::extend_by(&pool, DEFAULT_USER_ID, Duration::minutes(5))
+            .await
+            .unwrap();
+        Session::extend_by(&pool, DEFAULT_USER_ID, Duration::minutes(5))
+            .await
+            .unwrap();
+
+        Undone::last_change(&pool, DEFAULT_USER_ID, Utc::now())
+            .await
+            .unwrap();
+        let undone = Undone::last_change(&pool, DEFAULT_USER_ID, Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(undone.restored, vec![session.clone()]);
+
+        let undone = Undone::last_change(&pool, DEFAULT_USER_ID, Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(undone.mutation, "start");
+        assert_eq!(undone.removed, vec![session]);
+
+        assert!(matches!(
+            Undone::last_change(&pool, DEFAULT_USER_ID, Utc::now()).await,
+            Err(Error::NothingToUndo)
+        ));
+    }
+
+    #[tokio::test]
+    async fn undoing_a_change_to_a_purged_session_fails() {
+        let pool = get_pool().await;
+        let now = Utc::now();
+        let session = start(&pool, DEFAULT_USER_ID, "foo", now - Duration::hours(1)).await;
+
+        Session::delete(&pool, DEFAULT_USER_ID, session.id, now)
+            .await
+            .unwrap();
+        Session::purge_deleted(&pool, now + Duration::minutes(1))
+            .await
+            .unwrap();
+
+        match Undone::last_change(&pool, DEFAULT_USER_ID, now).await {
+            Err(Error::SessionWasPurged(id)) if id == session.id => (),
+            other => panic!("expected SessionWasPurged, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn undo_only_touches_the_users_own_changes() {
+        let pool = get_pool().await;
+        let alice = User::create(&pool, "alice", Utc::now()).await.unwrap();
+        let mine = start(&pool, DEFAULT_USER_ID, "mine", Utc::now()).await;
+        start(&pool, alice.id, "alice's", Utc::now()).await;
+
+        let undone = Undone::last_change(&pool, DEFAULT_USER_ID, Utc::now())
+            .await
+            .unwrap();
+
+        assert_eq!(undone.removed, vec![mine]);
+        assert!(Session::current_session(&pool, alice.id)
+            .await
+            .unwrap()
+            .is_some());
+    }
+}
@@ -0,0 +1,163 @@
+use super::error::{Error, Result, UserError};
+use async_graphql::{Context, SimpleObject};
+use chrono::{DateTime, Utc};
+use indoc::indoc;
+use sqlx::{FromRow, Pool, Sqlite};
+
+/// The user who owns everything from before there were users. Requests act as them until the
+/// first API token is created.
+pub const DEFAULT_USER_ID: i64 = 1;
+
+/// Someone using the server. Sessions, the queue, goals, and webhooks belong to a user, and API
+/// tokens say which user a request is from.
+#[derive(SimpleObject, FromRow, Debug, PartialEq, Eq, Clone)]
+pub struct User {
+    pub id: i64,
+
+    /// What should we call this user?
+    pub name: String,
+
+    pub created_at: DateTime<Utc>,
+}
+
+impl User {
+    /// The user making the request, as decided by the server when it checked the request's token.
+    pub fn from_context<'ctx>(context: &'ctx Context<'_>) -> Result<&'ctx Self> {
+        context.data::<Self>().map_err(Error::Context)
+    }
+
+    /// Kinds and settings are shared by every user, so only the default user (who owned
+    /// everything before there were users) gets to change them.
+    pub fn check_can_change_shared(&self) -> Result<()> {
+        if self.id == DEFAULT_USER_ID {
+            Ok(())
+        } else {
+            Err(Error::OnlyDefaultUserCanChangeShared)
+        }
+    }
+
+    pub async fn all(pool: &Pool<Sqlite>) -> Result<Vec<Self>> {
+        sqlx::query_as::<_, Self>("SELECT * FROM users ORDER BY id ASC")
+            .fetch_all(pool)
+            .await
+            .map_err(Error::Query)
+    }
+
+    pub async fn create(
+        pool: &Pool<Sqlite>,
+        name: &str,
+        created_at: DateTime<Utc>,
+    ) -> Result<Self> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(Error::UserError(UserError::NameWasBlank));
+        }
+
+        // fetching every row (rather than just the first) finishes the statement, which commits
+        // it right away. `montage user add` exits soon after calling this, so it can't wait.
+        let user = sqlx::query_as::<_, Self>(indoc! {"
+            INSERT INTO users (name, created_at)
+            VALUES (?, ?)
+            ON CONFLICT (name) DO NOTHING
+            RETURNING *
+        "})
+        .bind(name)
+        .bind(created_at)
+        .fetch_all(pool)
+        .await
+        .map_err(Error::Query)?
+        .pop()
+        .ok_or_else(|| Error::UserError(UserError::AlreadyExists(name.to_string())))?;
+
+        tracing::info!(id = user.id, name = user.name, "created user");
+
+        Ok(user)
+    }
+
+    pub async fn by_id(pool: &Pool<Sqlite>, id: i64) -> Result<Option<Self>> {
+        sqlx::query_as::<_, Self>("SELECT * FROM users WHERE id = ?")
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+            .map_err(Error::Query)
+    }
+
+    pub async fn by_name(pool: &Pool<Sqlite>, name: &str) -> Result<Self> {
+        sqlx::query_as::<_, Self>("SELECT * FROM users WHERE name = ?")
+            .bind(name.trim())
+            .fetch_optional(pool)
+            .await
+            .map_err(Error::Query)?
+            .ok_or_else(|| Error::NoSuchUser(name.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn get_pool() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new().connect(":memory:").await.unwrap();
+
+        sqlx::migrate!("db/migrations").run(&pool).await.unwrap();
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn there_is_a_default_user() {
+        let pool = get_pool().await;
+
+        let users = User::all(&pool).await.unwrap();
+
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].id, DEFAULT_USER_ID);
+    }
+
+    #[tokio::test]
+    async fn create_adds_a_user() {
+        let pool = get_pool().await;
+
+        let created = User::create(&pool, " alice ", Utc::now()).await.unwrap();
+
+        assert_eq!(created.name, "alice");
+        assert_eq!(User::by_name(&pool, "alice").await.unwrap(), created);
+    }
+
+    #[tokio::test]
+    async fn create_requires_a_unique_name() {
+        let pool = get_pool().await;
+
+        User::create(&pool, "alice", Utc::now()).await.unwrap();
+
+        match User::create(&pool, "alice", Utc::now()).await {
+            Err(Error::UserError(UserError::AlreadyExists(name))) => assert_eq!(name, "alice"),
+            other => panic!("expected AlreadyExists, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn only_the_default_user_can_change_shared_things() {
+        let pool = get_pool().await;
+
+        let default = User::by_id(&pool, DEFAULT_USER_ID).await.unwrap().unwrap();
+        let alice = User::create(&pool, "alice", Utc::now()).await.unwrap();
+
+        assert!(default.check_can_change_shared().is_ok());
+        assert!(matches!(
+            alice.check_can_change_shared(),
+            Err(Error::OnlyDefaultUserCanChangeShared)
+        ));
+    }
+
+    #[tokio::test]
+    async fn create_requires_a_name() {
+        let pool = get_pool().await;
+
+        assert!(matches!(
+            User::create(&pool, " ", Utc::now()).await,
+            Err(Error::UserError(UserError::NameWasBlank))
+        ));
+    }
+}
@@ -0,0 +1,743 @@
+use super::error::{Error, Result, WebhookError};
+use super::event::{self, Event};
+use super::session::Session;
+use async_graphql::{ComplexObject, SimpleObject};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use hmac::{Hmac, Mac};
+use indoc::indoc;
+use sha2::Sha256;
+use sqlx::{FromRow, Pool, Sqlite};
+use tokio::sync::watch::Receiver;
+
+/// How many times we try to deliver an event before giving up
+static MAXIMUM_ATTEMPTS: i64 = 5;
+
+/// How long we wait after the first failed attempt. This doubles after every attempt after that.
+static INITIAL_BACKOFF: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// How long we wait for a receiver to respond before counting the attempt as failed
+static REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// An HTTP endpoint that gets a JSON payload when sessions start, get extended, end, or run over
+/// time
+#[derive(SimpleObject, FromRow, Debug, PartialEq, Eq, Clone)]
+pub struct Webhook {
+    /// The ID of this webhook
+    pub id: i64,
+
+    /// Where do we send payloads?
+    pub url: String,
+
+    /// The key we sign payloads with. Never sent back out once set.
+    #[graphql(skip)]
+    pub secret: String,
+
+    /// When was this webhook added?
+    pub created_at: DateTime<Utc>,
+}
+
+/// A single attempt to deliver an event to a webhook
+#[derive(SimpleObject, FromRow, Debug, PartialEq, Eq, Clone)]
+#[graphql(complex)]
+pub struct WebhookDelivery {
+    /// The ID of this delivery attempt
+    pub id: i64,
+
+    /// The webhook we were delivering to
+    pub webhook_id: i64,
+
+    /// Which event was this? (`SessionStarted`, `SessionExtended`, `SessionEnded`, or
+    /// `SessionOverTime`)
+    pub event: String,
+
+    /// The JSON we sent
+    pub payload: String,
+
+    /// Which attempt at delivering this event was this? (Starting at 1)
+    pub attempt: i64,
+
+    /// When did we make this attempt?
+    pub attempted_at: DateTime<Utc>,
+
+    /// The HTTP status the receiver responded with, if it responded at all
+    pub status_code: Option<i64>,
+
+    /// What went wrong, if the request didn't go through
+    pub error: Option<String>,
+}
+
+#[ComplexObject]
+impl WebhookDelivery {
+    /// Did the receiver accept the payload?
+    async fn succeeded(&self) -> bool {
+        self.get_succeeded()
+    }
+}
+
+impl WebhookDelivery {
+    pub fn get_succeeded(&self) -> bool {
+        matches!(self.status_code, Some(200..=299))
+    }
+
+    /// The most recent attempts to deliver to the user's webhooks, newest first, optionally for
+    /// just one webhook
+    pub async fn recent(
+        pool: &Pool<Sqlite>,
+        user_id: i64,
+        webhook_id: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<Self>> {
+        sqlx::query_as::<_, Self>(indoc! {"
+            SELECT *
+            FROM webhook_deliveries
+            WHERE webhook_id IN (SELECT id FROM webhooks WHERE user_id = ?3)
+              AND (?1 IS NULL OR webhook_id = ?1)
+            ORDER BY id DESC
+            LIMIT ?2
+        "})
+        .bind(webhook_id)
+        .bind(limit)
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map_err(Error::Query)
+    }
+
+    /// Permanently remove delivery attempts made before the cutoff, so the log doesn't grow
+    /// forever.
+    pub async fn prune(pool: &Pool<Sqlite>, cutoff: DateTime<Utc>) -> Result<u64> {
+        let receipt = sqlx::query("DELETE FROM webhook_deliveries WHERE attempted_at < ?")
+            .bind(cutoff)
+            .execute(pool)
+            .await
+            .map_err(Error::Query)?;
+
+        tracing::info!(count = receipt.rows_affected(), "pruned webhook deliveries");
+
+        Ok(receipt.rows_affected())
+    }
+}
+
+impl Webhook {
+    pub async fn all(pool: &Pool<Sqlite>, user_id: i64) -> Result<Vec<Self>> {
+        sqlx::query_as::<_, Self>("SELECT * FROM webhooks WHERE user_id = ? ORDER BY id ASC")
+            .bind(user_id)
+            .fetch_all(pool)
+            .await
+            .map_err(Error::Query)
+    }
+
+    pub async fn create(
+        pool: &Pool<Sqlite>,
+        user_id: i64,
+        url: &str,
+        secret: &str,
+        created_at: DateTime<Utc>,
+    ) -> Result<Self> {
+        let url = url.trim();
+
+        match reqwest::Url::parse(url) {
+            Ok(parsed) if parsed.scheme() == "http" || parsed.scheme() == "https" => (),
+            _ => return Err(Error::WebhookError(WebhookError::UrlWasNotHttp)),
+        }
+
+        if secret.trim().is_empty() {
+            return Err(Error::WebhookError(WebhookError::SecretWasBlank));
+        }
+
+        let webhook = sqlx::query_as::<_, Self>(indoc! {"
+            INSERT INTO webhooks (user_id, url, secret, created_at)
+            VALUES (?, ?, ?, ?)
+            RETURNING *
+        "})
+        .bind(user_id)
+        .bind(url)
+        .bind(secret)
+        .bind(created_at)
+        .fetch_one(pool)
+        .await
+        .map_err(Error::Query)?;
+
+        tracing::info!(id = webhook.id, url = webhook.url, "created webhook");
+
+        Ok(webhook)
+    }
+
+    /// Remove a webhook, along with its delivery log
+    pub async fn delete(pool: &Pool<Sqlite>, user_id: i64, id: i64) -> Result<Self> {
+        let mut transaction = pool.begin().await.map_err(Error::Query)?;
+
+        sqlx::query(indoc! {"
+            DELETE FROM webhook_deliveries
+            WHERE webhook_id IN (SELECT id FROM webhooks WHERE id = ? AND user_id = ?)
+        "})
+        .bind(id)
+        .bind(user_id)
+        .execute(&mut *transaction)
+        .await
+        .map_err(Error::Query)?;
+
+        let webhook = sqlx::query_as::<_, Self>(
+            "DELETE FROM webhooks WHERE id = ? AND user_id = ? RETURNING *",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&mut *transaction)
+        .await
+        .map_err(Error::Query)?
+        .ok_or(Error::NoSuchWebhook(id))?;
+
+        transaction.commit().await.map_err(Error::Query)?;
+
+        tracing::info!(id = webhook.id, "deleted webhook");
+
+        Ok(webhook)
+    }
+
+    async fn exists(pool: &Pool<Sqlite>, id: i64) -> Result<bool> {
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM webhooks WHERE id = ?)")
+            .bind(id)
+            .fetch_one(pool)
+            .await
+            .map_err(Error::Query)
+    }
+
+    /// The value of the `X-Montage-Signature` header: a hex-encoded HMAC-SHA256 of the body,
+    /// keyed with the webhook's secret.
+    pub fn sign(&self, body: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.secret.as_bytes())
+            .expect("HMAC can take keys of any size");
+        mac.update(body.as_bytes());
+
+        format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+    }
+
+    /// Try to deliver an event once, recording how it went in the delivery log.
+    async fn attempt(
+        &self,
+        pool: &Pool<Sqlite>,
+        client: &reqwest::Client,
+        event: &str,
+        payload: &str,
+        attempt: i64,
+    ) -> Result<WebhookDelivery> {
+        let attempted_at = Utc::now();
+
+        let response = client
+            .post(&self.url)
+            .header("Content-Type", "application/json")
+            .header("X-Montage-Event", event)
+            .header("X-Montage-Signature", self.sign(payload))
+            .body(payload.to_string())
+            .send()
+            .await;
+
+        let (status_code, error) = match response {
+            Ok(response) => (Some(i64::from(response.status().as_u16())), None),
+            Err(err) => (None, Some(err.to_string())),
+        };
+
+        let delivery = sqlx::query_as::<_, WebhookDelivery>(indoc! {"
+            INSERT INTO webhook_deliveries (
+                webhook_id, event, payload, attempt, attempted_at, status_code, error
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            RETURNING *
+        "})
+        .bind(self.id)
+        .bind(event)
+        .bind(payload)
+        .bind(attempt)
+        .bind(attempted_at)
+        .bind(status_code)
+        .bind(delivery_error(status_code, error))
+        .fetch_one(pool)
+        .await
+        .map_err(Error::Query)?;
+
+        Ok(delivery)
+    }
+
+    /// Deliver an event, retrying with exponential backoff until the receiver accepts it or we
+    /// run out of attempts. Returns whether it was eventually accepted.
+    pub async fn deliver(
+        &self,
+        pool: &Pool<Sqlite>,
+        client: &reqwest::Client,
+        event: &str,
+        payload: &str,
+        initial_backoff: std::time::Duration,
+    ) -> Result<bool> {
+        let mut backoff = initial_backoff;
+
+        for attempt in 1..=MAXIMUM_ATTEMPTS {
+            let delivery = self.attempt(pool, client, event, payload, attempt).await?;
+
+            if delivery.get_succeeded() {
+                tracing::info!(id = self.id, event, attempt, "delivered webhook");
+                return Ok(true);
+            }
+
+            tracing::warn!(
+                id = self.id,
+                event,
+                attempt,
+                status_code = delivery.status_code,
+                error = delivery.error,
+                "could not deliver webhook"
+            );
+
+            if attempt < MAXIMUM_ATTEMPTS {
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+
+                // the webhook may have been deleted while we were waiting to try again
+                if !Self::exists(pool, self.id).await? {
+                    tracing::info!(id = self.id, event, "webhook was deleted; not retrying");
+                    return Ok(false);
+                }
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+/// If the receiver responded, but not happily, we don't have a request error to show. Say
+/// something anyway so the log is easy to read.
+fn delivery_error(status_code: Option<i64>, error: Option<String>) -> Option<String> {
+    match (status_code, error) {
+        (_, Some(error)) => Some(error),
+        (Some(200..=299), None) => None,
+        (Some(status_code), None) => Some(format!("receiver responded with {status_code}")),
+        (None, None) => None,
+    }
+}
+
+/// The name and JSON body to send for an event. Reminders are only for subscribers who ask for
+/// them, so they don't get sent anywhere.
+pub fn payload(event: &Event, sent_at: DateTime<Utc>) -> Option<(&'static str, String)> {
+    let (name, session, previous_duration) = match event {
+        Event::SessionStarted(started) => ("SessionStarted", &started.session, None),
+        Event::SessionExtended(extended) => (
+            "SessionExtended",
+            &extended.session,
+            Some(extended.previous_duration),
+        ),
+        Event::SessionEnded(ended) => ("SessionEnded", &ended.session, None),
+        Event::SessionOverTime(over_time) => ("SessionOverTime", &over_time.session, None),
+        Event::ReminderDue(_) => return None,
+    };
+
+    let mut body = serde_json::json!({
+        "event": name,
+        "sentAt": sent_at.to_rfc3339(),
+        "session": session_json(session),
+    });
+
+    if let Some(previous_duration) = previous_duration {
+        body["previousDuration"] = serde_json::Value::String(previous_duration.to_string());
+    }
+
+    Some((name, body.to_string()))
+}
+
+/// Sessions in payloads look like they do in GraphQL, as far as the fields we include go.
+fn session_json(session: &Session) -> serde_json::Value {
+    serde_json::json!({
+        "id": session.id,
+        "kind": session.kind,
+        "description": session.description,
+        "startTime": session.start_time.to_rfc3339(),
+        "duration": session.duration.to_string(),
+        "projectedEndTime": session.get_projected_end_time().to_rfc3339(),
+        "endTime": session.end_time.map(|end_time| end_time.to_rfc3339()),
+        "tags": session.tags,
+    })
+}
+
+/// Deliver the user's session lifecycle events to each of their webhooks as they happen. Each
+/// delivery retries on its own, so a slow receiver doesn't hold up the others.
+pub async fn run_dispatcher(pool: Pool<Sqlite>, user_id: i64, receiver: Receiver<Option<Session>>) {
+    let client = match reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(err) => {
+            tracing::error!(err = ?err, "could not build an HTTP client for webhooks");
+            return;
+        }
+    };
+
+    let mut events = Box::pin(event::stream(receiver, pool.clone(), Vec::new()));
+
+    while let Some(event) = events.next().await {
+        let (name, body) = match payload(&event, Utc::now()) {
+            Some(payload) => payload,
+            None => continue,
+        };
+
+        let webhooks = match Webhook::all(&pool, user_id).await {
+            Ok(webhooks) => webhooks,
+            Err(err) => {
+                tracing::error!(err = ?err, "could not load webhooks");
+                continue;
+            }
+        };
+
+        for webhook in webhooks {
+            let pool = pool.clone();
+            let client = client.clone();
+            let body = body.clone();
+
+            tokio::spawn(async move {
+                if let Err(err) = webhook
+                    .deliver(&pool, &client, name, &body, INITIAL_BACKOFF)
+                    .await
+                {
+                    tracing::error!(id = webhook.id, err = ?err, "could not deliver webhook");
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::user::DEFAULT_USER_ID;
+    use chrono::Duration;
+    use chrono_tz::Tz;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::watch;
+    use warp::Filter;
+
+    async fn get_pool() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new().connect(":memory:").await.unwrap();
+
+        sqlx::migrate!("db/migrations").run(&pool).await.unwrap();
+
+        pool
+    }
+
+    type Received = Arc<Mutex<Vec<(String, String)>>>;
+
+    /// Listen locally, answering with the given statuses in order (and 200 after they run out),
+    /// and keeping track of the signature and body of everything we get.
+    fn receiver(statuses: Vec<u16>) -> (String, Received) {
+        let received: Received = Arc::new(Mutex::new(Vec::new()));
+        let statuses = Arc::new(Mutex::new(statuses.into_iter()));
+
+        let route = warp::post()
+            .and(warp::header::<String>("X-Montage-Signature"))
+            .and(warp::body::bytes())
+            .map({
+                let received = received.clone();
+                move |signature: String, body: warp::hyper::body::Bytes| {
+                    received
+                        .lock()
+                        .unwrap()
+                        .push((signature, String::from_utf8(body.to_vec()).unwrap()));
+
+                    let status = statuses.lock().unwrap().next().unwrap_or(200);
+                    warp::reply::with_status("", warp::http::StatusCode::from_u16(status).unwrap())
+                }
+            });
+
+        let (addr, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        (format!("http://{addr}/hook"), received)
+    }
+
+    async fn started_event(pool: &Pool<Sqlite>) -> Event {
+        let session = Session::start(
+            pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            Utc::now(),
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        Event::SessionStarted(event::SessionStarted { session })
+    }
+
+    #[tokio::test]
+    async fn create_requires_an_http_url() {
+        let pool = get_pool().await;
+
+        assert!(matches!(
+            Webhook::create(
+                &pool,
+                DEFAULT_USER_ID,
+                "ftp://example.com",
+                "secret",
+                Utc::now()
+            )
+            .await,
+            Err(Error::WebhookError(WebhookError::UrlWasNotHttp))
+        ));
+    }
+
+    #[tokio::test]
+    async fn create_requires_a_secret() {
+        let pool = get_pool().await;
+
+        assert!(matches!(
+            Webhook::create(
+                &pool,
+                DEFAULT_USER_ID,
+                "https://example.com",
+                " ",
+                Utc::now()
+            )
+            .await,
+            Err(Error::WebhookError(WebhookError::SecretWasBlank))
+        ));
+    }
+
+    #[tokio::test]
+    async fn payload_describes_the_session() {
+        let pool = get_pool().await;
+        let event = started_event(&pool).await;
+
+        let (name, body) = payload(&event, Utc::now()).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+
+        assert_eq!(name, "SessionStarted");
+        assert_eq!(body["event"], "SessionStarted");
+        assert_eq!(body["session"]["description"], "foo");
+        assert_eq!(body["session"]["kind"], "task");
+    }
+
+    #[tokio::test]
+    async fn delivers_signed_payloads() {
+        let pool = get_pool().await;
+        let (url, received) = receiver(Vec::new());
+        let webhook = Webhook::create(&pool, DEFAULT_USER_ID, &url, "secret", Utc::now())
+            .await
+            .unwrap();
+
+        let (name, body) = payload(&started_event(&pool).await, Utc::now()).unwrap();
+
+        let delivered = webhook
+            .deliver(
+                &pool,
+                &reqwest::Client::new(),
+                name,
+                &body,
+                std::time::Duration::ZERO,
+            )
+            .await
+            .unwrap();
+
+        assert!(delivered);
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![(webhook.sign(&body), body.clone())]
+        );
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"secret").unwrap();
+        mac.update(body.as_bytes());
+        assert_eq!(
+            webhook.sign(&body),
+            format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_and_logs_failed_deliveries() {
+        let pool = get_pool().await;
+        let (url, received) = receiver(vec![500, 503]);
+        let webhook = Webhook::create(&pool, DEFAULT_USER_ID, &url, "secret", Utc::now())
+            .await
+            .unwrap();
+
+        let delivered = webhook
+            .deliver(
+                &pool,
+                &reqwest::Client::new(),
+                "SessionStarted",
+                "{}",
+                std::time::Duration::ZERO,
+            )
+            .await
+            .unwrap();
+
+        assert!(delivered);
+        assert_eq!(received.lock().unwrap().len(), 3);
+
+        let log = WebhookDelivery::recent(&pool, DEFAULT_USER_ID, Some(webhook.id), 10)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            log.iter()
+                .map(|delivery| (
+                    delivery.attempt,
+                    delivery.status_code,
+                    delivery.get_succeeded()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (3, Some(200), true),
+                (2, Some(503), false),
+                (1, Some(500), false)
+            ]
+        );
+        assert_eq!(
+            log[1].error,
+            Some(String::from("receiver responded with 503"))
+        );
+    }
+
+    #[tokio::test]
+    async fn gives_up_eventually() {
+        let pool = get_pool().await;
+        let (url, received) = receiver(vec![500; 10]);
+        let webhook = Webhook::create(&pool, DEFAULT_USER_ID, &url, "secret", Utc::now())
+            .await
+            .unwrap();
+
+        let delivered = webhook
+            .deliver(
+                &pool,
+                &reqwest::Client::new(),
+                "SessionStarted",
+                "{}",
+                std::time::Duration::ZERO,
+            )
+            .await
+            .unwrap();
+
+        assert!(!delivered);
+        assert_eq!(received.lock().unwrap().len(), MAXIMUM_ATTEMPTS as usize);
+    }
+
+    #[tokio::test]
+    async fn dispatcher_announces_over_time_once() {
+        let pool = get_pool().await;
+        let (url, received) = receiver(Vec::new());
+        Webhook::create(&pool, DEFAULT_USER_ID, &url, "secret", Utc::now())
+            .await
+            .unwrap();
+
+        let session = Session::start(
+            &pool,
+            DEFAULT_USER_ID,
+            "task",
+            "foo",
+            Utc::now() - Duration::minutes(30),
+            Duration::minutes(25),
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        let (sender, receiver) = watch::channel(Some(session.clone()));
+        tokio::spawn(run_dispatcher(pool.clone(), DEFAULT_USER_ID, receiver));
+
+        for _ in 0..100 {
+            if !received.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        // republishing the same session (say, after adding a note) isn't news
+        sender.send_replace(Some(session));
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+        let events = received
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, body)| {
+                serde_json::from_str::<serde_json::Value>(body).unwrap()["event"]
+                    .as_str()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(events, vec![String::from("SessionOverTime")]);
+    }
+
+    #[tokio::test]
+    async fn prune_removes_old_deliveries() {
+        let pool = get_pool().await;
+        let (url, _) = receiver(Vec::new());
+        let webhook = Webhook::create(&pool, DEFAULT_USER_ID, &url, "secret", Utc::now())
+            .await
+            .unwrap();
+
+        webhook
+            .deliver(
+                &pool,
+                &reqwest::Client::new(),
+                "SessionStarted",
+                "{}",
+                std::time::Duration::ZERO,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            WebhookDelivery::prune(&pool, Utc::now() - Duration::days(30))
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            WebhookDelivery::prune(&pool, Utc::now() + Duration::minutes(1))
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            WebhookDelivery::recent(&pool, DEFAULT_USER_ID, None, 10)
+                .await
+                .unwrap(),
+            Vec::new()
+        );
+    }
+
+    #[tokio::test]
+    async fn delete_removes_the_delivery_log() {
+        let pool = get_pool().await;
+        let (url, _) = receiver(Vec::new());
+        let webhook = Webhook::create(&pool, DEFAULT_USER_ID, &url, "secret", Utc::now())
+            .await
+            .unwrap();
+
+        webhook
+            .deliver(
+                &pool,
+                &reqwest::Client::new(),
+                "SessionStarted",
+                "{}",
+                std::time::Duration::ZERO,
+            )
+            .await
+            .unwrap();
+
+        Webhook::delete(&pool, DEFAULT_USER_ID, webhook.id)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            Webhook::all(&pool, DEFAULT_USER_ID).await.unwrap(),
+            Vec::new()
+        );
+        assert_eq!(
+            WebhookDelivery::recent(&pool, DEFAULT_USER_ID, None, 10)
+                .await
+                .unwrap(),
+            Vec::new()
+        );
+    }
+}